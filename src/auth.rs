@@ -0,0 +1,424 @@
+//! Applies Postman auth blocks to outgoing requests.
+
+use anyhow::{anyhow, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::collection::Auth;
+
+/// The auth types `apply` can send.
+const SUPPORTED_TYPES: &[&str] = &["noauth", "bearer", "basic", "digest", "apikey", "oauth2"];
+
+/// Whether `apply` can send auth of type `auth_type`. Other types, such as
+/// `hawk` or `awsv4`, are skipped.
+pub fn is_supported(auth_type: &str) -> bool {
+    SUPPORTED_TYPES.contains(&auth_type)
+}
+
+/// Headers and query parameters that authenticate a request.
+#[derive(Debug, Default)]
+pub struct AuthParts {
+    pub headers: Vec<(String, String)>,
+    pub query: Vec<(String, String)>,
+}
+
+/// Credentials used to answer a digest challenge.
+#[derive(Debug, Clone)]
+pub struct DigestCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+struct CachedToken {
+    access_token: String,
+    expires_at: Option<Instant>,
+}
+
+/// OAuth 2.0 access tokens fetched during a run, reused by every request
+/// with the same token URL, grant, client, user and scope until they expire.
+#[derive(Default)]
+pub struct TokenCache {
+    tokens: Mutex<HashMap<String, CachedToken>>,
+}
+
+impl TokenCache {
+    fn get(&self, key: &str) -> Option<String> {
+        let tokens = self.tokens.lock().ok()?;
+        let token = tokens.get(key)?;
+        match token.expires_at {
+            Some(expires_at) if expires_at <= Instant::now() => None,
+            _ => Some(token.access_token.clone()),
+        }
+    }
+
+    fn insert(&self, key: String, access_token: String, expires_in: Option<u64>) {
+        if let Ok(mut tokens) = self.tokens.lock() {
+            tokens.insert(
+                key,
+                CachedToken {
+                    access_token,
+                    expires_at: expires_in.map(|seconds| Instant::now() + Duration::from_secs(seconds)),
+                },
+            );
+        }
+    }
+}
+
+/// Resolves the headers and query parameters for an auth block; `resolve`
+/// substitutes variables in its parameters. Digest auth needs the server's
+/// challenge first and adds nothing here, see `digest_authorization`; nor do
+/// types that are not supported.
+pub async fn apply(
+    auth: &Auth,
+    resolve: &dyn Fn(&str) -> String,
+    client: &reqwest::Client,
+    tokens: &TokenCache,
+) -> Result<AuthParts> {
+    let param = |key: &str| auth.param(key).map(|value| resolve(&value)).unwrap_or_default();
+    let mut parts = AuthParts::default();
+
+    match auth.auth_type.as_str() {
+        "noauth" | "digest" => {}
+        "bearer" => {
+            parts
+                .headers
+                .push(("Authorization".to_string(), format!("Bearer {}", param("token"))));
+        }
+        "basic" => {
+            let credentials = STANDARD.encode(format!("{}:{}", param("username"), param("password")));
+            parts
+                .headers
+                .push(("Authorization".to_string(), format!("Basic {}", credentials)));
+        }
+        "apikey" => {
+            let pair = (param("key"), param("value"));
+            if param("in") == "query" {
+                parts.query.push(pair);
+            } else {
+                parts.headers.push(pair);
+            }
+        }
+        "oauth2" => {
+            let token = oauth2_token(auth, resolve, client, tokens).await?;
+            if param("addTokenTo") == "queryParams" {
+                parts.query.push(("access_token".to_string(), token));
+            } else {
+                let prefix = auth.param("headerPrefix").unwrap_or_else(|| "Bearer".to_string());
+                let value = if prefix.is_empty() { token } else { format!("{} {}", prefix, token) };
+                parts.headers.push(("Authorization".to_string(), value));
+            }
+        }
+        _ => {}
+    }
+
+    Ok(parts)
+}
+
+/// Uses the access token saved in the collection, or requests one with the
+/// client credentials or password grant.
+async fn oauth2_token(
+    auth: &Auth,
+    resolve: &dyn Fn(&str) -> String,
+    client: &reqwest::Client,
+    tokens: &TokenCache,
+) -> Result<String> {
+    let param = |key: &str| auth.param(key).map(|value| resolve(&value)).unwrap_or_default();
+
+    let saved_token = param("accessToken");
+    if !saved_token.is_empty() {
+        return Ok(saved_token);
+    }
+
+    let grant_type = param("grant_type");
+    if !matches!(grant_type.as_str(), "client_credentials" | "password") {
+        return Err(anyhow!(
+            "OAuth 2.0 grant type '{}' requires a browser; set an access token in the collection instead",
+            grant_type
+        ));
+    }
+
+    let token_url = param("accessTokenUrl");
+    if token_url.is_empty() {
+        return Err(anyhow!("OAuth 2.0 auth is missing the access token URL"));
+    }
+
+    let (client_id, client_secret, scope) = (param("clientId"), param("clientSecret"), param("scope"));
+    let (username, password) = (param("username"), param("password"));
+    let cache_key = [token_url.as_str(), &grant_type, &client_id, &username, &scope].join("|");
+    if let Some(token) = tokens.get(&cache_key) {
+        return Ok(token);
+    }
+
+    let mut form = vec![("grant_type", grant_type.clone())];
+    if !scope.is_empty() {
+        form.push(("scope", scope));
+    }
+    if grant_type == "password" {
+        form.push(("username", username));
+        form.push(("password", password));
+    }
+
+    let mut request = client.post(&token_url);
+    if param("client_authentication") == "body" {
+        form.push(("client_id", client_id));
+        form.push(("client_secret", client_secret));
+    } else {
+        request = request.basic_auth(client_id, Some(client_secret));
+    }
+
+    let response: serde_json::Value = request
+        .form(&form)
+        .send()
+        .await
+        .context("Failed to request OAuth 2.0 access token")?
+        .error_for_status()
+        .context("OAuth 2.0 token request was rejected")?
+        .json()
+        .await
+        .context("Failed to parse OAuth 2.0 token response")?;
+
+    let access_token = response
+        .get("access_token")
+        .and_then(|token| token.as_str())
+        .ok_or_else(|| anyhow!("OAuth 2.0 token response has no access_token"))?
+        .to_string();
+    let expires_in = response.get("expires_in").and_then(|expires| expires.as_u64());
+
+    tokens.insert(cache_key, access_token.clone(), expires_in);
+    Ok(access_token)
+}
+
+/// The credentials of a digest auth block.
+pub fn digest_credentials(auth: &Auth, resolve: &dyn Fn(&str) -> String) -> Option<DigestCredentials> {
+    if auth.auth_type != "digest" {
+        return None;
+    }
+
+    Some(DigestCredentials {
+        username: resolve(&auth.param("username").unwrap_or_default()),
+        password: resolve(&auth.param("password").unwrap_or_default()),
+    })
+}
+
+/// Answers a `WWW-Authenticate: Digest ...` challenge (RFC 7616) with the
+/// value of the `Authorization` header for the retried request. `uri` is the
+/// path and query of the request. Returns `None` for challenges that cannot
+/// be answered, such as unknown algorithms.
+pub fn digest_authorization(
+    challenge: &str,
+    credentials: &DigestCredentials,
+    method: &str,
+    uri: &str,
+) -> Option<String> {
+    let params = parse_challenge(challenge)?;
+    let realm = params.get("realm").cloned().unwrap_or_default();
+    let nonce = params.get("nonce")?;
+    let algorithm = params.get("algorithm").cloned().unwrap_or_else(|| "MD5".to_string());
+
+    let hash: fn(&str) -> String = match algorithm.to_uppercase().trim_end_matches("-SESS") {
+        "MD5" => |data| hex(&Md5::digest(data.as_bytes())),
+        "SHA-256" => |data| hex(&Sha256::digest(data.as_bytes())),
+        _ => return None,
+    };
+
+    let qop = params
+        .get("qop")
+        .filter(|qop| qop.split(',').any(|value| value.trim() == "auth"))
+        .map(|_| "auth");
+    let nonce_count = "00000001";
+    let cnonce = hash(&format!("{:?}", SystemTime::now().duration_since(UNIX_EPOCH).ok()?))[..16].to_string();
+
+    let mut ha1 = hash(&format!("{}:{}:{}", credentials.username, realm, credentials.password));
+    if algorithm.to_uppercase().ends_with("-SESS") {
+        ha1 = hash(&format!("{}:{}:{}", ha1, nonce, cnonce));
+    }
+    let ha2 = hash(&format!("{}:{}", method, uri));
+    let response = match qop {
+        Some(qop) => hash(&format!("{}:{}:{}:{}:{}:{}", ha1, nonce, nonce_count, cnonce, qop, ha2)),
+        None => hash(&format!("{}:{}:{}", ha1, nonce, ha2)),
+    };
+
+    let mut header = format!(
+        "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", algorithm={}, response=\"{}\"",
+        credentials.username, realm, nonce, uri, algorithm, response
+    );
+    if let Some(qop) = qop {
+        header.push_str(&format!(", qop={}, nc={}, cnonce=\"{}\"", qop, nonce_count, cnonce));
+    }
+    if let Some(opaque) = params.get("opaque") {
+        header.push_str(&format!(", opaque=\"{}\"", opaque));
+    }
+
+    Some(header)
+}
+
+/// Parses the parameters of a digest challenge, such as `realm="api", nonce="abc"`.
+fn parse_challenge(challenge: &str) -> Option<HashMap<String, String>> {
+    let challenge = challenge.trim();
+    if !challenge.get(..7)?.eq_ignore_ascii_case("digest ") {
+        return None;
+    }
+
+    let mut params = HashMap::new();
+    let mut rest = challenge[7..].trim_start();
+    while !rest.is_empty() {
+        let (key, after_key) = rest.split_once('=')?;
+        let key = key.trim().to_lowercase();
+        let after_key = after_key.trim_start();
+
+        let (value, remaining) = match after_key.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"')?;
+                (&quoted[..end], &quoted[end + 1..])
+            }
+            None => match after_key.find(',') {
+                Some(end) => (&after_key[..end], &after_key[end..]),
+                None => (after_key, ""),
+            },
+        };
+
+        params.insert(key, value.trim().to_string());
+        rest = remaining.trim_start().trim_start_matches(',').trim_start();
+    }
+
+    Some(params)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth(value: serde_json::Value) -> Auth {
+        serde_json::from_value(value).unwrap()
+    }
+
+    async fn parts(auth: &Auth) -> Result<AuthParts> {
+        let resolve = |text: &str| text.replace("{{token}}", "abc123");
+        apply(auth, &resolve, &reqwest::Client::new(), &TokenCache::default()).await
+    }
+
+    #[tokio::test]
+    async fn bearer_and_basic_set_the_authorization_header() {
+        let bearer = auth(serde_json::json!({
+            "type": "bearer",
+            "bearer": [{ "key": "token", "value": "{{token}}" }],
+        }));
+        assert_eq!(
+            parts(&bearer).await.unwrap().headers,
+            [("Authorization".to_string(), "Bearer abc123".to_string())]
+        );
+
+        let basic = auth(serde_json::json!({
+            "type": "basic",
+            "basic": { "username": "alice", "password": "secret" },
+        }));
+        assert_eq!(
+            parts(&basic).await.unwrap().headers,
+            [("Authorization".to_string(), "Basic YWxpY2U6c2VjcmV0".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn api_key_goes_to_the_header_or_the_query() {
+        let header = auth(serde_json::json!({
+            "type": "apikey",
+            "apikey": [{ "key": "key", "value": "X-API-Key" }, { "key": "value", "value": "{{token}}" }],
+        }));
+        let header = parts(&header).await.unwrap();
+        assert_eq!(header.headers, [("X-API-Key".to_string(), "abc123".to_string())]);
+        assert!(header.query.is_empty());
+
+        let query = auth(serde_json::json!({
+            "type": "apikey",
+            "apikey": [
+                { "key": "key", "value": "api_key" },
+                { "key": "value", "value": "{{token}}" },
+                { "key": "in", "value": "query" },
+            ],
+        }));
+        let query = parts(&query).await.unwrap();
+        assert!(query.headers.is_empty());
+        assert_eq!(query.query, [("api_key".to_string(), "abc123".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn oauth2_uses_the_saved_token() {
+        let header = auth(serde_json::json!({
+            "type": "oauth2",
+            "oauth2": [{ "key": "accessToken", "value": "{{token}}" }, { "key": "headerPrefix", "value": "Token" }],
+        }));
+        assert_eq!(
+            parts(&header).await.unwrap().headers,
+            [("Authorization".to_string(), "Token abc123".to_string())]
+        );
+
+        let query = auth(serde_json::json!({
+            "type": "oauth2",
+            "oauth2": [{ "key": "accessToken", "value": "{{token}}" }, { "key": "addTokenTo", "value": "queryParams" }],
+        }));
+        assert_eq!(
+            parts(&query).await.unwrap().query,
+            [("access_token".to_string(), "abc123".to_string())]
+        );
+
+        let browser = auth(serde_json::json!({
+            "type": "oauth2",
+            "oauth2": [{ "key": "grant_type", "value": "authorization_code" }],
+        }));
+        assert!(parts(&browser).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn digest_and_unknown_types() {
+        let digest = auth(serde_json::json!({
+            "type": "digest",
+            "digest": [{ "key": "username", "value": "alice" }, { "key": "password", "value": "secret" }],
+        }));
+        let digest_parts = parts(&digest).await.unwrap();
+        assert!(digest_parts.headers.is_empty() && digest_parts.query.is_empty());
+
+        let hawk = auth(serde_json::json!({ "type": "hawk", "hawk": [{ "key": "authId", "value": "id" }] }));
+        let hawk_parts = parts(&hawk).await.unwrap();
+        assert!(hawk_parts.headers.is_empty() && hawk_parts.query.is_empty());
+        assert!(!is_supported("hawk"));
+        assert!(is_supported("digest"));
+    }
+
+    #[test]
+    fn answers_digest_challenges() {
+        let credentials = DigestCredentials {
+            username: "Mufasa".to_string(),
+            password: "Circle Of Life".to_string(),
+        };
+        let challenge = r#"Digest realm="testrealm@host.com", qop="auth,auth-int", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#;
+
+        let header = digest_authorization(challenge, &credentials, "GET", "/dir/index.html").unwrap();
+        assert!(header.starts_with(r#"Digest username="Mufasa", realm="testrealm@host.com""#));
+        assert!(header.contains("qop=auth, nc=00000001"));
+        assert!(header.ends_with(r#"opaque="5ccc069c403ebaf9f0171e9517f40e41""#));
+
+        assert!(digest_authorization(r#"Digest realm="x", nonce="y", algorithm=SHA-512-256"#, &credentials, "GET", "/").is_none());
+        assert!(digest_authorization(r#"Basic realm="x""#, &credentials, "GET", "/").is_none());
+    }
+
+    #[test]
+    fn digest_response_without_qop() {
+        // RFC 2069 example values
+        let credentials = DigestCredentials {
+            username: "Mufasa".to_string(),
+            password: "CircleOfLife".to_string(),
+        };
+        let challenge = r#"Digest realm="testrealm@host.com", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093""#;
+        let header = digest_authorization(challenge, &credentials, "GET", "/dir/index.html").unwrap();
+        assert!(header.contains(r#"response="1949323746fe6a43ef61f9606e7febea""#), "{}", header);
+    }
+}
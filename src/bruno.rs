@@ -0,0 +1,891 @@
+//! Converts Bruno collections to the Postman collection model.
+//!
+//! A Bruno collection is a directory with a `bruno.json` file, one `.bru`
+//! file per request, a `folder.bru` per folder and a `collection.bru` for
+//! settings shared by every request. Environments are the `.bru` files in
+//! `environments/`, and `{{process.env.NAME}}` placeholders are filled from
+//! the collection's `.env` file.
+//!
+//! Bru files are a list of blocks: dictionaries of `key: value` lines
+//! (`headers { ... }`, disabled entries start with `~`), text blocks such as
+//! `body:json { ... }` and lists such as `vars:secret [ ... ]`. Headers set on
+//! the collection and folders are copied into their requests, since Postman
+//! has no folder headers. Request variables are set by a pre-request
+//! script, and post-response variables that read the status, body or a header
+//! become extraction rules; scripts, tests and `assert` blocks are reported
+//! and skipped.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value as JsonValue};
+use std::path::Path;
+
+use crate::collection::{Environment, ImportedCollection};
+use crate::legacy::SCHEMA_V2_1;
+
+/// Blocks whose content is text rather than `key: value` lines.
+const TEXT_BLOCKS: &[&str] = &[
+    "body:json",
+    "body:text",
+    "body:xml",
+    "body:sparql",
+    "body:graphql",
+    "body:graphql:vars",
+    "script:pre-request",
+    "script:post-response",
+    "tests",
+    "docs",
+];
+
+const METHODS: &[&str] = &["get", "post", "put", "delete", "patch", "options", "head", "connect", "trace"];
+
+/// A parsed block of a Bru file.
+#[derive(Debug, Clone)]
+enum Block {
+    Dictionary(Vec<Pair>),
+    Text(String),
+    List(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+struct Pair {
+    key: String,
+    value: String,
+    enabled: bool,
+}
+
+/// The blocks of one Bru file, by name.
+struct BruFile {
+    blocks: Vec<(String, Block)>,
+}
+
+impl BruFile {
+    fn block(&self, name: &str) -> Option<&Block> {
+        self.blocks.iter().find(|(block, _)| block == name).map(|(_, block)| block)
+    }
+
+    fn pairs(&self, name: &str) -> &[Pair] {
+        match self.block(name) {
+            Some(Block::Dictionary(pairs)) => pairs,
+            _ => &[],
+        }
+    }
+
+    fn text(&self, name: &str) -> Option<&str> {
+        match self.block(name) {
+            Some(Block::Text(text)) if !text.trim().is_empty() => Some(text),
+            _ => None,
+        }
+    }
+
+    /// The value of an enabled entry of a dictionary block.
+    fn value(&self, block: &str, key: &str) -> Option<&str> {
+        self.pairs(block)
+            .iter()
+            .find(|pair| pair.enabled && pair.key == key)
+            .map(|pair| pair.value.as_str())
+    }
+}
+
+/// Whether `path` is a Bruno collection directory.
+pub fn is_collection(path: &Path) -> bool {
+    path.is_dir() && path.join("bruno.json").is_file()
+}
+
+/// Loads the Bruno collection in the directory `root`. Its environments are
+/// the files in `environments/`, named after the file.
+pub fn load(root: &Path) -> Result<ImportedCollection> {
+    let config: JsonValue = serde_json::from_str(
+        &std::fs::read_to_string(root.join("bruno.json")).context("Failed to read bruno.json")?,
+    )
+    .context("Failed to parse bruno.json")?;
+    let ignored: Vec<String> = config
+        .get("ignore")
+        .and_then(JsonValue::as_array)
+        .map(|ignore| ignore.iter().filter_map(JsonValue::as_str).map(str::to_string).collect())
+        .unwrap_or_default();
+
+    let mut loader = Loader {
+        root,
+        ignored,
+        warnings: Vec::new(),
+    };
+
+    let settings = match root.join("collection.bru") {
+        path if path.is_file() => Some(loader.read(&path)?),
+        _ => None,
+    };
+    let headers = settings.as_ref().map(headers).unwrap_or_default();
+    let item = loader.items(root, &headers)?;
+
+    let name = config
+        .get("name")
+        .and_then(JsonValue::as_str)
+        .map(str::to_string)
+        .or_else(|| root.file_name().map(|name| name.to_string_lossy().into_owned()))
+        .unwrap_or_default();
+    let mut collection = json!({
+        "info": { "name": name, "schema": SCHEMA_V2_1 },
+        "item": item,
+    });
+
+    if let Some(settings) = &settings {
+        if let Some(docs) = settings.text("docs") {
+            collection["info"]["description"] = json!(docs);
+        }
+        if let Some(auth) = loader.auth(settings, settings.value("auth", "mode"), "collection.bru") {
+            collection["auth"] = auth;
+        }
+        if let Some(events) = loader.events(settings, "collection.bru") {
+            collection["event"] = events;
+        }
+        loader.skip_post_response_vars(settings, "collection.bru");
+    }
+    let variables: Vec<JsonValue> = read_dotenv(&root.join(".env"))?
+        .into_iter()
+        .map(|(key, value)| json!({ "key": format!("process.env.{}", key), "value": value }))
+        .collect();
+    if !variables.is_empty() {
+        collection["variable"] = json!(variables);
+    }
+
+    let collection = serde_json::from_value(collection).context("Failed to convert Bruno collection")?;
+    let environments = loader.environments()?;
+
+    Ok(ImportedCollection {
+        collection,
+        environments,
+        warnings: loader.warnings,
+    })
+}
+
+struct Loader<'a> {
+    root: &'a Path,
+    /// Paths relative to the root that are not part of the collection
+    ignored: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl Loader<'_> {
+    fn read(&self, path: &Path) -> Result<BruFile> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        parse(&text).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// The folders and requests in `dir`, in their `seq` order. `headers` are
+    /// inherited from the collection and the enclosing folders.
+    fn items(&mut self, dir: &Path, headers: &[JsonValue]) -> Result<Vec<JsonValue>> {
+        let mut entries: Vec<_> = std::fs::read_dir(dir)
+            .with_context(|| format!("Failed to read {}", dir.display()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect();
+        entries.sort();
+
+        let mut items: Vec<(f64, String, JsonValue)> = Vec::new();
+        for path in entries {
+            let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+            let relative = path.strip_prefix(self.root).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+            if name.starts_with('.') || self.ignored.iter().any(|ignored| relative == *ignored || name == *ignored) {
+                continue;
+            }
+
+            if path.is_dir() {
+                if dir == self.root && name == "environments" {
+                    continue;
+                }
+                let folder = match path.join("folder.bru") {
+                    folder if folder.is_file() => Some(self.read(&folder)?),
+                    _ => None,
+                };
+                let inherited = override_headers(headers, folder.as_ref().map(self::headers).unwrap_or_default());
+                let children = self.items(&path, &inherited)?;
+                if children.is_empty() {
+                    continue;
+                }
+
+                let folder_name = folder
+                    .as_ref()
+                    .and_then(|folder| folder.value("meta", "name"))
+                    .unwrap_or(&name)
+                    .to_string();
+                let mut converted = json!({ "name": folder_name, "item": children });
+                if let Some(folder) = &folder {
+                    if let Some(docs) = folder.text("docs") {
+                        converted["description"] = json!(docs);
+                    }
+                    if let Some(auth) = self.auth(folder, folder.value("auth", "mode"), &relative) {
+                        converted["auth"] = auth;
+                    }
+                    let folder_path = format!("{}/folder.bru", relative);
+                    if let Some(events) = self.events(folder, &folder_path) {
+                        converted["event"] = events;
+                    }
+                    self.skip_post_response_vars(folder, &folder_path);
+                }
+                items.push((sequence(folder.as_ref()), folder_name, converted));
+            } else if name.ends_with(".bru") && !matches!(name.as_str(), "folder.bru" | "collection.bru") {
+                let file = self.read(&path)?;
+                if let Some(request) = self.request(&file, &name, &relative, headers) {
+                    let request_name = request["name"].as_str().unwrap_or_default().to_string();
+                    items.push((sequence(Some(&file)), request_name, request));
+                }
+            }
+        }
+
+        items.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+        Ok(items.into_iter().map(|(_, _, item)| item).collect())
+    }
+
+    fn request(&mut self, file: &BruFile, file_name: &str, path: &str, inherited_headers: &[JsonValue]) -> Option<JsonValue> {
+        let name = file
+            .value("meta", "name")
+            .unwrap_or_else(|| file_name.trim_end_matches(".bru"))
+            .to_string();
+        let request_type = file.value("meta", "type").unwrap_or("http");
+        if !matches!(request_type, "http" | "graphql") {
+            self.warnings.push(format!("Skipped Bruno {} request {}: only HTTP requests are supported", request_type, path));
+            return None;
+        }
+        let Some(method) = METHODS.iter().find(|method| file.block(method).is_some()) else {
+            self.warnings.push(format!("Skipped {}: no HTTP method block", path));
+            return None;
+        };
+
+        let header = override_headers(inherited_headers, headers(file));
+
+        let mut url = json!({ "raw": file.value(method, "url").unwrap_or_default() });
+        let query: Vec<JsonValue> = file.pairs("params:query").iter().map(key_value).collect();
+        if !query.is_empty() {
+            url["query"] = json!(query);
+        }
+        let variables: Vec<JsonValue> = file
+            .pairs("params:path")
+            .iter()
+            .map(|pair| json!({ "key": pair.key, "value": pair.value }))
+            .collect();
+        if !variables.is_empty() {
+            url["variable"] = json!(variables);
+        }
+
+        let mut request = json!({
+            "method": method.to_uppercase(),
+            "header": header,
+            "url": url,
+        });
+        if let Some(body) = body(file, file.value(method, "body").unwrap_or("none")) {
+            request["body"] = body;
+        }
+        // Bruno requests without an auth mode have none
+        if let Some(auth) = self.auth(file, Some(file.value(method, "auth").unwrap_or("none")), path) {
+            request["auth"] = auth;
+        }
+        if let Some(docs) = file.text("docs") {
+            request["description"] = json!(docs);
+        }
+
+        let mut item = json!({ "name": name, "request": request });
+        if let Some(events) = self.events(file, path) {
+            item["event"] = events;
+        }
+        let mut rules = serde_json::Map::new();
+        for pair in file.pairs("vars:post-response").iter().filter(|pair| pair.enabled) {
+            match extract_source(&pair.value) {
+                Some(source) => {
+                    rules.insert(pair.key.clone(), json!(source));
+                }
+                None => self.warnings.push(format!(
+                    "Skipped post-response variable {} in {}: only res.status, res.body and res.headers values can be extracted",
+                    pair.key, path
+                )),
+            }
+        }
+        if !rules.is_empty() {
+            item["extract"] = JsonValue::Object(rules);
+        }
+        Some(item)
+    }
+
+    /// A pre-request event that sets the file's `vars:pre-request` variables.
+    /// Scripts, tests and assertions are reported and skipped.
+    fn events(&mut self, file: &BruFile, path: &str) -> Option<JsonValue> {
+        for block in ["script:pre-request", "script:post-response", "tests"] {
+            if file.text(block).is_some() {
+                self.warnings.push(format!("Skipped the {} block of {}: Bruno scripts are not supported", block, path));
+            }
+        }
+        if file.pairs("assert").iter().any(|pair| pair.enabled) {
+            self.warnings.push(format!("Skipped the assert block of {}: Bruno assertions are not supported", path));
+        }
+        let exec: Vec<String> = file
+            .pairs("vars:pre-request")
+            .iter()
+            .filter(|pair| pair.enabled)
+            .map(|pair| format!("pm.variables.set({}, pm.variables.replaceIn({}));", json!(pair.key), json!(pair.value)))
+            .collect();
+        if exec.is_empty() {
+            return None;
+        }
+        Some(json!([{
+            "listen": "prerequest",
+            "script": { "type": "text/javascript", "exec": exec },
+        }]))
+    }
+
+    /// Reports the post-response variables of a folder or collection, since
+    /// only requests have extraction rules.
+    fn skip_post_response_vars(&mut self, file: &BruFile, path: &str) {
+        if file.pairs("vars:post-response").iter().any(|pair| pair.enabled) {
+            self.warnings.push(format!(
+                "Skipped the vars:post-response block of {}: only requests can extract response values",
+                path
+            ));
+        }
+    }
+
+    /// The auth block for an auth mode; `inherit` and a missing mode inherit
+    /// the enclosing folder's auth.
+    fn auth(&mut self, file: &BruFile, mode: Option<&str>, path: &str) -> Option<JsonValue> {
+        let mode = mode.filter(|mode| *mode != "inherit")?;
+        let param = |key: &str| file.value(&format!("auth:{}", mode), key).unwrap_or_default().to_string();
+        let block = |auth_type: &str, params: Vec<(&str, String)>| {
+            let params: Vec<JsonValue> = params
+                .into_iter()
+                .map(|(key, value)| json!({ "key": key, "value": value, "type": "string" }))
+                .collect();
+            json!({ "type": auth_type, auth_type: params })
+        };
+
+        Some(match mode {
+            "none" => json!({ "type": "noauth" }),
+            "basic" | "digest" => block(mode, vec![("username", param("username")), ("password", param("password"))]),
+            "bearer" => block("bearer", vec![("token", param("token"))]),
+            "apikey" => {
+                let location = if param("placement") == "queryparams" { "query" } else { "header" };
+                block("apikey", vec![("key", param("key")), ("value", param("value")), ("in", location.to_string())])
+            }
+            "oauth2" => {
+                let client_authentication = if param("credentials_placement") == "body" { "body" } else { "header" };
+                let mut params = vec![
+                    ("grant_type", param("grant_type")),
+                    ("accessTokenUrl", param("access_token_url")),
+                    ("authUrl", param("authorization_url")),
+                    ("clientId", param("client_id")),
+                    ("clientSecret", param("client_secret")),
+                    ("scope", param("scope")),
+                    ("username", param("username")),
+                    ("password", param("password")),
+                    ("client_authentication", client_authentication.to_string()),
+                ];
+                if param("token_placement") == "url" {
+                    params.push(("addTokenTo", "queryParams".to_string()));
+                }
+                if let Some(prefix) = file.value("auth:oauth2", "token_header_prefix") {
+                    params.push(("headerPrefix", prefix.to_string()));
+                }
+                block("oauth2", params)
+            }
+            other => {
+                self.warnings.push(format!("Bruno {} auth in {} is not supported; it is sent without auth", other, path));
+                json!({ "type": "noauth" })
+            }
+        })
+    }
+
+    /// The `environments/*.bru` files. Secret variables are listed without
+    /// values, since Bruno keeps those outside the collection.
+    fn environments(&self) -> Result<Vec<Environment>> {
+        let dir = self.root.join("environments");
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut paths: Vec<_> = std::fs::read_dir(&dir)
+            .with_context(|| format!("Failed to read {}", dir.display()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "bru"))
+            .collect();
+        paths.sort();
+
+        paths
+            .iter()
+            .map(|path| {
+                let file = self.read(path)?;
+                let mut values: Vec<JsonValue> = file
+                    .pairs("vars")
+                    .iter()
+                    .map(|pair| json!({ "key": pair.key, "value": pair.value, "type": "default", "enabled": pair.enabled }))
+                    .collect();
+                if let Some(Block::List(secrets)) = file.block("vars:secret") {
+                    values.extend(secrets.iter().map(|secret| {
+                        let (key, enabled) = match secret.strip_prefix('~') {
+                            Some(key) => (key, false),
+                            None => (secret.as_str(), true),
+                        };
+                        json!({ "key": key, "value": "", "type": "secret", "enabled": enabled })
+                    }));
+                }
+                let name = path.file_stem().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                serde_json::from_value(json!({ "name": name, "values": values }))
+                    .with_context(|| format!("Failed to convert {}", path.display()))
+            })
+            .collect()
+    }
+}
+
+/// Parses the blocks of a Bru file. Block contents are indented and end with
+/// a `}` (or `]` for lists) at the start of a line.
+fn parse(text: &str) -> Result<BruFile> {
+    let mut blocks = Vec::new();
+    let mut lines = text.lines().enumerate();
+
+    while let Some((number, line)) = lines.next() {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (name, open) = if let Some(name) = line.strip_suffix('{') {
+            (name.trim(), '{')
+        } else if let Some(name) = line.strip_suffix('[') {
+            (name.trim(), '[')
+        } else if let Some(name) = line.strip_suffix("{}") {
+            blocks.push((name.trim().to_string(), empty_block(name.trim())));
+            continue;
+        } else {
+            return Err(anyhow!("line {}: expected a block, found `{}`", number + 1, line));
+        };
+        let close = if open == '{' { "}" } else { "]" };
+
+        let mut content = Vec::new();
+        loop {
+            match lines.next() {
+                Some((_, line)) if line.trim_end() == close => break,
+                Some((_, line)) => content.push(line),
+                None => return Err(anyhow!("line {}: block `{}` is never closed", number + 1, name)),
+            }
+        }
+
+        let block = if open == '[' {
+            Block::List(
+                content
+                    .iter()
+                    .map(|line| line.trim().trim_end_matches(',').to_string())
+                    .filter(|item| !item.is_empty())
+                    .collect(),
+            )
+        } else if TEXT_BLOCKS.contains(&name) {
+            Block::Text(dedent(&content, 2))
+        } else {
+            Block::Dictionary(parse_pairs(&content))
+        };
+        blocks.push((name.to_string(), block));
+    }
+
+    Ok(BruFile { blocks })
+}
+
+fn empty_block(name: &str) -> Block {
+    if TEXT_BLOCKS.contains(&name) {
+        Block::Text(String::new())
+    } else {
+        Block::Dictionary(Vec::new())
+    }
+}
+
+/// `key: value` lines; a value of `'''` starts a multi-line value that ends
+/// with another `'''`.
+fn parse_pairs(lines: &[&str]) -> Vec<Pair> {
+    let mut pairs = Vec::new();
+    let mut lines = lines.iter();
+
+    while let Some(line) = lines.next() {
+        let Some((key, value)) = line.trim().split_once(':') else {
+            continue;
+        };
+        let (key, enabled) = match key.trim().strip_prefix('~') {
+            Some(key) => (key, false),
+            None => (key.trim(), true),
+        };
+        let mut value = value.trim().to_string();
+        if value == "'''" {
+            let multiline: Vec<&str> = lines.by_ref().take_while(|line| line.trim() != "'''").copied().collect();
+            let indent = multiline
+                .iter()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.len() - line.trim_start().len())
+                .min()
+                .unwrap_or_default();
+            value = dedent(&multiline, indent);
+        }
+        pairs.push(Pair {
+            key: key.to_string(),
+            value,
+            enabled,
+        });
+    }
+
+    pairs
+}
+
+/// Joins lines after removing up to `indent` leading spaces from each.
+fn dedent(lines: &[&str], indent: usize) -> String {
+    lines
+        .iter()
+        .map(|line| {
+            let spaces = line.len() - line.trim_start_matches(' ').len();
+            &line[spaces.min(indent)..]
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn sequence(file: Option<&BruFile>) -> f64 {
+    file.and_then(|file| file.value("meta", "seq"))
+        .and_then(|seq| seq.parse().ok())
+        .unwrap_or(f64::MAX)
+}
+
+fn key_value(pair: &Pair) -> JsonValue {
+    let mut converted = json!({ "key": pair.key, "value": pair.value });
+    if !pair.enabled {
+        converted["disabled"] = json!(true);
+    }
+    converted
+}
+
+fn headers(file: &BruFile) -> Vec<JsonValue> {
+    file.pairs("headers").iter().map(key_value).collect()
+}
+
+/// The `inherited` headers that `own` does not replace, followed by `own`.
+/// Header names are compared case-insensitively.
+fn override_headers(inherited: &[JsonValue], own: Vec<JsonValue>) -> Vec<JsonValue> {
+    let mut headers: Vec<JsonValue> = inherited
+        .iter()
+        .filter(|inherited| {
+            let key = inherited["key"].as_str().unwrap_or_default();
+            !own.iter()
+                .any(|own| own["key"].as_str().is_some_and(|own| own.eq_ignore_ascii_case(key)))
+        })
+        .cloned()
+        .collect();
+    headers.extend(own);
+    headers
+}
+
+/// The body for the request's body mode.
+fn body(file: &BruFile, mode: &str) -> Option<JsonValue> {
+    let raw = |block: &str, language: &str| {
+        json!({
+            "mode": "raw",
+            "raw": file.text(block).unwrap_or_default(),
+            "options": { "raw": { "language": language } },
+        })
+    };
+
+    Some(match mode {
+        "json" => raw("body:json", "json"),
+        "xml" => raw("body:xml", "xml"),
+        "text" => raw("body:text", "text"),
+        "sparql" => raw("body:sparql", "text"),
+        "formUrlEncoded" => {
+            let fields: Vec<JsonValue> = file.pairs("body:form-urlencoded").iter().map(key_value).collect();
+            json!({ "mode": "urlencoded", "urlencoded": fields })
+        }
+        "multipartForm" => {
+            let fields: Vec<JsonValue> = file
+                .pairs("body:multipart-form")
+                .iter()
+                .map(|pair| {
+                    let (value, content_type) = split_content_type(&pair.value);
+                    let mut field = match file_paths(value) {
+                        Some(paths) if paths.len() == 1 => json!({ "key": pair.key, "type": "file", "src": paths[0] }),
+                        Some(paths) => json!({ "key": pair.key, "type": "file", "src": paths }),
+                        None => json!({ "key": pair.key, "value": value, "type": "text" }),
+                    };
+                    if let Some(content_type) = content_type {
+                        field["contentType"] = json!(content_type);
+                    }
+                    if !pair.enabled {
+                        field["disabled"] = json!(true);
+                    }
+                    field
+                })
+                .collect();
+            json!({ "mode": "formdata", "formdata": fields })
+        }
+        "graphql" => json!({
+            "mode": "graphql",
+            "graphql": {
+                "query": file.text("body:graphql").unwrap_or_default(),
+                "variables": file.text("body:graphql:vars").unwrap_or_default(),
+            },
+        }),
+        "file" => {
+            let selected = file.pairs("body:file").iter().find(|pair| pair.enabled)?;
+            let (value, _) = split_content_type(&selected.value);
+            let path = file_paths(value)?.into_iter().next()?;
+            json!({ "mode": "file", "file": { "src": path } })
+        }
+        _ => return None,
+    })
+}
+
+/// The paths of an `@file(a.png|b.png)` value.
+fn file_paths(value: &str) -> Option<Vec<String>> {
+    let paths = value.trim().strip_prefix("@file(")?.strip_suffix(')')?;
+    Some(paths.split('|').map(str::to_string).collect())
+}
+
+/// Splits a trailing `@contentType(...)` off a form value.
+fn split_content_type(value: &str) -> (&str, Option<&str>) {
+    match value.rsplit_once("@contentType(") {
+        Some((value, content_type)) => (value.trim(), content_type.strip_suffix(')')),
+        None => (value, None),
+    }
+}
+
+/// The extraction rule for a `vars:post-response` expression that reads the
+/// response: `res.status`, `res.body.path` (or `res("path")`) and
+/// `res.headers.name` (or `res.getHeader("name")`).
+fn extract_source(expression: &str) -> Option<String> {
+    let expression = expression.trim();
+    let quoted = |text: &str| {
+        let text = text.trim();
+        text.strip_prefix('"')
+            .and_then(|text| text.strip_suffix('"'))
+            .or_else(|| text.strip_prefix('\'').and_then(|text| text.strip_suffix('\'')))
+            .map(str::to_string)
+    };
+    let json_path = |path: &str| match path {
+        "" => Some("$".to_string()),
+        _ if (path.starts_with('.') || path.starts_with('[')) && is_plain_path(path) => Some(format!("${}", path)),
+        _ => None,
+    };
+
+    if matches!(expression, "res.status" | "res.getStatus()") {
+        return Some("status".to_string());
+    }
+    if let Some(path) = expression.strip_prefix("res.body").or_else(|| expression.strip_prefix("res.getBody()")) {
+        return json_path(path);
+    }
+    if let Some(argument) = expression.strip_prefix("res(").and_then(|rest| rest.strip_suffix(')')) {
+        let path = quoted(argument)?;
+        return json_path(&path).or_else(|| json_path(&format!(".{}", path)));
+    }
+    if let Some(name) = expression.strip_prefix("res.headers.") {
+        return Some(format!("header:{}", name));
+    }
+    let header = expression
+        .strip_prefix("res.headers[")
+        .and_then(|rest| rest.strip_suffix(']'))
+        .or_else(|| expression.strip_prefix("res.getHeader(").and_then(|rest| rest.strip_suffix(')')))?;
+    quoted(header).map(|name| format!("header:{}", name))
+}
+
+/// Whether `path` is only property accesses like `.items[0]["id"]`, rather
+/// than an expression that computes something from the response.
+fn is_plain_path(path: &str) -> bool {
+    let mut quote = None;
+    path.chars().all(|c| match quote {
+        Some(open) => {
+            if c == open {
+                quote = None;
+            }
+            true
+        }
+        None if c == '"' || c == '\'' => {
+            quote = Some(c);
+            true
+        }
+        None => c.is_alphanumeric() || matches!(c, '_' | '$' | '.' | '[' | ']'),
+    }) && quote.is_none()
+}
+
+/// Reads `NAME=value` lines of a `.env` file, if there is one.
+fn read_dotenv(path: &Path) -> Result<Vec<(String, String)>> {
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (key, value) = line.strip_prefix("export ").unwrap_or(line).split_once('=')?;
+            let value = value.trim();
+            let unquoted = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|value| value.strip_suffix('\'')))
+                .unwrap_or(value);
+            Some((key.trim().to_string(), unquoted.to_string()))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILES: &[(&str, &str)] = &[
+        ("bruno.json", r#"{ "version": "1", "name": "Backend API", "type": "collection", "ignore": ["node_modules"] }"#),
+        (".env", "API_KEY=\"from-dotenv\"\n# comment\n"),
+        (
+            "collection.bru",
+            "headers {\n  X-Client: harbinger\n}\n\nauth {\n  mode: bearer\n}\n\nauth:bearer {\n  token: {{token}}\n}\n\ndocs {\n  # Backend API\n  Shared docs\n}\n",
+        ),
+        ("Users/folder.bru", "meta {\n  name: User management\n  seq: 1\n}\n\nheaders {\n  X-Folder: users\n  x-client: users\n}\n"),
+        ("Users/Get.bru", "meta {\n  name: Get user\n  seq: 3\n}\n\nget {\n  url: {{baseUrl}}/users/1\n  auth: hawk\n}\n"),
+        (
+            "Users/List.bru",
+            "meta {\n  name: List users\n  seq: 2\n}\n\nget {\n  url: {{baseUrl}}/users?page=1\n  body: none\n  auth: inherit\n}\n\n\
+             params:query {\n  page: 1\n  ~limit: 10\n}\n\nheaders {\n  X-Client: list\n  ~X-Off: no\n}\n\n\
+             vars:post-response {\n  firstId: res.body.id\n  total: res.getHeader(\"X-Total\")\n  computed: res.body.items.length * 2\n}\n\n\
+             assert {\n  res.status: eq 200\n}\n\ntests {\n  test(\"ok\", function() {});\n}\n",
+        ),
+        (
+            "Create.bru",
+            "meta {\n  name: Create user\n  seq: 3\n}\n\npost {\n  url: {{baseUrl}}/users\n  body: json\n  auth: apikey\n}\n\n\
+             auth:apikey {\n  key: X-Api-Key\n  value: {{process.env.API_KEY}}\n  placement: queryparams\n}\n\n\
+             body:json {\n  {\n    \"name\": \"Ada\"\n  }\n}\n\nvars:pre-request {\n  stamp: {{$timestamp}}\n}\n",
+        ),
+        (
+            "Upload.bru",
+            "meta {\n  name: Upload\n  seq: 4\n}\n\npost {\n  url: {{baseUrl}}/upload\n  body: multipartForm\n}\n\n\
+             body:multipart-form {\n  title: '''\n    line one\n      line two\n  '''\n  file: @file(files/a.txt) @contentType(text/plain)\n  ~skip: nope\n}\n",
+        ),
+        ("node_modules/x/Bad.bru", "not a bru file {"),
+        ("environments/Local.bru", "vars {\n  baseUrl: http://localhost\n  ~unused: x\n}\nvars:secret [\n  password\n]\n"),
+    ];
+
+    /// Writes `FILES` to a fresh directory and loads it as a collection.
+    fn load_fixture(name: &str) -> ImportedCollection {
+        let root = std::env::temp_dir().join(format!("harbinger-bruno-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&root);
+        for (path, text) in FILES {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, text).unwrap();
+        }
+        assert!(is_collection(&root));
+        let imported = load(&root).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        imported
+    }
+
+    #[test]
+    fn parses_blocks() {
+        let file = parse("meta {\n  name: Ping\n}\n\nget {\n  url: http://localhost/ping\n}\n\nbody:json {\n  {\n    \"a\": 1\n  }\n}\n\nvars:secret [\n  token,\n  ~old\n]\n").unwrap();
+        assert_eq!(file.value("meta", "name"), Some("Ping"));
+        assert_eq!(file.value("get", "url"), Some("http://localhost/ping"));
+        assert_eq!(file.text("body:json"), Some("{\n  \"a\": 1\n}"));
+        assert!(matches!(file.block("vars:secret"), Some(Block::List(names)) if names == &["token", "~old"]));
+        assert!(file.block("post").is_none());
+    }
+
+    #[test]
+    fn converts_requests_in_seq_order() {
+        let imported = load_fixture("requests");
+        let collection = serde_json::to_value(&imported.collection).unwrap();
+        assert_eq!(collection["info"]["name"], "Backend API");
+        assert_eq!(collection["info"]["description"], "# Backend API\nShared docs");
+        assert_eq!(collection["auth"]["type"], "bearer");
+        assert_eq!(collection["variable"][0]["key"], "process.env.API_KEY");
+        assert_eq!(collection["variable"][0]["value"], "from-dotenv");
+
+        let names: Vec<&str> = collection["item"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["User management", "Create user", "Upload"]);
+
+        let list = &collection["item"][0]["item"][0];
+        assert_eq!(list["request"]["method"], "GET");
+        assert_eq!(list["request"]["url"]["query"][1]["key"], "limit");
+        assert_eq!(list["request"]["url"]["query"][1]["disabled"], true);
+        // Own headers replace inherited ones with the same name
+        let headers: Vec<(&str, &str)> = list["request"]["header"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|header| (header["key"].as_str().unwrap(), header["value"].as_str().unwrap()))
+            .collect();
+        assert_eq!(headers, [("X-Folder", "users"), ("X-Client", "list"), ("X-Off", "no")]);
+        assert!(list["request"].get("auth").is_none());
+
+        // Folder headers replace collection headers with the same name too
+        let get = &collection["item"][0]["item"][1]["request"];
+        assert_eq!(
+            get["header"],
+            json!([{ "key": "X-Folder", "value": "users" }, { "key": "x-client", "value": "users" }])
+        );
+        // Unsupported auth is sent without auth
+        assert_eq!(get["auth"], json!({ "type": "noauth" }));
+
+        let create = &collection["item"][1];
+        assert_eq!(create["request"]["auth"]["apikey"][2]["value"], "query");
+        assert_eq!(create["request"]["body"]["raw"], "{\n  \"name\": \"Ada\"\n}");
+
+        let upload = &collection["item"][2]["request"];
+        assert_eq!(upload["auth"]["type"], "noauth");
+        assert_eq!(upload["body"]["formdata"][0]["value"], "line one\n  line two");
+        assert_eq!(upload["body"]["formdata"][1]["src"], "files/a.txt");
+        assert_eq!(upload["body"]["formdata"][1]["contentType"], "text/plain");
+        assert_eq!(upload["body"]["formdata"][2]["disabled"], true);
+    }
+
+    #[test]
+    fn converts_variables_and_environments() {
+        let imported = load_fixture("variables");
+        let collection = serde_json::to_value(&imported.collection).unwrap();
+
+        let list = &collection["item"][0]["item"][0];
+        assert_eq!(list["extract"], json!({ "firstId": "$.id", "total": "header:X-Total" }));
+
+        let create = &collection["item"][1];
+        assert_eq!(create["event"][0]["listen"], "prerequest");
+        assert_eq!(
+            create["event"][0]["script"]["exec"][0],
+            r#"pm.variables.set("stamp", pm.variables.replaceIn("{{$timestamp}}"));"#
+        );
+
+        let local = imported.environment("Local").unwrap();
+        let values = serde_json::to_value(&local.values).unwrap();
+        assert_eq!(values[0], json!({ "key": "baseUrl", "value": "http://localhost", "type": "default", "enabled": true }));
+        assert_eq!(values[1]["enabled"], false);
+        assert_eq!(values[2], json!({ "key": "password", "value": "", "type": "secret", "enabled": true }));
+    }
+
+    #[test]
+    fn reports_what_it_cannot_convert() {
+        let imported = load_fixture("warnings");
+        assert_eq!(
+            imported.warnings,
+            [
+                "Bruno hawk auth in Users/Get.bru is not supported; it is sent without auth",
+                "Skipped the tests block of Users/List.bru: Bruno scripts are not supported",
+                "Skipped the assert block of Users/List.bru: Bruno assertions are not supported",
+                "Skipped post-response variable computed in Users/List.bru: only res.status, res.body and res.headers values can be extracted",
+            ]
+        );
+    }
+
+    #[test]
+    fn extracts_response_values() {
+        assert_eq!(extract_source("res.status").as_deref(), Some("status"));
+        assert_eq!(extract_source("res.getStatus()").as_deref(), Some("status"));
+        assert_eq!(extract_source("res.body").as_deref(), Some("$"));
+        assert_eq!(extract_source("res.body.items[0].id").as_deref(), Some("$.items[0].id"));
+        assert_eq!(extract_source("res.getBody().token").as_deref(), Some("$.token"));
+        assert_eq!(extract_source(r#"res("data.id")"#).as_deref(), Some("$.data.id"));
+        assert_eq!(extract_source("res.headers.etag").as_deref(), Some("header:etag"));
+        assert_eq!(extract_source(r#"res.headers["x-request-id"]"#).as_deref(), Some("header:x-request-id"));
+        assert_eq!(extract_source("res.getHeader('location')").as_deref(), Some("header:location"));
+        assert_eq!(extract_source(r#"res.body["user name"]"#).as_deref(), Some(r#"$["user name"]"#));
+        assert_eq!(extract_source("res.body.items.length * 2"), None);
+        assert_eq!(extract_source("res.bodyText"), None);
+        assert_eq!(extract_source("bru.getVar(\"id\")"), None);
+    }
+}
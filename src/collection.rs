@@ -1,47 +1,219 @@
+use anyhow::{anyhow, Context, Result};
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
 
-#[derive(Debug, Deserialize, Serialize)]
+use crate::extract::ExtractRules;
+use crate::legacy;
+
+/// A Postman v2.1 collection. Every type of the model keeps the fields it
+/// does not model in `extra`, so reading and writing a collection loses
+/// nothing.
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct PostmanCollection {
     pub info: CollectionInfo,
     pub item: Vec<Item>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub variable: Option<Vec<Variable>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<Vec<Event>>,
+    #[serde(rename = "protocolProfileBehavior", skip_serializing_if = "Option::is_none")]
+    pub protocol_profile_behavior: Option<IndexMap<String, JsonValue>>,
+    /// Fields not modelled above, kept so the collection round-trips
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct CollectionInfo {
     pub name: String,
+    #[serde(rename = "_postman_id", skip_serializing_if = "Option::is_none")]
+    pub postman_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<Description>,
+    /// A version string, or `{major, minor, patch, ...}`
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub description: Option<String>,
+    pub version: Option<JsonValue>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub schema: Option<String>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
+}
+
+/// A description, as plain text or with its content type.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum Description {
+    Text(String),
+    Object(DescriptionObject),
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DescriptionObject {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// e.g. `text/markdown`
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<JsonValue>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl Description {
+    pub fn text(&self) -> &str {
+        match self {
+            Description::Text(text) => text,
+            Description::Object(object) => object.content.as_deref().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
 pub enum Item {
     Request(RequestItem),
     Folder(FolderItem),
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct RequestItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<Description>,
     pub request: Request,
+    /// Saved example responses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<Vec<ExampleResponse>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub response: Option<Vec<serde_json::Value>>,
+    pub event: Option<Vec<Event>>,
+    #[serde(rename = "protocolProfileBehavior", skip_serializing_if = "Option::is_none")]
+    pub protocol_profile_behavior: Option<IndexMap<String, JsonValue>>,
+    /// Values to extract from the response into variables
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extract: Option<ExtractRules>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct FolderItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     pub name: String,
     pub item: Vec<Item>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub description: Option<String>,
+    pub description: Option<Description>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variable: Option<Vec<Variable>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<Vec<Event>>,
+    #[serde(rename = "protocolProfileBehavior", skip_serializing_if = "Option::is_none")]
+    pub protocol_profile_behavior: Option<IndexMap<String, JsonValue>>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
+}
+
+/// A saved example response of a request.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ExampleResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "originalRequest", skip_serializing_if = "Option::is_none")]
+    pub original_request: Option<Request>,
+    /// Status text, e.g. `OK`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub header: Option<ResponseHeaders>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cookie: Option<Vec<JsonValue>>,
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub body: Option<JsonValue>,
+    /// Language of the body in Postman's preview, e.g. `json`
+    #[serde(rename = "_postman_previewlanguage", skip_serializing_if = "Option::is_none")]
+    pub preview_language: Option<String>,
+    /// Milliseconds, as a number or a string
+    #[serde(rename = "responseTime", default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub response_time: Option<JsonValue>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
+}
+
+/// The headers of an example response: a list, or the raw header lines.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum ResponseHeaders {
+    List(Vec<Header>),
+    Text(String),
+}
+
+/// A script attached to a collection, folder or request.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Event {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// `prerequest` or `test`
+    pub listen: String,
+    pub script: Script,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disabled: Option<bool>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Script {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub script_type: Option<String>,
+    /// Source lines; older exports use a single string
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exec: Option<ScriptSource>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum ScriptSource {
+    Lines(Vec<String>),
+    Text(String),
+}
+
+impl Script {
+    pub fn source(&self) -> String {
+        match &self.exec {
+            Some(ScriptSource::Lines(lines)) => lines.join("\n"),
+            Some(ScriptSource::Text(text)) => text.clone(),
+            None => String::new(),
+        }
+    }
+}
+
+/// A request together with the folders containing it, outermost first.
+#[derive(Debug)]
+pub struct RequestContext<'a> {
+    pub item: &'a RequestItem,
+    pub folders: Vec<&'a FolderItem>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
 pub enum Request {
     Simple(String),
     Full(FullRequest),
@@ -56,11 +228,26 @@ pub struct FullRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub body: Option<Body>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub auth: Option<serde_json::Value>,
+    pub auth: Option<Auth>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<Description>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
+}
+
+/// A Postman auth block, e.g. `{"type": "bearer", "bearer": [{"key": "token", "value": "..."}]}`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Auth {
+    #[serde(rename = "type")]
+    pub auth_type: String,
+    /// Parameters keyed by auth type
+    #[serde(flatten)]
+    pub params: IndexMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
 pub enum Url {
     String(String),
     Object(UrlObject),
@@ -73,27 +260,123 @@ pub struct UrlObject {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub protocol: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub host: Option<Vec<String>>,
+    pub host: Option<UrlParts>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub path: Option<Vec<String>>,
+    pub path: Option<UrlParts>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub query: Option<Vec<QueryParam>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variable: Option<Vec<Variable>>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
+}
+
+impl Url {
+    /// The URL as text, with the enabled parameters of the query array and
+    /// the `hash` fragment.
+    pub fn raw(&self) -> String {
+        let object = match self {
+            Url::String(raw) => return raw.clone(),
+            Url::Object(object) => object,
+        };
+        let raw = object.raw.clone().unwrap_or_default();
+        let (raw, raw_hash) = match raw.split_once('#') {
+            Some((raw, hash)) => (raw, Some(hash)),
+            None => (raw.as_str(), None),
+        };
+
+        // The query array is authoritative since it also knows which
+        // parameters are disabled
+        let mut url = match &object.query {
+            Some(query) => {
+                let base = raw.split('?').next().unwrap_or_default();
+                let params: Vec<String> = query
+                    .iter()
+                    .filter(|param| !param.disabled.unwrap_or(false))
+                    .map(|param| match param.value_text() {
+                        // A parameter without a value is a bare key
+                        Some(value) => format!("{}={}", param.key.as_deref().unwrap_or_default(), value),
+                        None => param.key.clone().unwrap_or_default(),
+                    })
+                    .collect();
+                if params.is_empty() {
+                    base.to_string()
+                } else {
+                    format!("{}?{}", base, params.join("&"))
+                }
+            }
+            None => raw.to_string(),
+        };
+
+        if let Some(hash) = object.hash.as_deref().or(raw_hash).filter(|hash| !hash.is_empty()) {
+            url.push('#');
+            url.push_str(hash);
+        }
+        url
+    }
+}
+
+/// A URL's host or path, as one string or as segments. Path segments are
+/// strings, or `{"type": ..., "value": ...}` objects.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum UrlParts {
+    Text(String),
+    Segments(Vec<JsonValue>),
+}
+
+impl UrlParts {
+    /// The segments, splitting text on `separator`.
+    pub fn segments(&self, separator: char) -> Vec<String> {
+        match self {
+            UrlParts::Text(text) => text.split(separator).map(str::to_string).collect(),
+            UrlParts::Segments(segments) => segments
+                .iter()
+                .map(|segment| match segment {
+                    JsonValue::String(text) => text.clone(),
+                    JsonValue::Object(object) => {
+                        object.get("value").and_then(JsonValue::as_str).unwrap_or_default().to_string()
+                    }
+                    other => other.to_string(),
+                })
+                .collect(),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Header {
     pub key: String,
-    pub value: String,
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub value: Option<JsonValue>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub disabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<Description>,
+    /// e.g. `text`
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub header_type: Option<String>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
 }
 
+/// A query parameter; a missing or `null` value is sent as a bare key (`?flag`).
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct QueryParam {
-    pub key: String,
-    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub value: Option<JsonValue>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub disabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<Description>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -105,31 +388,293 @@ pub struct Body {
     pub urlencoded: Option<Vec<KeyValue>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub formdata: Option<Vec<KeyValue>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<BodyFile>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub graphql: Option<GraphQlBody>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<BodyOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disabled: Option<bool>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
+}
+
+impl Body {
+    /// The language selected for a raw body: `json`, `xml`, `text`,
+    /// `javascript` or `html`.
+    pub fn raw_language(&self) -> Option<&str> {
+        self.options.as_ref()?.raw.as_ref()?.language.as_deref()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct BodyOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<RawOptions>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RawOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
+}
+
+/// A urlencoded or form-data field. Form-data fields of type `file` name
+/// the files to upload in `src`.
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct KeyValue {
     pub key: String,
-    pub value: String,
+    /// Unset for file fields
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub value: Option<JsonValue>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub disabled: Option<bool>,
+    /// `text` or `file`
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub field_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub src: Option<FileSource>,
+    #[serde(rename = "contentType", skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<Description>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
+}
+
+impl Header {
+    /// The value, or an empty string when it is missing or not text.
+    pub fn value(&self) -> &str {
+        self.value.as_ref().and_then(JsonValue::as_str).unwrap_or_default()
+    }
+}
+
+impl QueryParam {
+    /// The value as text, or `None` for a bare key.
+    pub fn value_text(&self) -> Option<String> {
+        match &self.value {
+            Some(JsonValue::String(text)) => Some(text.clone()),
+            Some(JsonValue::Null) | None => None,
+            Some(other) => Some(other.to_string()),
+        }
+    }
+}
+
+impl KeyValue {
+    /// The value, or an empty string when it is missing or not text.
+    pub fn value(&self) -> &str {
+        self.value.as_ref().and_then(JsonValue::as_str).unwrap_or_default()
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.field_type.as_deref() == Some("file")
+    }
+}
+
+/// One or several file paths; Postman allows multiple files per form field.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(untagged)]
+pub enum FileSource {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl FileSource {
+    pub fn paths(&self) -> Vec<&str> {
+        match self {
+            FileSource::Single(path) => vec![path.as_str()],
+            FileSource::Multiple(paths) => paths.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+/// The file sent as the whole body in `file` mode.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct BodyFile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub src: Option<String>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
+}
+
+/// A GraphQL query; Postman stores the variables as a JSON string.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GraphQlBody {
+    pub query: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variables: Option<String>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// A collection, folder, path or environment variable. Values may be any
+/// JSON value; they are used as text.
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Variable {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     pub key: String,
-    pub value: String,
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub value: Option<JsonValue>,
+    /// `string`, `boolean`, `number`, `any`, or `default` / `secret` in environments
     #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub var_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<Description>,
+    /// Set on collection variables that are switched off
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disabled: Option<bool>,
+    /// Set on environment values; `false` switches them off
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+impl Variable {
+    pub fn is_enabled(&self) -> bool {
+        !self.disabled.unwrap_or(false) && self.enabled.unwrap_or(true)
+    }
+
+    /// The value as text: strings as they are, `null` as nothing and other
+    /// values as JSON.
+    pub fn value_text(&self) -> String {
+        match &self.value {
+            Some(JsonValue::String(text)) => text.clone(),
+            Some(JsonValue::Null) | None => String::new(),
+            Some(other) => other.to_string(),
+        }
+    }
+}
+
+/// A Postman environment or globals file.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct Environment {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default)]
     pub name: String,
+    #[serde(default)]
     pub values: Vec<Variable>,
+    /// `environment` or `globals`
+    #[serde(rename = "_postman_variable_scope", skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(rename = "_postman_exported_at", skip_serializing_if = "Option::is_none")]
+    pub exported_at: Option<String>,
+    #[serde(rename = "_postman_exported_using", skip_serializing_if = "Option::is_none")]
+    pub exported_using: Option<String>,
+    #[serde(flatten)]
+    pub extra: IndexMap<String, JsonValue>,
+}
+
+/// A collection converted from another tool's format, with the environments
+/// that came with it.
+pub struct ImportedCollection {
+    pub collection: PostmanCollection,
+    pub environments: Vec<Environment>,
+    /// Parts of the original that could not be converted
+    pub warnings: Vec<String>,
+}
+
+impl ImportedCollection {
+    /// The environment called `name`.
+    pub fn environment(&self, name: &str) -> Result<Environment> {
+        self.environments
+            .iter()
+            .find(|environment| environment.name == name)
+            .cloned()
+            .ok_or_else(|| {
+                let names: Vec<&str> = self.environments.iter().map(|environment| environment.name.as_str()).collect();
+                anyhow!(
+                    "No environment named '{}'; the collection has: {}",
+                    name,
+                    if names.is_empty() { "none".to_string() } else { names.join(", ") }
+                )
+            })
+    }
+}
+
+/// Postman collection format versions that can be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollectionVersion {
+    V1,
+    V2_0,
+    V2_1,
+}
+
+impl CollectionVersion {
+    /// Detects the format from `info.schema`, or from the structure when
+    /// there is no schema.
+    pub fn detect(collection: &JsonValue) -> Result<Self> {
+        let Some(info) = collection.get("info") else {
+            if collection.get("requests").is_some_and(JsonValue::is_array) {
+                return Ok(CollectionVersion::V1);
+            }
+            return Err(anyhow!(
+                "Not a Postman collection: expected `info` and `item` (v2) or `requests` (v1)"
+            ));
+        };
+
+        match info.get("schema").and_then(JsonValue::as_str) {
+            Some(schema) if schema.contains("/v2.1.") => Ok(CollectionVersion::V2_1),
+            Some(schema) if schema.contains("/v2.0.") => Ok(CollectionVersion::V2_0),
+            Some(schema) if schema.contains("/v1.") => Ok(CollectionVersion::V1),
+            Some(schema) => Err(anyhow!(
+                "Unsupported Postman collection schema {}; v2.1, v2.0 and v1 collections are supported",
+                schema
+            )),
+            None => Ok(CollectionVersion::V2_1),
+        }
+    }
 }
 
 impl PostmanCollection {
+    /// An empty v2.1 collection.
+    pub fn named(name: &str) -> Self {
+        PostmanCollection {
+            info: CollectionInfo {
+                name: name.to_string(),
+                postman_id: None,
+                description: None,
+                version: None,
+                schema: Some(legacy::SCHEMA_V2_1.to_string()),
+                extra: IndexMap::new(),
+            },
+            item: Vec::new(),
+            variable: None,
+            auth: None,
+            event: None,
+            protocol_profile_behavior: None,
+            extra: IndexMap::new(),
+        }
+    }
+
+    /// Parses a v2.1, v2.0 or v1 collection, converting older formats.
+    pub fn from_json(text: &str) -> Result<Self> {
+        let mut value: JsonValue = serde_json::from_str(text).context("Failed to parse collection JSON")?;
+
+        match CollectionVersion::detect(&value)? {
+            CollectionVersion::V1 => value = legacy::convert_v1(&value)?,
+            CollectionVersion::V2_0 => legacy::normalize_v2_0(&mut value),
+            CollectionVersion::V2_1 => {}
+        }
+
+        serde_json::from_value(value.clone()).map_err(|error| {
+            // Items are untagged, so serde only reports that none matched;
+            // find the item that failed and say why
+            let items = value.get("item").and_then(JsonValue::as_array);
+            match items.and_then(|items| invalid_item(items, &mut Vec::new())) {
+                Some(problem) => anyhow!("Failed to parse collection: {}", problem),
+                None => anyhow!("Failed to parse collection: {}", error),
+            }
+        })
+    }
+
     pub fn get_all_requests(&self) -> Vec<&RequestItem> {
         let mut requests = Vec::new();
         self.collect_requests(&self.item, &mut requests);
@@ -144,4 +689,196 @@ impl PostmanCollection {
             }
         }
     }
+
+    /// Like `get_all_requests`, but keeps the folders each request is in so
+    /// settings inherited from them can be resolved.
+    pub fn get_all_request_contexts(&self) -> Vec<RequestContext<'_>> {
+        let mut requests = Vec::new();
+        self.collect_request_contexts(&self.item, &[], &mut requests);
+        requests
+    }
+
+    fn collect_request_contexts<'a>(
+        &self,
+        items: &'a [Item],
+        folders: &[&'a FolderItem],
+        requests: &mut Vec<RequestContext<'a>>,
+    ) {
+        for item in items {
+            match item {
+                Item::Request(req) => requests.push(RequestContext {
+                    item: req,
+                    folders: folders.to_vec(),
+                }),
+                Item::Folder(folder) => {
+                    let mut nested = folders.to_vec();
+                    nested.push(folder);
+                    self.collect_request_contexts(&folder.item, &nested, requests);
+                }
+            }
+        }
+    }
+
+    /// The scripts that run for an event (`prerequest` or `test`) of a
+    /// request: the collection's first, then each folder's from the
+    /// outermost in, then the request's own.
+    pub fn scripts(&self, context: &RequestContext, listen: &str) -> Vec<String> {
+        self.event
+            .iter()
+            .chain(context.folders.iter().filter_map(|folder| folder.event.as_ref()))
+            .chain(context.item.event.iter())
+            .flatten()
+            .filter(|event| event.listen == listen && !event.disabled.unwrap_or(false))
+            .map(|event| event.script.source())
+            .filter(|source| !source.trim().is_empty())
+            .collect()
+    }
+
+    /// The auth that applies to a request: its own, otherwise the nearest
+    /// folder's, otherwise the collection's. `inherit` defers to the parent,
+    /// while `noauth` is returned as-is and stops the inheritance.
+    pub fn effective_auth<'a>(&'a self, context: &RequestContext<'a>) -> Option<&'a Auth> {
+        let request_auth = match &context.item.request {
+            Request::Full(request) => request.auth.as_ref(),
+            Request::Simple(_) => None,
+        };
+
+        request_auth
+            .into_iter()
+            .chain(context.folders.iter().rev().filter_map(|folder| folder.auth.as_ref()))
+            .chain(self.auth.as_ref())
+            .find(|auth| auth.auth_type != "inherit")
+    }
+}
+
+/// Describes the first item in `items` that does not deserialize, by its
+/// path of names.
+fn invalid_item(items: &[JsonValue], path: &mut Vec<String>) -> Option<String> {
+    for item in items {
+        path.push(item.get("name").and_then(JsonValue::as_str).unwrap_or("(unnamed)").to_string());
+
+        let problem = match item.get("item").and_then(JsonValue::as_array) {
+            Some(children) => invalid_item(children, path).or_else(|| {
+                serde_json::from_value::<FolderItem>(item.clone())
+                    .err()
+                    .map(|error| format!("folder {}: {}", path.join("/"), error))
+            }),
+            None => {
+                // The request is untagged too, so try the full form directly
+                let request = item.get("request").filter(|request| request.is_object());
+                request
+                    .and_then(|request| serde_json::from_value::<FullRequest>(request.clone()).err())
+                    .or_else(|| serde_json::from_value::<RequestItem>(item.clone()).err())
+                    .map(|error| format!("request {}: {}", path.join("/"), error))
+            }
+        };
+        if problem.is_some() {
+            return problem;
+        }
+
+        path.pop();
+    }
+    None
+}
+
+impl Auth {
+    /// Looks up a parameter of this auth type, such as `token` for bearer auth.
+    /// Handles both the v2.1 list of key/value pairs and the v2.0 object form.
+    pub fn param(&self, key: &str) -> Option<String> {
+        let value = match self.params.get(&self.auth_type)? {
+            serde_json::Value::Array(pairs) => pairs
+                .iter()
+                .find(|pair| pair.get("key").and_then(|k| k.as_str()) == Some(key))?
+                .get("value")?,
+            serde_json::Value::Object(map) => map.get(key)?,
+            _ => return None,
+        };
+
+        match value {
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Null => None,
+            other => Some(other.to_string()),
+        }
+    }
+}
+
+/// Deserializes a field that is present, even as `null`, to `Some`; with
+/// `#[serde(default)]` only a missing field is `None`.
+fn present<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<JsonValue>, D::Error> {
+    JsonValue::deserialize(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn url(value: JsonValue) -> Url {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn builds_raw_urls_from_the_query_array() {
+        let object = url(json!({
+            "raw": "https://example.com/users?page=1&old=x#top",
+            "query": [
+                { "key": "page", "value": "2" },
+                { "key": "old", "value": "x", "disabled": true },
+                { "key": "flag", "value": null },
+            ],
+        }));
+        assert_eq!(object.raw(), "https://example.com/users?page=2&flag#top");
+
+        let object = url(json!({ "raw": "https://example.com/?a=1#top", "hash": "section" }));
+        assert_eq!(object.raw(), "https://example.com/?a=1#section");
+
+        let object = url(json!({ "raw": "https://example.com/?a=1", "query": [{ "key": "a", "value": "1", "disabled": true }] }));
+        assert_eq!(object.raw(), "https://example.com/");
+
+        assert_eq!(url(json!("https://example.com/#x")).raw(), "https://example.com/#x");
+    }
+
+    #[test]
+    fn keeps_query_params_without_a_key_or_value() {
+        let param: QueryParam = serde_json::from_value(json!({ "value": "orphan" })).unwrap();
+        assert_eq!(param.key, None);
+        assert_eq!(serde_json::to_value(&param).unwrap(), json!({ "value": "orphan" }));
+
+        let param: QueryParam = serde_json::from_value(json!({ "key": "flag", "value": null })).unwrap();
+        assert_eq!(param.value_text(), None);
+        assert_eq!(serde_json::to_value(&param).unwrap(), json!({ "key": "flag", "value": null }));
+
+        let param: QueryParam = serde_json::from_value(json!({ "key": "flag" })).unwrap();
+        assert_eq!(serde_json::to_value(&param).unwrap(), json!({ "key": "flag" }));
+    }
+
+    #[test]
+    fn keeps_null_values() {
+        let request = json!({
+            "method": "POST",
+            "url": "https://example.com",
+            "header": [{ "key": "X-Empty", "value": null }],
+            "body": { "mode": "urlencoded", "urlencoded": [{ "key": "a", "value": null }] },
+        });
+        let parsed: FullRequest = serde_json::from_value(request.clone()).unwrap();
+        assert_eq!(parsed.header.as_ref().unwrap()[0].value(), "");
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), request);
+
+        let response = json!({ "name": "Empty", "code": 204, "body": null });
+        let parsed: ExampleResponse = serde_json::from_value(response.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), response);
+    }
+
+    #[test]
+    fn reads_response_headers_as_a_list_or_text() {
+        let response = json!({ "code": 200, "header": "Content-Type: application/json\nX-Id: 1" });
+        let parsed: ExampleResponse = serde_json::from_value(response.clone()).unwrap();
+        assert!(matches!(&parsed.header, Some(ResponseHeaders::Text(text)) if text.starts_with("Content-Type")));
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), response);
+
+        let response = json!({ "code": 200, "header": [{ "key": "Content-Type", "value": "text/plain" }] });
+        let parsed: ExampleResponse = serde_json::from_value(response.clone()).unwrap();
+        assert!(matches!(&parsed.header, Some(ResponseHeaders::List(headers)) if headers[0].value() == "text/plain"));
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), response);
+    }
 }
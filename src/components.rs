@@ -0,0 +1,436 @@
+//! Extraction of reusable component schemas.
+//!
+//! Object schemas that are structurally identical (same property names and
+//! compatible types) are hoisted into `components.schemas` and replaced with a
+//! `$ref`. Top-level request and response bodies are always hoisted; nested
+//! objects only when they occur more than once. Objects that would get the same
+//! name share one component when one's properties are a subset of the other's.
+
+use indexmap::IndexMap;
+use std::collections::{HashMap, HashSet};
+
+use crate::openapi::{Operation, PathItem};
+use crate::schema::Schema;
+
+const REFERENCE_PREFIX: &str = "#/components/schemas/";
+
+/// Verbs and articles stripped from Postman request names before naming.
+const NAME_STOP_WORDS: &[&str] = &[
+    "get", "list", "create", "update", "delete", "remove", "fetch", "retrieve", "add", "patch", "put",
+    "post", "find", "show", "read", "edit", "replace", "a", "an", "the", "all", "new",
+];
+
+struct Candidate {
+    hint: String,
+    count: usize,
+    top_level: bool,
+    properties: IndexMap<String, String>,
+}
+
+struct Group {
+    name: String,
+    hint: String,
+    properties: IndexMap<String, String>,
+}
+
+/// Hoists shared object schemas out of the operations and returns the
+/// resulting `components.schemas`, sorted by name.
+pub fn extract(paths: &mut IndexMap<String, PathItem>) -> IndexMap<String, Schema> {
+    let mut candidates: IndexMap<String, Candidate> = IndexMap::new();
+    for (path, item) in paths.iter_mut() {
+        for operation in item.operations_mut() {
+            for (hint, schema) in body_schemas(path, operation) {
+                collect(schema, &hint, true, &mut candidates);
+            }
+        }
+    }
+
+    let names = assign_names(&candidates);
+    let mut components: IndexMap<String, Schema> = IndexMap::new();
+    for (path, item) in paths.iter_mut() {
+        for operation in item.operations_mut() {
+            for (_, schema) in body_schemas(path, operation) {
+                rewrite(schema, &names, &mut components);
+            }
+        }
+    }
+
+    components.sort_keys();
+    components
+}
+
+/// Every request and response body schema of an operation, with the name it
+/// would get as a component.
+fn body_schemas<'a>(path: &str, operation: &'a mut Operation) -> Vec<(String, &'a mut Schema)> {
+    let resource = resource_name(path, operation.summary.as_deref());
+    let mut schemas = Vec::new();
+
+    if let Some(request_body) = operation.request_body.as_mut() {
+        for media_type in request_body.content.values_mut() {
+            if let Some(schema) = media_type.schema.as_mut() {
+                schemas.push((format!("{}Request", resource), schema));
+            }
+        }
+    }
+
+    for (status, response) in operation.responses.iter_mut() {
+        let hint = if status.starts_with('4') || status.starts_with('5') || status == "default" {
+            "Error".to_string()
+        } else {
+            resource.clone()
+        };
+        for media_type in response.content.iter_mut().flat_map(|content| content.values_mut()) {
+            if let Some(schema) = media_type.schema.as_mut() {
+                schemas.push((hint.clone(), schema));
+            }
+        }
+    }
+
+    schemas
+}
+
+fn is_object(schema: &Schema) -> bool {
+    schema.schema_type.as_deref() == Some("object")
+        && schema.properties.as_ref().is_some_and(|properties| !properties.is_empty())
+}
+
+/// A key that is equal for structurally compatible schemas: it ignores
+/// `required`, `nullable`, formats and examples, and treats integers as numbers.
+fn shape_key(schema: &Schema) -> String {
+    if let Some(reference) = &schema.reference {
+        return reference.clone();
+    }
+
+    match schema.schema_type.as_deref() {
+        Some("object") => {
+            let mut properties: Vec<String> = schema
+                .properties
+                .iter()
+                .flatten()
+                .map(|(key, property)| format!("{}:{}", key, shape_key(property)))
+                .collect();
+            properties.sort();
+            format!("{{{}}}", properties.join(","))
+        }
+        Some("array") => format!("[{}]", schema.items.as_deref().map(shape_key).unwrap_or_default()),
+        Some("integer") => "number".to_string(),
+        Some(schema_type) => schema_type.to_string(),
+        None => {
+            let mut variants: Vec<String> = schema
+                .one_of
+                .iter()
+                .chain(schema.all_of.iter())
+                .flatten()
+                .map(shape_key)
+                .collect();
+            variants.sort();
+            variants.join("|")
+        }
+    }
+}
+
+fn collect(schema: &Schema, hint: &str, top_level: bool, candidates: &mut IndexMap<String, Candidate>) {
+    if is_object(schema) {
+        let candidate = candidates.entry(shape_key(schema)).or_insert_with(|| Candidate {
+            hint: hint.to_string(),
+            count: 0,
+            top_level,
+            properties: schema
+                .properties
+                .iter()
+                .flatten()
+                .map(|(key, property)| (key.clone(), shape_key(property)))
+                .collect(),
+        });
+        candidate.count += 1;
+        if top_level && !candidate.top_level {
+            candidate.top_level = true;
+            candidate.hint = hint.to_string();
+        }
+    }
+
+    for (key, property) in schema.properties.iter().flatten() {
+        collect(property, &type_name(key), false, candidates);
+    }
+    if let Some(items) = &schema.items {
+        collect(items, hint, top_level, candidates);
+    }
+    for variant in schema.one_of.iter().flatten() {
+        collect(variant, hint, top_level, candidates);
+    }
+}
+
+fn assign_names(candidates: &IndexMap<String, Candidate>) -> HashMap<String, String> {
+    let mut taken: HashSet<String> = HashSet::new();
+    let mut groups: Vec<Group> = Vec::new();
+    let mut names = HashMap::new();
+
+    for (key, candidate) in candidates {
+        if !(candidate.top_level || candidate.count > 1) {
+            continue;
+        }
+
+        if let Some(group) = groups.iter_mut().find(|group| {
+            group.hint == candidate.hint && is_compatible(&group.properties, &candidate.properties)
+        }) {
+            for (property, shape) in &candidate.properties {
+                group.properties.entry(property.clone()).or_insert_with(|| shape.clone());
+            }
+            names.insert(key.clone(), group.name.clone());
+            continue;
+        }
+
+        let base = if candidate.hint.is_empty() { "Object" } else { &candidate.hint };
+        let mut name = base.to_string();
+        let mut suffix = 2;
+        while !taken.insert(name.clone()) {
+            name = format!("{}{}", base, suffix);
+            suffix += 1;
+        }
+        names.insert(key.clone(), name.clone());
+        groups.push(Group {
+            name,
+            hint: candidate.hint.clone(),
+            properties: candidate.properties.clone(),
+        });
+    }
+
+    names
+}
+
+/// Two objects are compatible when one's properties are a subset of the
+/// other's and the properties they share have the same shape.
+fn is_compatible(left: &IndexMap<String, String>, right: &IndexMap<String, String>) -> bool {
+    let (smaller, larger) = if left.len() <= right.len() { (left, right) } else { (right, left) };
+    smaller
+        .iter()
+        .all(|(property, shape)| larger.get(property) == Some(shape))
+}
+
+/// Replaces hoisted objects with references, innermost first, merging every
+/// occurrence into its component.
+fn rewrite(schema: &mut Schema, names: &HashMap<String, String>, components: &mut IndexMap<String, Schema>) {
+    let key = if is_object(schema) { Some(shape_key(schema)) } else { None };
+
+    for property in schema.properties.iter_mut().flat_map(|properties| properties.values_mut()) {
+        rewrite(property, names, components);
+    }
+    if let Some(items) = schema.items.as_mut() {
+        rewrite(items, names, components);
+    }
+    for variant in schema.one_of.iter_mut().flatten() {
+        rewrite(variant, names, components);
+    }
+
+    let Some(name) = key.and_then(|key| names.get(&key)) else {
+        return;
+    };
+
+    let mut component = std::mem::take(schema);
+    let nullable = component.nullable.take();
+    let merged = match components.shift_remove(name) {
+        Some(existing) => existing.merge(component),
+        None => component,
+    };
+    components.insert(name.clone(), merged);
+
+    let reference = Schema::reference(&format!("{}{}", REFERENCE_PREFIX, name));
+    *schema = match nullable {
+        // Siblings of `$ref` are ignored in OpenAPI 3.0, so wrap it
+        Some(true) => Schema {
+            all_of: Some(vec![reference]),
+            nullable,
+            ..Schema::default()
+        },
+        _ => reference,
+    };
+}
+
+/// Names the resource an operation deals with: the last path segment when it
+/// is a plural collection name, otherwise the Postman request name.
+fn resource_name(path: &str, request_name: Option<&str>) -> String {
+    let segment = path
+        .split('/')
+        .rev()
+        .find(|segment| !segment.is_empty() && !segment.starts_with('{') && !is_version(segment));
+
+    let from_request = request_name
+        .map(|name| {
+            let words: Vec<&str> = name
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|word| !word.is_empty())
+                .skip_while(|word| NAME_STOP_WORDS.contains(&word.to_lowercase().as_str()))
+                .collect();
+            type_name(&words.join(" "))
+        })
+        .filter(|name| !name.is_empty());
+
+    match segment {
+        Some(segment) if segment.ends_with('s') || from_request.is_none() => type_name(segment),
+        _ => from_request.unwrap_or_else(|| "Root".to_string()),
+    }
+}
+
+fn is_version(segment: &str) -> bool {
+    segment == "api"
+        || (segment.len() > 1
+            && segment.starts_with(['v', 'V'])
+            && segment[1..].chars().all(|c| c.is_ascii_digit() || c == '.'))
+}
+
+/// Turns a path segment, property key or request name into a singular
+/// PascalCase type name: `user-profiles` becomes `UserProfile`.
+pub fn type_name(text: &str) -> String {
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    let mut name = String::new();
+    for (index, word) in words.iter().enumerate() {
+        let word = if index + 1 == words.len() { singular(word) } else { word.to_string() };
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            name.extend(first.to_uppercase());
+            name.push_str(chars.as_str());
+        }
+    }
+
+    // Type names cannot start with a digit
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+fn singular(word: &str) -> String {
+    let ends_with = |suffix: &str| strip_suffix(word, suffix).is_some();
+    if word.chars().count() <= 3 || ["ss", "us", "is"].into_iter().any(ends_with) {
+        word.to_string()
+    } else if let Some(stem) = strip_suffix(word, "ies") {
+        format!("{}y", stem)
+    } else if ["sses", "shes", "ches", "xes", "zes"].into_iter().any(ends_with) {
+        strip_suffix(word, "es").unwrap_or(word).to_string()
+    } else {
+        strip_suffix(word, "s").unwrap_or(word).to_string()
+    }
+}
+
+/// `word` without an ASCII `suffix`, matched case-insensitively.
+fn strip_suffix<'a>(word: &'a str, suffix: &str) -> Option<&'a str> {
+    let start = word.len().checked_sub(suffix.len())?;
+    let (stem, end) = (word.get(..start)?, word.get(start..)?);
+    end.eq_ignore_ascii_case(suffix).then_some(stem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value as JsonValue};
+
+    fn paths(value: JsonValue) -> IndexMap<String, PathItem> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    /// A response with a JSON body of `schema`.
+    fn responds(schema: JsonValue) -> JsonValue {
+        json!({ "description": "OK", "content": { "application/json": { "schema": schema } } })
+    }
+
+    fn object(properties: JsonValue) -> JsonValue {
+        json!({ "type": "object", "properties": properties })
+    }
+
+    #[test]
+    fn names_types() {
+        let cases = [
+            ("user-profiles", "UserProfile"),
+            ("categories", "Category"),
+            ("addresses", "Address"),
+            ("boxes", "Box"),
+            ("WATCHES", "WATCH"),
+            ("status", "Status"),
+            ("analysis", "Analysis"),
+            ("bus", "Bus"),
+            ("2fa_codes", "_2faCode"),
+            ("cafés", "Café"),
+            ("écoles", "École"),
+            ("ünïcödes", "Ünïcöde"),
+            ("日本語s", "日本語"),
+        ];
+        for (text, name) in cases {
+            assert_eq!(type_name(text), name, "{}", text);
+        }
+    }
+
+    #[test]
+    fn names_resources() {
+        assert_eq!(resource_name("/api/v2/users/{userId}", None), "User");
+        assert_eq!(resource_name("/users/{id}/profile", Some("Get the user profile")), "UserProfile");
+        assert_eq!(resource_name("/users/{id}/profile", None), "Profile");
+        assert_eq!(resource_name("/v1", None), "Root");
+        assert_eq!(resource_name("/", Some("List all")), "Root");
+    }
+
+    #[test]
+    fn hoists_bodies_and_shared_objects() {
+        let address = object(json!({ "street": { "type": "string" }, "city": { "type": "string" } }));
+        let mut paths = paths(json!({
+            "/users": {
+                "get": { "responses": {
+                    "200": responds(json!({ "type": "array", "items": object(json!({ "id": { "type": "integer" }, "address": address })) })),
+                    "404": responds(object(json!({ "error": { "type": "string" } }))),
+                } },
+                "post": {
+                    "requestBody": { "content": { "application/json": { "schema": object(json!({ "name": { "type": "string" } })) } } },
+                    "responses": { "201": { "description": "Created" } },
+                },
+            },
+            "/orders/{orderId}": {
+                "get": { "responses": {
+                    "200": responds(object(json!({ "id": { "type": "number" }, "shipping": address, "note": object(json!({ "text": { "type": "string" } })) }))),
+                    "500": responds(object(json!({ "error": { "type": "string" }, "trace": { "type": "string" } }))),
+                } },
+            },
+        }));
+
+        let components = extract(&mut paths);
+        // Nested objects are hoisted only when they occur more than once, and
+        // named after the first property they were seen in (paths are sorted)
+        let names: Vec<&str> = components.keys().map(String::as_str).collect();
+        assert_eq!(names, ["Error", "Order", "Shipping", "User", "UserRequest"]);
+
+        let paths = serde_json::to_value(&paths).unwrap();
+        let reference = |name: &str| json!({ "$ref": format!("#/components/schemas/{}", name) });
+        let users = &paths["/users"]["get"]["responses"];
+        assert_eq!(users["200"]["content"]["application/json"]["schema"]["items"], reference("User"));
+        assert_eq!(users["404"]["content"]["application/json"]["schema"], reference("Error"));
+        assert_eq!(paths["/users"]["post"]["requestBody"]["content"]["application/json"]["schema"], reference("UserRequest"));
+        assert_eq!(paths["/orders/{orderId}"]["get"]["responses"]["200"]["content"]["application/json"]["schema"], reference("Order"));
+
+        let components = serde_json::to_value(&components).unwrap();
+        assert_eq!(components["User"]["properties"]["address"], reference("Shipping"));
+        assert_eq!(components["Order"]["properties"]["shipping"], reference("Shipping"));
+        assert_eq!(components["Order"]["properties"]["note"]["properties"]["text"], json!({ "type": "string" }));
+        // Compatible error bodies share one component
+        assert!(components["Error"]["properties"]["trace"].is_object());
+    }
+
+    #[test]
+    fn wraps_nullable_references() {
+        let mut paths = paths(json!({
+            "/teams": { "get": { "responses": {
+                "200": responds(object(json!({ "lead": { "type": "object", "nullable": true, "properties": { "id": { "type": "integer" } } } }))),
+            } } },
+            "/members": { "get": { "responses": { "200": responds(object(json!({ "id": { "type": "integer" } }))) } } },
+        }));
+
+        let components = serde_json::to_value(extract(&mut paths)).unwrap();
+        // `$ref` siblings are ignored in OpenAPI 3.0
+        assert_eq!(
+            components["Team"]["properties"]["lead"],
+            json!({ "allOf": [{ "$ref": "#/components/schemas/Member" }], "nullable": true })
+        );
+        assert_eq!(components["Member"], object(json!({ "id": { "type": "integer" } })));
+    }
+}
@@ -0,0 +1,759 @@
+//! Converts `curl` command lines, such as a browser's "Copy as cURL", into
+//! collection requests.
+//!
+//! Commands are split with POSIX shell quoting: single, double and `$'...'`
+//! quotes, backslash escapes and `\` line continuations. Several commands
+//! can be given, one per line or separated by `;` or `&&`; commands piped
+//! into others (`curl ... | jq`) keep only the `curl` part.
+//!
+//! The method, URL, headers, data (`-d`, `--data-raw`, `--data-binary`,
+//! `--data-urlencode`, `--json`, `-G`), forms (`-F`), `-T` uploads, `-u`
+//! basic or digest credentials, `--oauth2-bearer`, cookies and the user
+//! agent and referer are converted. `-k` is kept as Postman's `strictSSL`
+//! setting. `--compressed` drops the `Accept-Encoding` header browsers add,
+//! since the runner does not decompress responses. Output, progress, retry
+//! and connection options do not change the request and are ignored. Other
+//! options are errors, as they may take an argument.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Value as JsonValue};
+use std::path::{Path, PathBuf};
+use url::form_urlencoded;
+
+use crate::collection::RequestItem;
+
+/// Short options and the long options they stand for.
+const SHORT_OPTIONS: &[(char, &str)] = &[
+    ('0', "http1.0"),
+    ('4', "ipv4"),
+    ('6', "ipv6"),
+    ('A', "user-agent"),
+    ('b', "cookie"),
+    ('c', "cookie-jar"),
+    ('C', "continue-at"),
+    ('d', "data"),
+    ('D', "dump-header"),
+    ('e', "referer"),
+    ('E', "cert"),
+    ('f', "fail"),
+    ('F', "form"),
+    ('g', "globoff"),
+    ('G', "get"),
+    ('H', "header"),
+    ('i', "include"),
+    ('I', "head"),
+    ('k', "insecure"),
+    ('L', "location"),
+    ('m', "max-time"),
+    ('N', "no-buffer"),
+    ('o', "output"),
+    ('O', "remote-name"),
+    ('r', "range"),
+    ('s', "silent"),
+    ('S', "show-error"),
+    ('T', "upload-file"),
+    ('u', "user"),
+    ('v', "verbose"),
+    ('w', "write-out"),
+    ('x', "proxy"),
+    ('X', "request"),
+];
+
+/// Options that take an argument.
+const VALUE_OPTIONS: &[&str] = &[
+    "cacert",
+    "capath",
+    "cert",
+    "cert-type",
+    "connect-timeout",
+    "continue-at",
+    "cookie",
+    "cookie-jar",
+    "data",
+    "data-ascii",
+    "data-binary",
+    "data-raw",
+    "data-urlencode",
+    "dump-header",
+    "form",
+    "form-string",
+    "header",
+    "interface",
+    "json",
+    "keepalive-time",
+    "key",
+    "key-type",
+    "limit-rate",
+    "max-filesize",
+    "max-redirs",
+    "max-time",
+    "oauth2-bearer",
+    "output",
+    "proxy",
+    "proxy-user",
+    "range",
+    "referer",
+    "request",
+    "resolve",
+    "retry",
+    "retry-delay",
+    "retry-max-time",
+    "trace",
+    "trace-ascii",
+    "upload-file",
+    "url",
+    "url-query",
+    "user",
+    "user-agent",
+    "write-out",
+];
+
+/// Options that do not change the request.
+const IGNORED_OPTIONS: &[&str] = &[
+    "cacert",
+    "capath",
+    "cert",
+    "cert-type",
+    "connect-timeout",
+    "continue-at",
+    "cookie-jar",
+    "dump-header",
+    "fail",
+    "globoff",
+    "http1.0",
+    "http1.1",
+    "http2",
+    "http2-prior-knowledge",
+    "include",
+    "interface",
+    "ipv4",
+    "ipv6",
+    "keepalive-time",
+    "key",
+    "key-type",
+    "limit-rate",
+    "location",
+    "max-filesize",
+    "max-redirs",
+    "max-time",
+    "no-buffer",
+    "output",
+    "path-as-is",
+    "progress-bar",
+    "proxy",
+    "proxy-user",
+    "range",
+    "remote-name",
+    "resolve",
+    "retry",
+    "retry-delay",
+    "retry-max-time",
+    "show-error",
+    "silent",
+    "trace",
+    "trace-ascii",
+    "verbose",
+    "write-out",
+];
+
+/// Converts one or more `curl` commands into requests, named by their
+/// method and URL. The files of `-F name=<file` fields are read relative to
+/// `dir`.
+pub fn parse(text: &str, dir: &Path) -> Result<Vec<RequestItem>> {
+    let commands = split_commands(text)?;
+    if commands.is_empty() {
+        bail!("No curl command found");
+    }
+    commands
+        .iter()
+        .enumerate()
+        .map(|(index, words)| {
+            parse_command(words, dir).with_context(|| format!("Failed to convert curl command {}", index + 1))
+        })
+        .collect()
+}
+
+/// The options of one command, as given.
+#[derive(Default)]
+struct Command {
+    method: Option<String>,
+    url: Option<String>,
+    query: Vec<String>,
+    headers: Vec<(String, String)>,
+    /// `-d` style data, joined with `&`
+    data: Vec<String>,
+    /// A `-d @file` or `--data-binary @file` body
+    data_file: Option<String>,
+    json: bool,
+    form: Vec<JsonValue>,
+    upload: Option<String>,
+    user: Option<String>,
+    digest: bool,
+    bearer: Option<String>,
+    get: bool,
+    head: bool,
+    insecure: bool,
+    compressed: bool,
+    /// Directory of files read into form fields
+    dir: PathBuf,
+}
+
+fn parse_command(words: &[String], dir: &Path) -> Result<RequestItem> {
+    let mut command = Command {
+        dir: dir.to_path_buf(),
+        ..Default::default()
+    };
+    let mut args = words.iter().skip(1);
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            for url in args.by_ref() {
+                command.url = Some(url.clone());
+            }
+        } else if let Some(name) = arg.strip_prefix("--") {
+            let value = if VALUE_OPTIONS.contains(&name) {
+                Some(args.next().ok_or_else(|| anyhow!("--{} needs an argument", name))?.clone())
+            } else {
+                None
+            };
+            command.option(name, value)?;
+        } else if let Some(flags) = arg.strip_prefix('-').filter(|flags| !flags.is_empty()) {
+            // Short options can be grouped (`-sSL`) and take their argument
+            // from the rest of the word (`-XPOST`)
+            for (index, flag) in flags.char_indices() {
+                let name = SHORT_OPTIONS
+                    .iter()
+                    .find(|(short, _)| *short == flag)
+                    .map(|(_, name)| *name)
+                    .ok_or_else(|| anyhow!("Unsupported curl option -{}", flag))?;
+                if VALUE_OPTIONS.contains(&name) {
+                    let rest = &flags[index + flag.len_utf8()..];
+                    let value = match rest {
+                        "" => args.next().ok_or_else(|| anyhow!("-{} needs an argument", flag))?.clone(),
+                        rest => rest.to_string(),
+                    };
+                    command.option(name, Some(value))?;
+                    break;
+                }
+                command.option(name, None)?;
+            }
+        } else {
+            command.url = Some(arg.clone());
+        }
+    }
+
+    command.into_item()
+}
+
+impl Command {
+    fn option(&mut self, name: &str, value: Option<String>) -> Result<()> {
+        let value = value.unwrap_or_default();
+        match name {
+            "request" => self.method = Some(value.to_uppercase()),
+            "url" => self.url = Some(value),
+            "url-query" => self.query.push(value),
+            "header" => {
+                if value.starts_with('@') {
+                    bail!("reading headers from a file (-H @file) is not supported");
+                }
+                // `Name;` sends an empty header, `Name:` removes a default one
+                if let Some(key) = value.strip_suffix(';').filter(|key| !key.contains(':')) {
+                    self.headers.push((key.trim().to_string(), String::new()));
+                } else if let Some((key, value)) = value.split_once(':').filter(|(_, value)| !value.trim().is_empty()) {
+                    self.headers.push((key.trim().to_string(), value.trim().to_string()));
+                }
+            }
+            "user-agent" => self.headers.push(("User-Agent".to_string(), value)),
+            "referer" => self.headers.push(("Referer".to_string(), value)),
+            "cookie" => {
+                if !value.contains('=') {
+                    bail!("reading cookies from a file (-b {}) is not supported", value);
+                }
+                self.headers.push(("Cookie".to_string(), value));
+            }
+            "data" | "data-ascii" | "data-binary" => match value.strip_prefix('@') {
+                Some(path) => self.data_file = Some(path.to_string()),
+                None => self.data.push(value),
+            },
+            "data-raw" => self.data.push(value),
+            "data-urlencode" => self.data.push(url_encode_data(&value)?),
+            "json" => {
+                self.json = true;
+                match value.strip_prefix('@') {
+                    Some(path) => self.data_file = Some(path.to_string()),
+                    None => self.data.push(value),
+                }
+            }
+            "form" => self.form.push(form_field(&value, true, &self.dir)?),
+            "form-string" => self.form.push(form_field(&value, false, &self.dir)?),
+            "upload-file" => self.upload = Some(value),
+            "user" => self.user = Some(value),
+            "digest" => self.digest = true,
+            "basic" => self.digest = false,
+            "oauth2-bearer" => self.bearer = Some(value),
+            "get" => self.get = true,
+            "head" => self.head = true,
+            "insecure" => self.insecure = true,
+            "compressed" => self.compressed = true,
+            ignored if IGNORED_OPTIONS.contains(&ignored) => {}
+            other => bail!("Unsupported curl option --{}", other),
+        }
+        Ok(())
+    }
+
+    fn into_item(mut self) -> Result<RequestItem> {
+        let url = self.url.take().ok_or_else(|| anyhow!("the command has no URL"))?;
+        // curl defaults to http:// for URLs without a scheme
+        let mut url = if url.contains("://") { url } else { format!("http://{}", url) };
+
+        let mut query = std::mem::take(&mut self.query);
+        if self.get {
+            query.append(&mut self.data);
+        }
+        for parameter in query {
+            let separator = if url.contains('?') { '&' } else { '?' };
+            url = format!("{}{}{}", url, separator, parameter);
+        }
+
+        // The runner reads bodies as they arrive, so they must not be compressed
+        if self.compressed {
+            self.headers.retain(|(key, _)| !key.eq_ignore_ascii_case("accept-encoding"));
+        }
+        if self.json {
+            for (key, value) in [("Content-Type", "application/json"), ("Accept", "application/json")] {
+                if !self.has_header(key) {
+                    self.headers.push((key.to_string(), value.to_string()));
+                }
+            }
+        }
+        let body = self.body();
+
+        let method = match &self.method {
+            Some(method) => method.clone(),
+            None if self.head => "HEAD".to_string(),
+            None if self.upload.is_some() => "PUT".to_string(),
+            None if body.is_some() => "POST".to_string(),
+            None => "GET".to_string(),
+        };
+
+        let header: Vec<JsonValue> = self
+            .headers
+            .iter()
+            .map(|(key, value)| json!({ "key": key, "value": value }))
+            .collect();
+        let mut request = json!({ "method": method, "header": header, "url": { "raw": url } });
+        if let Some(body) = body {
+            request["body"] = body;
+        }
+        if let Some(auth) = self.auth() {
+            request["auth"] = auth;
+        }
+
+        let mut item = json!({ "name": format!("{} {}", method, url), "request": request });
+        if self.insecure {
+            item["protocolProfileBehavior"] = json!({ "strictSSL": false });
+        }
+        serde_json::from_value(item).context("Failed to build the request")
+    }
+
+    fn has_header(&self, name: &str) -> bool {
+        self.headers.iter().any(|(key, _)| key.eq_ignore_ascii_case(name))
+    }
+
+    /// The body: an upload, a form, a data file, or the data as form fields
+    /// or raw text depending on the `Content-Type` (curl sends data as a
+    /// form unless told otherwise).
+    fn body(&mut self) -> Option<JsonValue> {
+        if let Some(path) = &self.upload {
+            return Some(json!({ "mode": "file", "file": { "src": path } }));
+        }
+        if !self.form.is_empty() {
+            return Some(json!({ "mode": "formdata", "formdata": self.form }));
+        }
+
+        let content_type = self
+            .headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+            .map(|(_, value)| value.to_lowercase());
+        if let Some(path) = &self.data_file {
+            if content_type.is_none() {
+                self.headers.push(("Content-Type".to_string(), "application/x-www-form-urlencoded".to_string()));
+            }
+            return Some(json!({ "mode": "file", "file": { "src": path } }));
+        }
+        if self.data.is_empty() {
+            return None;
+        }
+
+        let data = self.data.join("&");
+        let is_form = content_type
+            .as_deref()
+            .is_none_or(|content_type| content_type.starts_with("application/x-www-form-urlencoded"));
+        let is_pairs = data
+            .split('&')
+            .all(|pair| pair.split_once('=').is_some_and(|(key, _)| !key.is_empty()));
+        if is_form && is_pairs {
+            let fields: Vec<JsonValue> = form_urlencoded::parse(data.as_bytes())
+                .map(|(key, value)| json!({ "key": key, "value": value, "type": "text" }))
+                .collect();
+            return Some(json!({ "mode": "urlencoded", "urlencoded": fields }));
+        }
+
+        // Data that is not key/value pairs is sent as it is
+        let language = match content_type.as_deref() {
+            None => {
+                self.headers.push(("Content-Type".to_string(), "application/x-www-form-urlencoded".to_string()));
+                "text"
+            }
+            Some(mime) if mime.contains("json") => "json",
+            Some(mime) if mime.contains("xml") => "xml",
+            Some(mime) if mime.contains("html") => "html",
+            Some(mime) if mime.contains("javascript") => "javascript",
+            Some(_) => "text",
+        };
+        Some(json!({ "mode": "raw", "raw": data, "options": { "raw": { "language": language } } }))
+    }
+
+    fn auth(&self) -> Option<JsonValue> {
+        if let Some(token) = &self.bearer {
+            return Some(json!({
+                "type": "bearer",
+                "bearer": [{ "key": "token", "value": token, "type": "string" }],
+            }));
+        }
+        let user = self.user.as_ref()?;
+        let (username, password) = user.split_once(':').unwrap_or((user, ""));
+        let auth_type = if self.digest { "digest" } else { "basic" };
+        Some(json!({
+            "type": auth_type,
+            auth_type: [
+                { "key": "username", "value": username, "type": "string" },
+                { "key": "password", "value": password, "type": "string" },
+            ],
+        }))
+    }
+}
+
+/// Encodes a `--data-urlencode` argument the way curl does: `content`,
+/// `=content` and `name=content` encode the content.
+fn url_encode_data(value: &str) -> Result<String> {
+    let encode = |content: &str| form_urlencoded::byte_serialize(content.as_bytes()).collect::<String>();
+    // Whichever of `=` and `@` comes first separates the name
+    Ok(match value.find(['=', '@']) {
+        Some(index) if value[index..].starts_with('@') => {
+            bail!("reading --data-urlencode content from a file is not supported")
+        }
+        Some(0) => encode(&value[1..]),
+        Some(index) => format!("{}={}", &value[..index], encode(&value[index + 1..])),
+        None => encode(value),
+    })
+}
+
+/// A `-F` field: `name=value`, `name=@path` (a file upload) or `name=<path`
+/// (the file's content as the value, read relative to `dir`), with optional
+/// `;type=` and `;filename=`.
+fn form_field(value: &str, files: bool, dir: &Path) -> Result<JsonValue> {
+    let (key, content) = value.split_once('=').unwrap_or((value, ""));
+    let mut parts = content.split(';');
+    let first = parts.next().unwrap_or_default();
+    let content_type = parts.find_map(|part| part.trim().strip_prefix("type="));
+
+    let mut field = if !files {
+        json!({ "key": key, "value": content, "type": "text" })
+    } else if let Some(path) = first.strip_prefix('@') {
+        json!({ "key": key, "type": "file", "src": path })
+    } else if let Some(path) = first.strip_prefix('<') {
+        let text = std::fs::read_to_string(dir.join(path))
+            .with_context(|| format!("Failed to read form field {} from {}", key, path))?;
+        json!({ "key": key, "value": text, "type": "text" })
+    } else {
+        json!({ "key": key, "value": first, "type": "text" })
+    };
+    if let Some(content_type) = content_type.filter(|_| files) {
+        field["contentType"] = json!(content_type);
+    }
+    Ok(field)
+}
+
+/// Splits shell text into the words of each `curl` command.
+fn split_commands(text: &str) -> Result<Vec<Vec<String>>> {
+    let mut commands = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut word: Option<String> = None;
+    // Commands after a pipe read curl's output and are dropped
+    let mut piped = false;
+    let mut chars = text.chars().peekable();
+
+    let mut end_command = |words: &mut Vec<String>, piped: bool| -> Result<()> {
+        if let Some(first) = words.first() {
+            let program = first.rsplit(['/', '\\']).next().unwrap_or_default();
+            if program == "curl" || program == "curl.exe" {
+                commands.push(std::mem::take(words));
+            } else if !piped {
+                bail!("Expected a curl command, found `{}`", first);
+            }
+        }
+        words.clear();
+        Ok(())
+    };
+
+    while let Some(character) = chars.next() {
+        match character {
+            ' ' | '\t' | '\r' => words.extend(word.take()),
+            '\n' | ';' | '|' | '&' => {
+                words.extend(word.take());
+                if character == '&' && chars.peek() != Some(&'&') {
+                    bail!("Background commands (`&`) are not supported");
+                }
+                if matches!(character, '&' | '|') && chars.peek() == Some(&character) {
+                    chars.next();
+                    end_command(&mut words, piped)?;
+                    piped = false;
+                } else {
+                    end_command(&mut words, piped)?;
+                    piped = character == '|';
+                }
+            }
+            '#' if word.is_none() => {
+                while chars.next_if(|&next| next != '\n').is_some() {}
+            }
+            '\\' => match chars.next() {
+                // Line continuation
+                Some('\n') => {}
+                Some('\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                }
+                Some(escaped) => word.get_or_insert_with(String::new).push(escaped),
+                None => {}
+            },
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(character) => word.push(character),
+                        None => bail!("Unclosed ' quote"),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped @ ('"' | '\\' | '$' | '`')) => word.push(escaped),
+                            Some('\n') => {}
+                            Some(other) => {
+                                word.push('\\');
+                                word.push(other);
+                            }
+                            None => bail!("Unclosed \" quote"),
+                        },
+                        Some(character) => word.push(character),
+                        None => bail!("Unclosed \" quote"),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                ansi_c_quoted(&mut chars, word.get_or_insert_with(String::new))?;
+            }
+            character => word.get_or_insert_with(String::new).push(character),
+        }
+    }
+    words.extend(word.take());
+    end_command(&mut words, piped)?;
+
+    Ok(commands)
+}
+
+/// Reads the rest of a `$'...'` string, which has C style escapes.
+fn ansi_c_quoted(chars: &mut std::iter::Peekable<std::str::Chars>, word: &mut String) -> Result<()> {
+    loop {
+        match chars.next() {
+            Some('\'') => return Ok(()),
+            Some('\\') => {
+                let escaped = chars.next().ok_or_else(|| anyhow!("Unclosed $' quote"))?;
+                match escaped {
+                    'n' => word.push('\n'),
+                    't' => word.push('\t'),
+                    'r' => word.push('\r'),
+                    'a' => word.push('\x07'),
+                    'b' => word.push('\x08'),
+                    'e' | 'E' => word.push('\x1b'),
+                    'f' => word.push('\x0c'),
+                    'v' => word.push('\x0b'),
+                    'x' | 'u' | 'U' => {
+                        let length = match escaped {
+                            'x' => 2,
+                            'u' => 4,
+                            _ => 8,
+                        };
+                        let mut digits = String::new();
+                        while digits.len() < length {
+                            match chars.next_if(char::is_ascii_hexdigit) {
+                                Some(digit) => digits.push(digit),
+                                None => break,
+                            }
+                        }
+                        let decoded = u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32);
+                        match decoded {
+                            Some(decoded) => word.push(decoded),
+                            None => {
+                                word.push('\\');
+                                word.push(escaped);
+                                word.push_str(&digits);
+                            }
+                        }
+                    }
+                    other => word.push(other),
+                }
+            }
+            Some(character) => word.push(character),
+            None => bail!("Unclosed $' quote"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_one(text: &str) -> JsonValue {
+        let items = parse(text, Path::new("")).unwrap();
+        assert_eq!(items.len(), 1);
+        serde_json::to_value(&items[0]).unwrap()
+    }
+
+    fn headers(item: &JsonValue) -> Vec<(&str, &str)> {
+        item["request"]["header"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|header| (header["key"].as_str().unwrap(), header["value"].as_str().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn converts_a_copied_browser_request() {
+        let item = parse_one(
+            "curl 'https://api.example.com/users?page=1' \\\n  \
+             -H 'accept: application/json' \\\n  \
+             -H 'accept-encoding: gzip, deflate, br' \\\n  \
+             -H 'content-type: application/json' \\\n  \
+             --data-raw '{\"name\":\"Ada O'\\''Brien\"}' \\\n  \
+             --compressed",
+        );
+        assert_eq!(item["name"], "POST https://api.example.com/users?page=1");
+        assert_eq!(item["request"]["method"], "POST");
+        assert_eq!(headers(&item), [("accept", "application/json"), ("content-type", "application/json")]);
+        assert_eq!(item["request"]["body"]["raw"], r#"{"name":"Ada O'Brien"}"#);
+        assert_eq!(item["request"]["body"]["options"]["raw"]["language"], "json");
+    }
+
+    #[test]
+    fn splits_commands() {
+        let items = parse(
+            "curl example.com/a; curl -sSL -XDELETE example.com/b && curl \"example.com/$'c'\" | jq .\ncurl $'example.com/d\\x21'",
+            Path::new(""),
+        )
+        .unwrap();
+        let lines: Vec<String> = items
+            .iter()
+            .map(|item| serde_json::to_value(item).unwrap()["name"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "GET http://example.com/a",
+                "DELETE http://example.com/b",
+                "GET http://example.com/$'c'",
+                "GET http://example.com/d!",
+            ]
+        );
+    }
+
+    #[test]
+    fn converts_data_options() {
+        let item = parse_one("curl -G https://example.com/search -d q=rust --data-urlencode 'tag=a b'");
+        assert_eq!(item["request"]["method"], "GET");
+        assert_eq!(item["request"]["url"]["raw"], "https://example.com/search?q=rust&tag=a+b");
+        assert!(item["request"].get("body").is_none());
+
+        let item = parse_one("curl https://example.com/form -d a=1 -d 'b=two%20words'");
+        assert_eq!(
+            item["request"]["body"]["urlencoded"],
+            json!([
+                { "key": "a", "value": "1", "type": "text" },
+                { "key": "b", "value": "two words", "type": "text" },
+            ])
+        );
+
+        let item = parse_one("curl https://example.com --json '{\"a\":1}'");
+        assert_eq!(headers(&item), [("Content-Type", "application/json"), ("Accept", "application/json")]);
+
+        let item = parse_one("curl -T report.csv https://example.com/upload");
+        assert_eq!(item["request"]["method"], "PUT");
+        assert_eq!(item["request"]["body"]["file"]["src"], "report.csv");
+    }
+
+    #[test]
+    fn converts_forms() {
+        let dir = std::env::temp_dir().join(format!("harbinger-curl-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("note.txt"), "from a file").unwrap();
+
+        let items = parse(
+            "curl https://example.com/upload -F 'title=Report' -F 'file=@data.csv;type=text/csv' -F 'note=<note.txt' --form-string 'raw=@literal'",
+            &dir,
+        )
+        .unwrap();
+        let missing = parse("curl https://example.com -F 'note=<missing.txt'", &dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let item = serde_json::to_value(&items[0]).unwrap();
+        assert_eq!(
+            item["request"]["body"]["formdata"],
+            json!([
+                { "key": "title", "value": "Report", "type": "text" },
+                { "key": "file", "type": "file", "src": "data.csv", "contentType": "text/csv" },
+                { "key": "note", "value": "from a file", "type": "text" },
+                { "key": "raw", "value": "@literal", "type": "text" },
+            ])
+        );
+        let Err(error) = missing else {
+            panic!("read a missing file");
+        };
+        let message = format!("{:#}", error);
+        assert!(message.starts_with("Failed to convert curl command 1: Failed to read form field note from missing.txt"));
+    }
+
+    #[test]
+    fn converts_auth_and_settings() {
+        let item = parse_one("curl -k --digest -u ada:secret https://example.com");
+        assert_eq!(item["request"]["auth"]["type"], "digest");
+        assert_eq!(item["request"]["auth"]["digest"][1]["value"], "secret");
+        assert_eq!(item["protocolProfileBehavior"], json!({ "strictSSL": false }));
+
+        let item = parse_one("curl --oauth2-bearer abc -A agent/1.0 -b 'a=1' https://example.com");
+        assert_eq!(item["request"]["auth"]["bearer"][0]["value"], "abc");
+        assert_eq!(headers(&item), [("User-Agent", "agent/1.0"), ("Cookie", "a=1")]);
+    }
+
+    #[test]
+    fn rejects_what_it_cannot_convert() {
+        let error = |text: &str| match parse(text, Path::new("")) {
+            Ok(_) => panic!("converted {}", text),
+            Err(error) => format!("{:#}", error),
+        };
+        assert_eq!(error("  \n"), "No curl command found");
+        assert_eq!(error("ls -la"), "Expected a curl command, found `ls`");
+        assert_eq!(error("curl -s"), "Failed to convert curl command 1: the command has no URL");
+        assert_eq!(
+            error("curl --proxy-magic https://example.com"),
+            "Failed to convert curl command 1: Unsupported curl option --proxy-magic"
+        );
+    }
+}
@@ -0,0 +1,617 @@
+//! Postman's dynamic variables (`{{$guid}}`, `{{$timestamp}}`,
+//! `{{$randomEmail}}`, ...), which produce a new value every time they are
+//! used.
+//!
+//! Values come from a single random number generator per run, so giving it a
+//! seed makes runs reproducible. Timestamps and the random dates around them
+//! still follow the clock.
+
+use chrono::{DateTime, Duration, Utc};
+use fake::faker::address::en::{
+    BuildingNumber, CityName, CountryCode, CountryName, StreetName,
+};
+use fake::faker::company::en::{
+    Bs, BsAdj, BsNoun, BsVerb, Buzzword, BuzzwordMiddle, BuzzwordTail, CatchPhrase, CompanyName,
+    CompanySuffix,
+};
+use fake::faker::currency::en::{CurrencyCode, CurrencyName, CurrencySymbol};
+use fake::faker::filesystem::en::{DirPath, FileExtension, FileName, FilePath, MimeType};
+use fake::faker::finance::en::Bic;
+use fake::faker::internet::en::{
+    DomainSuffix, FreeEmail, IPv4, IPv6, MACAddress, UserAgent, Username,
+};
+use fake::faker::lorem::en::{Paragraph, Paragraphs, Sentence, Sentences, Word, Words};
+use fake::faker::name::en::{FirstName, LastName, Name, Suffix, Title};
+use fake::faker::number::en::NumberWithFormat;
+use fake::Fake;
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+use std::sync::{Arc, Mutex};
+
+const ALPHANUMERIC: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+const COLORS: &[&str] = &[
+    "red", "green", "blue", "yellow", "purple", "mint green", "teal", "white", "black", "orange",
+    "pink", "grey", "maroon", "violet", "turquoise", "tan", "sky blue", "salmon", "plum",
+    "orchid", "olive", "magenta", "lime", "ivory", "indigo", "gold", "fuchsia", "cyan", "azure",
+    "lavender", "silver",
+];
+
+const ABBREVIATIONS: &[&str] = &[
+    "ADP", "AGP", "AI", "API", "ASCII", "CLI", "COM", "CSS", "DNS", "DRAM", "EXE", "FTP", "GB",
+    "HDD", "HEX", "HTTP", "IB", "IP", "JBOD", "JSON", "OCR", "PCI", "PNG", "RAM", "RSS", "SAS",
+    "SCSI", "SDD", "SMS", "SMTP", "SQL", "SSD", "SSL", "TCP", "THX", "TLS", "UTF", "XML", "XSS",
+];
+
+const ADJECTIVES: &[&str] = &[
+    "auxiliary", "primary", "back-end", "digital", "open-source", "virtual", "cross-platform",
+    "redundant", "online", "haptic", "multi-byte", "bluetooth", "wireless", "1080p", "neural",
+    "optical", "solid state", "mobile",
+];
+
+const NOUNS: &[&str] = &[
+    "driver", "protocol", "bandwidth", "panel", "microchip", "program", "port", "card", "array",
+    "interface", "system", "sensor", "firewall", "hard drive", "pixel", "alarm", "feed",
+    "monitor", "application", "transmitter", "bus", "circuit", "capacitor", "matrix",
+];
+
+const VERBS: &[&str] = &[
+    "back up", "bypass", "hack", "override", "compress", "copy", "navigate", "index", "connect",
+    "generate", "quantify", "calculate", "synthesize", "input", "transmit", "program", "reboot",
+    "parse",
+];
+
+const INGVERBS: &[&str] = &[
+    "backing up", "bypassing", "hacking", "overriding", "compressing", "copying", "navigating",
+    "indexing", "connecting", "generating", "quantifying", "calculating", "synthesizing",
+    "transmitting", "programming", "parsing",
+];
+
+/// Hacker phrase templates, filled in by [`DynamicVariables::phrase`]
+const PHRASES: &[&str] = &[
+    "If we {verb} the {noun}, we can get to the {abbreviation} {noun} through the {adjective} {abbreviation} {noun}!",
+    "We need to {verb} the {adjective} {abbreviation} {noun}!",
+    "Try to {verb} the {abbreviation} {noun}, maybe it will {verb} the {adjective} {noun}!",
+    "You can't {verb} the {noun} without {ingverb} the {adjective} {abbreviation} {noun}!",
+    "Use the {adjective} {abbreviation} {noun}, then you can {verb} the {adjective} {noun}!",
+    "The {abbreviation} {noun} is down, {verb} the {adjective} {noun} so we can {verb} the {abbreviation} {noun}!",
+    "{ingverb} the {noun} won't do anything, we need to {verb} the {adjective} {abbreviation} {noun}!",
+    "I'll {verb} the {adjective} {abbreviation} {noun}, that should {noun} the {abbreviation} {noun}!",
+];
+
+const LOCALES: &[&str] = &[
+    "af", "ar", "az", "cs", "de", "el", "en", "es", "fa", "fi", "fr", "ge", "he", "hr", "hu",
+    "hy", "id", "it", "ja", "ko", "lv", "nb", "ne", "nl", "pl", "pt", "ro", "ru", "sk", "sv",
+    "tr", "uk", "vi", "zh",
+];
+
+const JOB_AREAS: &[&str] = &[
+    "Solutions", "Program", "Brand", "Security", "Research", "Marketing", "Directives",
+    "Implementation", "Integration", "Functionality", "Response", "Paradigm", "Tactics",
+    "Identity", "Markets", "Group", "Division", "Applications", "Optimization", "Operations",
+    "Infrastructure", "Intranet", "Communications", "Web", "Branding", "Quality", "Assurance",
+    "Mobility", "Accounts", "Factors", "Creative", "Configuration", "Accountability",
+    "Interactions", "Usability", "Metrics",
+];
+
+const JOB_DESCRIPTORS: &[&str] = &[
+    "Lead", "Senior", "Direct", "Corporate", "Dynamic", "Future", "Product", "National",
+    "Regional", "District", "Central", "Global", "Customer", "Investor", "Human", "Legacy",
+    "Forward", "Internal", "Chief", "Principal",
+];
+
+const JOB_TYPES: &[&str] = &[
+    "Supervisor", "Associate", "Executive", "Liaison", "Officer", "Manager", "Engineer",
+    "Specialist", "Director", "Coordinator", "Administrator", "Architect", "Analyst", "Designer",
+    "Planner", "Orchestrator", "Technician", "Developer", "Producer", "Consultant", "Assistant",
+    "Facilitator", "Agent", "Representative", "Strategist",
+];
+
+const BANK_ACCOUNT_TYPES: &[&str] = &[
+    "Checking", "Savings", "Money Market", "Investment", "Home Loan", "Credit Card", "Auto Loan",
+    "Personal Loan",
+];
+
+const TRANSACTION_TYPES: &[&str] = &["deposit", "withdrawal", "payment", "invoice"];
+
+/// Countries and the number of digits in their IBAN account numbers
+const IBAN_FORMATS: &[(&str, usize)] = &[
+    ("AT", 16),
+    ("BE", 12),
+    ("DE", 18),
+    ("ES", 20),
+    ("FI", 14),
+    ("PL", 24),
+    ("PT", 21),
+];
+
+const BASE58: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+const DATABASE_COLUMNS: &[&str] = &[
+    "id", "title", "name", "email", "phone", "token", "group", "category", "password",
+    "comment", "avatar", "status", "createdAt", "updatedAt",
+];
+
+const DATABASE_TYPES: &[&str] = &[
+    "int", "varchar", "text", "date", "datetime", "tinyint", "time", "timestamp", "smallint",
+    "mediumint", "bigint", "decimal", "float", "double", "real", "bit", "boolean", "serial",
+    "blob", "binary", "enum", "set", "geometry", "point",
+];
+
+const DATABASE_COLLATIONS: &[&str] = &[
+    "utf8_unicode_ci", "utf8_general_ci", "utf8_bin", "ascii_bin", "ascii_general_ci",
+    "cp1250_bin", "cp1250_general_ci",
+];
+
+const DATABASE_ENGINES: &[&str] = &["InnoDB", "MyISAM", "MEMORY", "CSV", "BLACKHOLE", "ARCHIVE"];
+
+const WEEKDAYS: &[&str] = &[
+    "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday",
+];
+
+const MONTHS: &[&str] = &[
+    "January", "February", "March", "April", "May", "June", "July", "August", "September",
+    "October", "November", "December",
+];
+
+/// Common file extensions and their MIME types
+const COMMON_FILES: &[(&str, &str)] = &[
+    ("pdf", "application/pdf"),
+    ("mpeg", "audio/mpeg"),
+    ("wav", "audio/wav"),
+    ("png", "image/png"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("mp4", "video/mp4"),
+    ("m2v", "video/mpeg"),
+    ("htm", "text/html"),
+];
+
+const PRODUCTS: &[&str] = &[
+    "Chair", "Car", "Computer", "Keyboard", "Mouse", "Bike", "Ball", "Gloves", "Pants", "Shirt",
+    "Table", "Shoes", "Hat", "Towels", "Soap", "Tuna", "Chicken", "Fish", "Cheese", "Bacon",
+    "Pizza", "Salad", "Sausages", "Chips",
+];
+
+const PRODUCT_ADJECTIVES: &[&str] = &[
+    "Small", "Ergonomic", "Rustic", "Intelligent", "Gorgeous", "Incredible", "Fantastic",
+    "Practical", "Sleek", "Awesome", "Generic", "Handcrafted", "Handmade", "Licensed", "Refined",
+    "Unbranded", "Tasty",
+];
+
+const PRODUCT_MATERIALS: &[&str] = &[
+    "Steel", "Wooden", "Concrete", "Plastic", "Cotton", "Granite", "Rubber", "Metal", "Soft",
+    "Fresh", "Frozen",
+];
+
+const DEPARTMENTS: &[&str] = &[
+    "Books", "Movies", "Music", "Games", "Electronics", "Computers", "Home", "Garden", "Tools",
+    "Grocery", "Health", "Beauty", "Toys", "Kids", "Baby", "Clothing", "Shoes", "Jewelery",
+    "Sports", "Outdoors", "Automotive", "Industrial",
+];
+
+/// The dynamic variables [`DynamicVariables::generate`] knows, without their `$`
+const NAMES: &[&str] = &[
+    "guid", "randomUUID", "timestamp", "isoTimestamp", "randomAlphaNumeric", "randomBoolean",
+    "randomInt", "randomColor", "randomHexColor", "randomAbbreviation", "randomIP", "randomIPV6",
+    "randomMACAddress", "randomPassword", "randomLocale", "randomUserAgent", "randomProtocol",
+    "randomSemver", "randomFirstName", "randomLastName", "randomFullName", "randomNamePrefix",
+    "randomNameSuffix", "randomJobArea", "randomJobDescriptor", "randomJobTitle", "randomJobType",
+    "randomPhoneNumber", "randomPhoneNumberExt", "randomCity", "randomStreetName",
+    "randomStreetAddress", "randomCountry", "randomCountryCode", "randomLatitude",
+    "randomLongitude", "randomAvatarImage", "randomImageUrl", "randomAbstractImage",
+    "randomAnimalsImage", "randomBusinessImage", "randomCatsImage", "randomCityImage",
+    "randomFoodImage", "randomNightlifeImage", "randomFashionImage", "randomPeopleImage",
+    "randomNatureImage", "randomSportsImage", "randomTransportImage", "randomImageDataUri",
+    "randomBankAccount", "randomBankAccountName", "randomCreditCardMask", "randomBankAccountBic",
+    "randomBankAccountIban", "randomTransactionType", "randomCurrencyCode", "randomCurrencyName",
+    "randomCurrencySymbol", "randomBitcoin", "randomCompanyName", "randomCompanySuffix", "randomBs",
+    "randomBsAdjective", "randomBsBuzz", "randomBsNoun", "randomCatchPhrase",
+    "randomCatchPhraseAdjective", "randomCatchPhraseDescriptor", "randomCatchPhraseNoun",
+    "randomDatabaseColumn", "randomDatabaseType", "randomDatabaseCollation", "randomDatabaseEngine",
+    "randomDateFuture", "randomDatePast", "randomDateRecent", "randomWeekday", "randomMonth",
+    "randomDomainName", "randomDomainSuffix", "randomDomainWord", "randomEmail",
+    "randomExampleEmail", "randomUserName", "randomUrl", "randomFileName", "randomFileType",
+    "randomFileExt", "randomCommonFileName", "randomCommonFileType", "randomCommonFileExt",
+    "randomFilePath", "randomDirectoryPath", "randomMimeType", "randomPrice", "randomProduct",
+    "randomProductAdjective", "randomProductMaterial", "randomProductName", "randomDepartment",
+    "randomNoun", "randomVerb", "randomIngverb", "randomAdjective", "randomWord", "randomWords",
+    "randomPhrase", "randomLoremWord", "randomLoremWords", "randomLoremSentence",
+    "randomLoremSentences", "randomLoremParagraph", "randomLoremText", "randomLoremParagraphs",
+    "randomLoremSlug", "randomLoremLines",
+];
+
+/// Generates dynamic variable values. Clones share the same generator.
+#[derive(Clone)]
+pub struct DynamicVariables {
+    rng: Arc<Mutex<StdRng>>,
+}
+
+impl Default for DynamicVariables {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl DynamicVariables {
+    /// Seeds the generator, or seeds it randomly if no seed is given.
+    pub fn new(seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_os_rng(),
+        };
+        Self {
+            rng: Arc::new(Mutex::new(rng)),
+        }
+    }
+
+    /// Whether Postman defines a dynamic variable, named with its leading `$`.
+    pub fn is_defined(name: &str) -> bool {
+        name.strip_prefix('$').is_some_and(|name| NAMES.contains(&name))
+    }
+
+    /// Generates a value for a dynamic variable, named with its leading `$`.
+    /// Returns `None` for names Postman does not define.
+    pub fn generate(&self, name: &str) -> Option<String> {
+        let mut rng = self.rng.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let rng = &mut *rng;
+
+        let value = match name.strip_prefix('$')? {
+            // Common
+            "guid" | "randomUUID" => uuid::Builder::from_random_bytes(rng.random())
+                .into_uuid()
+                .to_string(),
+            "timestamp" => Utc::now().timestamp().to_string(),
+            "isoTimestamp" => Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+
+            // Text, numbers and colors
+            "randomAlphaNumeric" => (*ALPHANUMERIC.choose(rng)? as char).to_string(),
+            "randomBoolean" => rng.random::<bool>().to_string(),
+            "randomInt" => rng.random_range(0..=1000).to_string(),
+            "randomColor" => pick(rng, COLORS),
+            "randomHexColor" => format!("#{:06x}", rng.random_range(0..0x1000000)),
+            "randomAbbreviation" => pick(rng, ABBREVIATIONS),
+
+            // Internet and IP addresses
+            "randomIP" => IPv4().fake_with_rng(rng),
+            "randomIPV6" => IPv6().fake_with_rng(rng),
+            "randomMACAddress" => MACAddress().fake_with_rng::<String, _>(rng).to_lowercase(),
+            "randomPassword" => alphanumeric(rng, 15),
+            "randomLocale" => pick(rng, LOCALES),
+            "randomUserAgent" => UserAgent().fake_with_rng(rng),
+            "randomProtocol" => pick(rng, &["http", "https"]),
+            "randomSemver" => format!(
+                "{}.{}.{}",
+                rng.random_range(0..10),
+                rng.random_range(0..10),
+                rng.random_range(0..10)
+            ),
+
+            // Names
+            "randomFirstName" => FirstName().fake_with_rng(rng),
+            "randomLastName" => LastName().fake_with_rng(rng),
+            "randomFullName" => Name().fake_with_rng(rng),
+            "randomNamePrefix" => Title().fake_with_rng(rng),
+            "randomNameSuffix" => Suffix().fake_with_rng(rng),
+
+            // Profession
+            "randomJobArea" => pick(rng, JOB_AREAS),
+            "randomJobDescriptor" => pick(rng, JOB_DESCRIPTORS),
+            "randomJobTitle" => format!(
+                "{} {} {}",
+                pick(rng, JOB_DESCRIPTORS),
+                pick(rng, JOB_AREAS),
+                pick(rng, JOB_TYPES)
+            ),
+            "randomJobType" => pick(rng, JOB_TYPES),
+
+            // Phone, address and location
+            "randomPhoneNumber" => NumberWithFormat("^##-###-####").fake_with_rng(rng),
+            "randomPhoneNumberExt" => NumberWithFormat("^#-^##-###-####").fake_with_rng(rng),
+            "randomCity" => CityName().fake_with_rng(rng),
+            "randomStreetName" => StreetName().fake_with_rng(rng),
+            "randomStreetAddress" => format!(
+                "{} {}",
+                BuildingNumber().fake_with_rng::<String, _>(rng),
+                StreetName().fake_with_rng::<String, _>(rng)
+            ),
+            "randomCountry" => CountryName().fake_with_rng(rng),
+            "randomCountryCode" => CountryCode().fake_with_rng(rng),
+            "randomLatitude" => format!("{:.4}", rng.random_range(-90.0..=90.0)),
+            "randomLongitude" => format!("{:.4}", rng.random_range(-180.0..=180.0)),
+
+            // Images
+            "randomAvatarImage" => format!(
+                "https://avatars.githubusercontent.com/u/{}",
+                rng.random_range(0..100_000_000)
+            ),
+            "randomImageUrl" => "https://loremflickr.com/640/480".to_string(),
+            "randomAbstractImage" => image_url("abstract"),
+            "randomAnimalsImage" => image_url("animals"),
+            "randomBusinessImage" => image_url("business"),
+            "randomCatsImage" => image_url("cats"),
+            "randomCityImage" => image_url("city"),
+            "randomFoodImage" => image_url("food"),
+            "randomNightlifeImage" => image_url("nightlife"),
+            "randomFashionImage" => image_url("fashion"),
+            "randomPeopleImage" => image_url("people"),
+            "randomNatureImage" => image_url("nature"),
+            "randomSportsImage" => image_url("sports"),
+            "randomTransportImage" => image_url("transport"),
+            "randomImageDataUri" => format!(
+                "data:image/svg+xml;charset=UTF-8,%3Csvg%20xmlns%3D%22http%3A%2F%2Fwww.w3.org%2F2000%2Fsvg%22%20version%3D%221.1%22%20baseProfile%3D%22full%22%20width%3D%22640%22%20height%3D%22480%22%3E%3Crect%20width%3D%22100%25%22%20height%3D%22100%25%22%20fill%3D%22%23{:06x}%22%2F%3E%3C%2Fsvg%3E",
+                rng.random_range(0..0x1000000)
+            ),
+
+            // Finance
+            "randomBankAccount" => digits(rng, 8),
+            "randomBankAccountName" => format!("{} Account", pick(rng, BANK_ACCOUNT_TYPES)),
+            "randomCreditCardMask" => digits(rng, 4),
+            "randomBankAccountBic" => Bic().fake_with_rng(rng),
+            "randomBankAccountIban" => iban(rng),
+            "randomTransactionType" => pick(rng, TRANSACTION_TYPES),
+            "randomCurrencyCode" => CurrencyCode().fake_with_rng(rng),
+            "randomCurrencyName" => CurrencyName().fake_with_rng(rng),
+            "randomCurrencySymbol" => CurrencySymbol().fake_with_rng(rng),
+            "randomBitcoin" => {
+                let length = rng.random_range(25..34);
+                let mut address = pick(rng, &["1", "3"]);
+                address.extend((0..length).map(|_| *BASE58.choose(rng).unwrap() as char));
+                address
+            }
+
+            // Business
+            "randomCompanyName" => CompanyName().fake_with_rng(rng),
+            "randomCompanySuffix" => CompanySuffix().fake_with_rng(rng),
+            "randomBs" => Bs().fake_with_rng(rng),
+            "randomBsAdjective" => BsAdj().fake_with_rng(rng),
+            "randomBsBuzz" => BsVerb().fake_with_rng(rng),
+            "randomBsNoun" => BsNoun().fake_with_rng(rng),
+
+            // Catchphrases
+            "randomCatchPhrase" => CatchPhrase().fake_with_rng(rng),
+            "randomCatchPhraseAdjective" => Buzzword().fake_with_rng(rng),
+            "randomCatchPhraseDescriptor" => BuzzwordMiddle().fake_with_rng(rng),
+            "randomCatchPhraseNoun" => BuzzwordTail().fake_with_rng(rng),
+
+            // Databases
+            "randomDatabaseColumn" => pick(rng, DATABASE_COLUMNS),
+            "randomDatabaseType" => pick(rng, DATABASE_TYPES),
+            "randomDatabaseCollation" => pick(rng, DATABASE_COLLATIONS),
+            "randomDatabaseEngine" => pick(rng, DATABASE_ENGINES),
+
+            // Dates, formatted like JavaScript's `Date.toString()`
+            "randomDateFuture" => date(Utc::now() + Duration::seconds(rng.random_range(1..=365 * 86400))),
+            "randomDatePast" => date(Utc::now() - Duration::seconds(rng.random_range(1..=365 * 86400))),
+            "randomDateRecent" => date(Utc::now() - Duration::seconds(rng.random_range(1..=86400))),
+            "randomWeekday" => pick(rng, WEEKDAYS),
+            "randomMonth" => pick(rng, MONTHS),
+
+            // Domains, emails and usernames
+            "randomDomainName" => format!("{}.{}", domain_word(rng), DomainSuffix().fake_with_rng::<String, _>(rng)),
+            "randomDomainSuffix" => DomainSuffix().fake_with_rng(rng),
+            "randomDomainWord" => domain_word(rng),
+            "randomEmail" => FreeEmail().fake_with_rng(rng),
+            "randomExampleEmail" => format!(
+                "{}@{}",
+                Username().fake_with_rng::<String, _>(rng),
+                pick(rng, &["example.com", "example.net", "example.org"])
+            ),
+            "randomUserName" => Username().fake_with_rng(rng),
+            "randomUrl" => format!(
+                "{}://{}.{}",
+                pick(rng, &["http", "https"]),
+                domain_word(rng),
+                DomainSuffix().fake_with_rng::<String, _>(rng)
+            ),
+
+            // Files and directories
+            "randomFileName" => FileName().fake_with_rng(rng),
+            "randomFileType" => {
+                let mime: String = MimeType().fake_with_rng(rng);
+                mime.split('/').next().unwrap_or_default().to_string()
+            }
+            "randomFileExt" => FileExtension().fake_with_rng(rng),
+            "randomCommonFileName" => {
+                let (extension, _) = COMMON_FILES.choose(rng)?;
+                format!("{}.{}", Word().fake_with_rng::<String, _>(rng), extension)
+            }
+            "randomCommonFileType" => {
+                let (_, mime) = COMMON_FILES.choose(rng)?;
+                mime.split('/').next().unwrap_or_default().to_string()
+            }
+            "randomCommonFileExt" => COMMON_FILES.choose(rng)?.0.to_string(),
+            "randomFilePath" => FilePath().fake_with_rng(rng),
+            "randomDirectoryPath" => DirPath().fake_with_rng(rng),
+            "randomMimeType" => MimeType().fake_with_rng(rng),
+
+            // Stores
+            "randomPrice" => format!("{}.{:02}", rng.random_range(1..1000), rng.random_range(0..100)),
+            "randomProduct" => pick(rng, PRODUCTS),
+            "randomProductAdjective" => pick(rng, PRODUCT_ADJECTIVES),
+            "randomProductMaterial" => pick(rng, PRODUCT_MATERIALS),
+            "randomProductName" => format!(
+                "{} {} {}",
+                pick(rng, PRODUCT_ADJECTIVES),
+                pick(rng, PRODUCT_MATERIALS),
+                pick(rng, PRODUCTS)
+            ),
+            "randomDepartment" => pick(rng, DEPARTMENTS),
+
+            // Grammar
+            "randomNoun" => pick(rng, NOUNS),
+            "randomVerb" => pick(rng, VERBS),
+            "randomIngverb" => pick(rng, INGVERBS),
+            "randomAdjective" => pick(rng, ADJECTIVES),
+            "randomWord" => match rng.random_range(0..4) {
+                0 => pick(rng, NOUNS),
+                1 => pick(rng, VERBS),
+                2 => pick(rng, ADJECTIVES),
+                _ => Word().fake_with_rng(rng),
+            },
+            "randomWords" => Words(2..6).fake_with_rng::<Vec<String>, _>(rng).join(" "),
+            "randomPhrase" => Self::phrase(rng),
+
+            // Lorem ipsum
+            "randomLoremWord" => Word().fake_with_rng(rng),
+            "randomLoremWords" => Words(3..4).fake_with_rng::<Vec<String>, _>(rng).join(" "),
+            "randomLoremSentence" => Sentence(3..10).fake_with_rng(rng),
+            "randomLoremSentences" => Sentences(2..7).fake_with_rng::<Vec<String>, _>(rng).join(" "),
+            "randomLoremParagraph" | "randomLoremText" => Paragraph(3..7).fake_with_rng(rng),
+            "randomLoremParagraphs" => Paragraphs(3..4).fake_with_rng::<Vec<String>, _>(rng).join("\n \r"),
+            "randomLoremSlug" => Words(3..4).fake_with_rng::<Vec<String>, _>(rng).join("-"),
+            "randomLoremLines" => Sentences(1..6).fake_with_rng::<Vec<String>, _>(rng).join("\n"),
+
+            _ => return None,
+        };
+
+        Some(value)
+    }
+
+    fn phrase(rng: &mut StdRng) -> String {
+        let mut phrase = pick(rng, PHRASES);
+        for (placeholder, words) in [
+            ("{abbreviation}", ABBREVIATIONS),
+            ("{adjective}", ADJECTIVES),
+            ("{noun}", NOUNS),
+            ("{verb}", VERBS),
+            ("{ingverb}", INGVERBS),
+        ] {
+            // Each occurrence gets its own word
+            while let Some(start) = phrase.find(placeholder) {
+                phrase.replace_range(start..start + placeholder.len(), &pick(rng, words));
+            }
+        }
+
+        // Templates may start with a placeholder
+        let mut chars = phrase.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => phrase,
+        }
+    }
+}
+
+fn pick(rng: &mut StdRng, words: &[&str]) -> String {
+    words.choose(rng).copied().unwrap_or_default().to_string()
+}
+
+fn digits(rng: &mut StdRng, count: usize) -> String {
+    (0..count)
+        .map(|_| char::from(b'0' + rng.random_range(0..10)))
+        .collect()
+}
+
+fn alphanumeric(rng: &mut StdRng, count: usize) -> String {
+    (0..count)
+        .map(|_| *ALPHANUMERIC.choose(rng).unwrap() as char)
+        .collect()
+}
+
+fn image_url(category: &str) -> String {
+    format!("https://loremflickr.com/640/480/{}", category)
+}
+
+fn domain_word(rng: &mut StdRng) -> String {
+    FirstName()
+        .fake_with_rng::<String, _>(rng)
+        .to_lowercase()
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect()
+}
+
+fn date(date: DateTime<Utc>) -> String {
+    date.format("%a %b %d %Y %H:%M:%S GMT+0000 (Coordinated Universal Time)")
+        .to_string()
+}
+
+/// An IBAN with valid check digits.
+fn iban(rng: &mut StdRng) -> String {
+    let (country, length) = IBAN_FORMATS.choose(rng).copied().unwrap_or(("DE", 18));
+    let account = digits(rng, length);
+
+    // ISO 13616: the account number followed by the country code as digits
+    // and "00", modulo 97
+    let rearranged: String = account
+        .chars()
+        .chain(country.chars())
+        .map(|c| c.to_digit(36).unwrap_or_default().to_string())
+        .chain(["00".to_string()])
+        .collect();
+    let remainder = rearranged
+        .chars()
+        .fold(0, |remainder, digit| (remainder * 10 + digit.to_digit(10).unwrap_or_default()) % 97);
+
+    format!("{}{:02}{}", country, 98 - remainder, account)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Names whose values follow the clock rather than the seed.
+    const CLOCK_NAMES: &[&str] = &["timestamp", "isoTimestamp", "randomDateFuture", "randomDatePast", "randomDateRecent"];
+
+    #[test]
+    fn knows_every_name_it_generates() {
+        let dynamic = DynamicVariables::new(Some(7));
+        for name in NAMES {
+            let name = format!("${}", name);
+            assert!(DynamicVariables::is_defined(&name), "{}", name);
+            assert!(dynamic.generate(&name).is_some_and(|value| !value.is_empty()), "{}", name);
+        }
+        for name in ["$randomNope", "guid", "$", ""] {
+            assert!(!DynamicVariables::is_defined(name), "{}", name);
+            assert_eq!(dynamic.generate(name), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn seeds_make_values_repeatable() {
+        let values = |seed: u64| -> Vec<String> {
+            let dynamic = DynamicVariables::new(Some(seed));
+            NAMES
+                .iter()
+                .filter(|name| !CLOCK_NAMES.contains(name))
+                .map(|name| dynamic.generate(&format!("${}", name)).unwrap())
+                .collect()
+        };
+        assert_eq!(values(42), values(42));
+        assert_ne!(values(42), values(43));
+
+        // Clones draw from the same sequence
+        let dynamic = DynamicVariables::new(Some(1));
+        let clone = dynamic.clone();
+        let shared = [clone.generate("$guid").unwrap(), dynamic.generate("$guid").unwrap()];
+        let fresh = DynamicVariables::new(Some(1));
+        assert_eq!(shared, [fresh.generate("$guid").unwrap(), fresh.generate("$guid").unwrap()]);
+    }
+
+    #[test]
+    fn generates_well_formed_values() {
+        let dynamic = DynamicVariables::new(None);
+        let generate = |name: &str| dynamic.generate(name).unwrap();
+
+        assert!(uuid::Uuid::parse_str(&generate("$guid")).is_ok());
+        assert!(DateTime::parse_from_rfc3339(&generate("$isoTimestamp")).is_ok());
+        let color = generate("$randomHexColor");
+        assert!(color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit()));
+        assert!(generate("$randomAvatarImage").starts_with("https://avatars.githubusercontent.com/u/"));
+        assert!(generate("$randomPhrase").starts_with(|c: char| c.is_uppercase()));
+        assert!(generate("$randomDateFuture").ends_with("GMT+0000 (Coordinated Universal Time)"));
+
+        for _ in 0..20 {
+            // The check digits make the rearranged number 1 modulo 97
+            let iban = generate("$randomBankAccountIban");
+            let rearranged: String = iban[4..]
+                .chars()
+                .chain(iban[..4].chars())
+                .map(|c| c.to_digit(36).unwrap().to_string())
+                .collect();
+            let remainder = rearranged.chars().fold(0, |remainder, digit| (remainder * 10 + digit.to_digit(10).unwrap()) % 97);
+            assert_eq!(remainder, 1, "{}", iban);
+        }
+    }
+}
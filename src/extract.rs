@@ -0,0 +1,207 @@
+//! Declarative extraction of values from responses into variables, so
+//! requests can be chained without scripts.
+//!
+//! Rules map a variable name to where its value comes from:
+//!
+//! - `$.data.id`: a JSONPath expression evaluated on the JSON body
+//! - `/data/id`: a JSON Pointer into the JSON body
+//! - `header:Location`: a response header, matched case-insensitively
+//! - `status`: the status code
+//!
+//! Rules are set on an item as `"extract": {"userId": "$.data.id"}`, or in a
+//! sidecar file keyed by request name (or `Folder/Request` path).
+
+use anyhow::{anyhow, Context, Result};
+use indexmap::IndexMap;
+use jsonpath_rust::JsonPath;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::runner::HarEntry;
+
+/// Variable name to source expression.
+pub type ExtractRules = IndexMap<String, String>;
+
+/// Loads a JSON or YAML sidecar file of rules keyed by request name.
+pub fn load(path: &Path) -> Result<IndexMap<String, ExtractRules>> {
+    let text = std::fs::read_to_string(path).context("Failed to read extract rules file")?;
+    // YAML is a superset of JSON, so this parses both
+    serde_yaml::from_str(&text).context("Failed to parse extract rules")
+}
+
+/// Applies rules to a response, storing each extracted value in `variables`.
+/// Returns a message for every rule that did not match.
+pub fn apply(rules: &ExtractRules, entry: &HarEntry, variables: &mut HashMap<String, String>) -> Vec<String> {
+    let body: Option<JsonValue> = serde_json::from_str(&entry.response.content.text).ok();
+    let mut problems = Vec::new();
+
+    for (name, expression) in rules {
+        match extract(expression, entry, body.as_ref()) {
+            Ok(value) => {
+                variables.insert(name.clone(), value);
+            }
+            Err(error) => problems.push(format!("Could not extract {}: {}", name, error)),
+        }
+    }
+
+    problems
+}
+
+fn extract(expression: &str, entry: &HarEntry, body: Option<&JsonValue>) -> Result<String> {
+    let expression = expression.trim();
+
+    if let Some(header) = expression.strip_prefix("header:") {
+        return entry
+            .response
+            .headers
+            .iter()
+            .find(|candidate| candidate.name.eq_ignore_ascii_case(header.trim()))
+            .map(|header| header.value.clone())
+            .ok_or_else(|| anyhow!("no {} header in the response", header.trim()));
+    }
+    if expression == "status" {
+        return Ok(entry.response.status.to_string());
+    }
+
+    let body = body.ok_or_else(|| anyhow!("the response body is not JSON"))?;
+    let value = if expression.starts_with('$') {
+        body.query(expression)
+            .map_err(|error| anyhow!("invalid JSONPath {}: {}", expression, error))?
+            .into_iter()
+            .next()
+    } else if expression.is_empty() || expression.starts_with('/') {
+        body.pointer(expression)
+    } else {
+        return Err(anyhow!(
+            "{} is not a JSONPath ($...), JSON Pointer (/...), header:Name or status",
+            expression
+        ));
+    };
+
+    match value {
+        Some(JsonValue::String(text)) => Ok(text.clone()),
+        Some(value) => Ok(value.to_string()),
+        None => Err(anyhow!("{} matched nothing", expression)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(status: u16, headers: &[(&str, &str)], body: &str) -> HarEntry {
+        let headers: Vec<JsonValue> = headers.iter().map(|(name, value)| json!({ "name": name, "value": value })).collect();
+        serde_json::from_value(json!({
+            "startedDateTime": "2024-01-01T00:00:00Z",
+            "time": 0.0,
+            "request": {
+                "method": "POST",
+                "url": "https://example.com/users",
+                "httpVersion": "HTTP/1.1",
+                "headers": [],
+                "queryString": [],
+                "headersSize": -1,
+                "bodySize": 0,
+            },
+            "response": {
+                "status": status,
+                "statusText": "",
+                "httpVersion": "HTTP/1.1",
+                "headers": headers,
+                "content": { "size": body.len(), "mimeType": "application/json", "text": body },
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": body.len(),
+            },
+            "cache": {},
+            "timings": { "send": 0.0, "wait": 0.0, "receive": 0.0 },
+        }))
+        .unwrap()
+    }
+
+    fn rules(rules: &[(&str, &str)]) -> ExtractRules {
+        rules.iter().map(|(name, expression)| (name.to_string(), expression.to_string())).collect()
+    }
+
+    #[test]
+    fn extracts_from_body_headers_and_status() {
+        let entry = entry(
+            201,
+            &[("Location", "/users/7")],
+            r#"{"data": {"id": 7, "name": "Ada", "tags": ["a", "b"], "owner": {"id": 1}}}"#,
+        );
+        let mut variables = HashMap::new();
+        let problems = apply(
+            &rules(&[
+                ("id", "$.data.id"),
+                ("firstTag", "$.data.tags[0]"),
+                ("name", "/data/name"),
+                ("owner", "/data/owner"),
+                ("location", "header: location"),
+                ("status", "status"),
+            ]),
+            &entry,
+            &mut variables,
+        );
+
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(variables["id"], "7");
+        assert_eq!(variables["firstTag"], "a");
+        // Strings are unquoted, other values are kept as JSON
+        assert_eq!(variables["name"], "Ada");
+        assert_eq!(variables["owner"], r#"{"id":1}"#);
+        assert_eq!(variables["location"], "/users/7");
+        assert_eq!(variables["status"], "201");
+    }
+
+    #[test]
+    fn reports_rules_that_do_not_match() {
+        let mut variables = HashMap::from([("id".to_string(), "old".to_string())]);
+        let problems = apply(
+            &rules(&[
+                ("id", "$.missing"),
+                ("name", "/data/name"),
+                ("etag", "header:ETag"),
+                ("other", "data.id"),
+            ]),
+            &entry(200, &[], r#"{"data": {}}"#),
+            &mut variables,
+        );
+
+        assert_eq!(problems, [
+            "Could not extract id: $.missing matched nothing",
+            "Could not extract name: /data/name matched nothing",
+            "Could not extract etag: no ETag header in the response",
+            "Could not extract other: data.id is not a JSONPath ($...), JSON Pointer (/...), header:Name or status",
+        ]);
+        // Variables are left alone when a rule fails
+        assert_eq!(variables["id"], "old");
+
+        // Headers and the status do not need a JSON body
+        let text = entry(204, &[("ETag", "\"v1\"")], "not json");
+        let problems = apply(&rules(&[("etag", "header:etag"), ("status", "status"), ("id", "$.id")]), &text, &mut variables);
+        assert_eq!(problems, ["Could not extract id: the response body is not JSON"]);
+        assert_eq!(variables["etag"], "\"v1\"");
+        assert_eq!(variables["status"], "204");
+    }
+
+    #[test]
+    fn loads_json_and_yaml_rule_files() {
+        let dir = std::env::temp_dir().join(format!("harbinger-extract-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let json_path = dir.join("rules.json");
+        std::fs::write(&json_path, r#"{"Users/Create": {"userId": "$.id", "location": "header:Location"}}"#).unwrap();
+        let yaml_path = dir.join("rules.yaml");
+        std::fs::write(&yaml_path, "Login:\n  token: /token\n").unwrap();
+
+        let json_rules = load(&json_path).unwrap();
+        assert_eq!(json_rules["Users/Create"], rules(&[("userId", "$.id"), ("location", "header:Location")]));
+        assert_eq!(json_rules["Users/Create"].keys().collect::<Vec<_>>(), ["userId", "location"]);
+        assert_eq!(load(&yaml_path).unwrap()["Login"]["token"], "/token");
+        assert!(load(&dir.join("missing.json")).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,950 @@
+//! Reads and writes `.http` files, the request format of the JetBrains HTTP
+//! Client and the VS Code REST Client.
+//!
+//! Requests are separated by `###` lines, whose text names the request.
+//! Each request is a request line (`POST {{host}}/users HTTP/1.1`, or just
+//! a URL for GET), header lines, a blank line and the body. A body of
+//! `< ./file.json` sends a file. `@name = value` lines define variables, and
+//! environments come from `http-client.env.json` (overlaid with
+//! `http-client.private.env.json`) next to the file.
+//!
+//! A request named with `# @name login` can be referenced by later ones as
+//! `{{login.response.body.$.token}}` or `{{login.response.headers.Location}}`;
+//! these become extraction rules on the named request. Response handler
+//! scripts (`> {% ... %}`) are skipped with a warning.
+//!
+//! `Authorization: Basic user password` and `Digest user password` headers,
+//! which both HTTP clients encode themselves, are read and written as auth
+//! blocks.
+
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use serde_json::{json, Map, Value as JsonValue};
+use std::path::Path;
+
+use crate::collection::{
+    Auth, Body, Environment, FullRequest, ImportedCollection, PostmanCollection, Request, RequestContext, Url,
+};
+use crate::dynamic::DynamicVariables;
+use crate::hurl::encode_query;
+use crate::legacy::SCHEMA_V2_1;
+use crate::runner::raw_content_type;
+
+const METHODS: &[&str] = &["GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS", "TRACE", "CONNECT"];
+
+/// The multipart boundary used when writing form-data bodies.
+const BOUNDARY: &str = "WebAppBoundary";
+
+/// Whether `path` is a `.http` or `.rest` file.
+pub fn is_http_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("http") || extension.eq_ignore_ascii_case("rest"))
+}
+
+/// Loads a `.http` file with the environments defined next to it.
+pub fn load(path: &Path) -> Result<ImportedCollection> {
+    let text = std::fs::read_to_string(path).context("Failed to read .http file")?;
+    let name = path.file_stem().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut imported = parse(&text, &name)?;
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    imported.environments = environments(dir)?;
+    Ok(imported)
+}
+
+/// Parses the requests and variables of a `.http` file into a collection
+/// called `name`.
+pub fn parse(text: &str, name: &str) -> Result<ImportedCollection> {
+    let mut parser = Parser::default();
+    let mut section: Vec<&str> = Vec::new();
+    let mut title = String::new();
+
+    for line in text.lines() {
+        if let Some(rest) = line.strip_prefix("###") {
+            parser.request(&title, &section);
+            section.clear();
+            title = rest.trim().to_string();
+        } else {
+            section.push(line);
+        }
+    }
+    parser.request(&title, &section);
+
+    let references = parser.references(text);
+    for (request, rules) in references {
+        if let Some(item) = parser.items.iter_mut().find(|item| item.0.as_deref() == Some(request.as_str())) {
+            item.1["extract"] = json!(rules);
+        }
+    }
+
+    let mut collection = json!({
+        "info": { "name": name, "schema": SCHEMA_V2_1 },
+        "item": parser.items.iter().map(|(_, item)| item).collect::<Vec<_>>(),
+    });
+    if !parser.variables.is_empty() {
+        let variables: Vec<JsonValue> = parser
+            .variables
+            .iter()
+            .map(|(key, value)| json!({ "key": key, "value": value }))
+            .collect();
+        collection["variable"] = json!(variables);
+    }
+
+    Ok(ImportedCollection {
+        collection: serde_json::from_value(collection).context("Failed to convert .http file")?,
+        environments: Vec::new(),
+        warnings: parser.warnings,
+    })
+}
+
+#[derive(Default)]
+struct Parser {
+    /// Converted requests, with the name given by `# @name`
+    items: Vec<(Option<String>, JsonValue)>,
+    variables: IndexMap<String, String>,
+    warnings: Vec<String>,
+}
+
+impl Parser {
+    /// Converts the lines of one `###` section, if it has a request.
+    fn request(&mut self, title: &str, lines: &[&str]) {
+        let mut lines = lines.iter().map(|line| line.trim_end()).peekable();
+        let mut reference = None;
+
+        // Comments and variables before the request line
+        let request_line = loop {
+            let Some(line) = lines.next() else {
+                return;
+            };
+            let trimmed = line.trim();
+            if let Some(comment) = trimmed.strip_prefix('#').or_else(|| trimmed.strip_prefix("//")) {
+                if let Some(name) = comment.trim().strip_prefix("@name") {
+                    reference = Some(name.trim().trim_start_matches('=').trim().to_string());
+                }
+            } else if let Some(definition) = trimmed.strip_prefix('@') {
+                if let Some((key, value)) = definition.split_once('=') {
+                    self.variables.insert(key.trim().to_string(), convert(value.trim()));
+                }
+            } else if trimmed.starts_with("< {%") {
+                self.warn("pre-request scripts (`< {% ... %}`) are not supported and were skipped");
+                skip_script(trimmed, &mut lines);
+            } else if !trimmed.is_empty() {
+                break trimmed.to_string();
+            }
+        };
+
+        let mut words: Vec<&str> = request_line.split_whitespace().collect();
+        if words.last().is_some_and(|word| word.starts_with("HTTP/")) {
+            words.pop();
+        }
+        let (method, mut url) = match words.as_slice() {
+            [method, url @ ..] if METHODS.contains(method) => (method.to_string(), url.join(" ")),
+            url => ("GET".to_string(), url.join(" ")),
+        };
+        // Query parameters may continue on the following lines
+        while let Some(line) = lines.next_if(|line| matches!(line.trim_start().chars().next(), Some('?' | '&'))) {
+            url.push_str(line.trim());
+        }
+
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for line in lines.by_ref() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                break;
+            }
+            if trimmed.starts_with('#') || trimmed.starts_with("//") {
+                continue;
+            }
+            if let Some((key, value)) = trimmed.split_once(':') {
+                headers.push((key.trim().to_string(), convert(value.trim())));
+            }
+        }
+
+        let mut body_lines = Vec::new();
+        while let Some(line) = lines.next() {
+            let trimmed = line.trim();
+            if trimmed.starts_with("> {%") {
+                self.warn("response handler scripts (`> {% ... %}`) are not supported and were skipped");
+                skip_script(trimmed, &mut lines);
+            } else if trimmed.starts_with("> ") {
+                self.warn("response handler scripts (`> file.js`) are not supported and were skipped");
+            } else if trimmed.starts_with("<> ") || trimmed.starts_with(">>") {
+                // Response references and output redirection
+            } else {
+                body_lines.push(line);
+            }
+        }
+        while body_lines.last().is_some_and(|line| line.trim().is_empty()) {
+            body_lines.pop();
+        }
+
+        let url = convert(&url);
+        // A path with a `Host` header, as in `GET /users` + `Host: example.com`
+        let url = match headers.iter().find(|(key, _)| key.eq_ignore_ascii_case("host")) {
+            Some((_, host)) if url.starts_with('/') => format!("http://{}{}", host, url),
+            _ => url,
+        };
+        let body = body(&convert(&body_lines.join("\n")), &mut headers);
+        let auth = auth(&mut headers);
+
+        let name = [title, reference.as_deref().unwrap_or_default()]
+            .into_iter()
+            .find(|name| !name.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{} {}", method, url));
+        let header: Vec<JsonValue> = headers
+            .iter()
+            .map(|(key, value)| json!({ "key": key, "value": value }))
+            .collect();
+        let mut request = json!({ "method": method, "header": header, "url": { "raw": url } });
+        if let Some(body) = body {
+            request["body"] = body;
+        }
+        if let Some(auth) = auth {
+            request["auth"] = auth;
+        }
+        self.items.push((reference, json!({ "name": name, "request": request })));
+    }
+
+    /// Extraction rules for `{{name.response.body.$.path}}` and
+    /// `{{name.response.headers.Name}}` references to named requests.
+    fn references(&mut self, text: &str) -> IndexMap<String, IndexMap<String, String>> {
+        let mut rules: IndexMap<String, IndexMap<String, String>> = IndexMap::new();
+        let mut rest = text;
+
+        while let Some(start) = rest.find("{{") {
+            let Some(length) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            let placeholder = rest[start + 2..start + 2 + length].trim();
+            rest = &rest[start + 2 + length + 2..];
+
+            let Some((request, reference)) = placeholder.split_once(".response.") else {
+                continue;
+            };
+            if !self.items.iter().any(|(name, _)| name.as_deref() == Some(request)) {
+                continue;
+            }
+            let source = if let Some(path) = reference.strip_prefix("body.") {
+                match path {
+                    "*" => "$".to_string(),
+                    path if path.starts_with('$') => path.to_string(),
+                    _ => {
+                        self.warn(&format!("{{{{{}}}}} is not supported: only JSONPath response references are", placeholder));
+                        continue;
+                    }
+                }
+            } else if let Some(header) = reference.strip_prefix("headers.") {
+                format!("header:{}", header)
+            } else {
+                continue;
+            };
+            rules
+                .entry(request.to_string())
+                .or_default()
+                .insert(placeholder.to_string(), source);
+        }
+
+        rules
+    }
+
+    fn warn(&mut self, warning: &str) {
+        if !self.warnings.iter().any(|existing| existing == warning) {
+            self.warnings.push(warning.to_string());
+        }
+    }
+}
+
+/// Skips the lines of a `{% ... %}` script starting on `first`.
+fn skip_script<'a>(first: &str, lines: &mut impl Iterator<Item = &'a str>) {
+    if !first.contains("%}") {
+        for line in lines.by_ref() {
+            if line.contains("%}") {
+                break;
+            }
+        }
+    }
+}
+
+/// The body for the request's `Content-Type`: forms become form bodies (and
+/// lose the header, since the runner sets it), `< path` sends a file and
+/// anything else is raw.
+fn body(text: &str, headers: &mut Vec<(String, String)>) -> Option<JsonValue> {
+    if text.trim().is_empty() {
+        return None;
+    }
+    let content_type = headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+        .map(|(_, value)| value.clone())
+        .unwrap_or_default();
+    let mime_type = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+    let mut remove_content_type = || headers.retain(|(key, _)| !key.eq_ignore_ascii_case("content-type"));
+
+    if mime_type == "application/x-www-form-urlencoded" {
+        remove_content_type();
+        let joined: String = text.lines().map(str::trim).collect();
+        let fields: Vec<JsonValue> = url::form_urlencoded::parse(joined.as_bytes())
+            .map(|(key, value)| json!({ "key": key, "value": value, "type": "text" }))
+            .collect();
+        return Some(json!({ "mode": "urlencoded", "urlencoded": fields }));
+    }
+
+    if mime_type == "multipart/form-data" {
+        if let Some(boundary) = content_type
+            .split(';')
+            .find_map(|part| part.trim().strip_prefix("boundary="))
+            .map(|boundary| boundary.trim_matches('"').to_string())
+        {
+            remove_content_type();
+            return Some(json!({ "mode": "formdata", "formdata": multipart(text, &boundary) }));
+        }
+    }
+
+    if let Some(path) = text.trim().strip_prefix("< ").filter(|_| !text.trim().contains('\n')) {
+        return Some(json!({ "mode": "file", "file": { "src": path.trim() } }));
+    }
+
+    let language = match mime_type.as_str() {
+        mime if mime.contains("json") => "json",
+        mime if mime.contains("xml") => "xml",
+        mime if mime.contains("html") => "html",
+        mime if mime.contains("javascript") => "javascript",
+        _ => "text",
+    };
+    Some(json!({ "mode": "raw", "raw": text, "options": { "raw": { "language": language } } }))
+}
+
+/// The auth block for an `Authorization: Basic user password` or `Digest
+/// user password` header, which is removed. `Basic user:password` is read
+/// too; encoded credentials (`Basic dXNlcjpwYXNz`) stay a header.
+fn auth(headers: &mut Vec<(String, String)>) -> Option<JsonValue> {
+    let index = headers.iter().position(|(key, _)| key.eq_ignore_ascii_case("authorization"))?;
+    let (scheme, credentials) = headers[index].1.trim().split_once(' ')?;
+    let auth_type = match scheme.to_lowercase().as_str() {
+        "basic" => "basic",
+        "digest" => "digest",
+        _ => return None,
+    };
+    let credentials = credentials.trim();
+    let (username, password) = match credentials.split_once(' ') {
+        Some(split) => split,
+        None if auth_type == "basic" => credentials.split_once(':')?,
+        None => return None,
+    };
+
+    let auth = json!({
+        "type": auth_type,
+        auth_type: [
+            { "key": "username", "value": username, "type": "string" },
+            { "key": "password", "value": password.trim(), "type": "string" },
+        ],
+    });
+    headers.remove(index);
+    Some(auth)
+}
+
+/// The parts of a multipart body as form-data fields; a part whose content
+/// is `< path` uploads that file.
+fn multipart(text: &str, boundary: &str) -> Vec<JsonValue> {
+    let delimiter = format!("--{}", boundary);
+    let mut fields = Vec::new();
+
+    for part in text.split(&delimiter).skip(1) {
+        if part.starts_with("--") {
+            break;
+        }
+        let part = part.trim_start_matches(['\r', '\n']);
+        let (head, content) = part.split_once("\n\n").unwrap_or((part, ""));
+        let content = content.trim_end_matches(['\r', '\n']);
+
+        let mut name = String::new();
+        let mut content_type = None;
+        for line in head.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            if key.trim().eq_ignore_ascii_case("content-disposition") {
+                name = value
+                    .split(';')
+                    .find_map(|parameter| parameter.trim().strip_prefix("name="))
+                    .map(|name| name.trim_matches('"').to_string())
+                    .unwrap_or_default();
+            } else if key.trim().eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.trim().to_string());
+            }
+        }
+
+        let mut field = match content.trim().strip_prefix("< ") {
+            Some(path) => json!({ "key": name, "type": "file", "src": path.trim() }),
+            None => json!({ "key": name, "value": content, "type": "text" }),
+        };
+        if let Some(content_type) = content_type {
+            field["contentType"] = json!(content_type);
+        }
+        fields.push(field);
+    }
+
+    fields
+}
+
+/// Rewrites the HTTP clients' dynamic variables as Postman's: `{{$uuid}}`
+/// and `{{$random.uuid}}` become `{{$guid}}`, `{{$datetime iso8601}}`
+/// becomes `{{$isoTimestamp}}`, `{{$randomInt 1 10}}` becomes
+/// `{{$randomInt}}` and `{{$random.email}}` becomes `{{$randomEmail}}`.
+fn convert(text: &str) -> String {
+    let mut output = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{$") {
+        let Some(length) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let expression = rest[start + 2..start + 2 + length].trim();
+        let name = expression.split_whitespace().next().unwrap_or_default();
+        let converted = match name {
+            "$uuid" | "$random.uuid" => Some("$guid".to_string()),
+            "$datetime" if expression.contains("iso8601") => Some("$isoTimestamp".to_string()),
+            "$randomInt" | "$timestamp" | "$isoTimestamp" => Some(name.to_string()),
+            _ => name.strip_prefix("$random.").and_then(|faker| {
+                let mut chars = faker.chars();
+                let capitalized: String = chars.next().map(|first| first.to_uppercase().chain(chars).collect())?;
+                Some(format!("$random{}", capitalized)).filter(|name| DynamicVariables::is_defined(name))
+            }),
+        };
+
+        output.push_str(&rest[..start]);
+        match converted {
+            Some(converted) => output.push_str(&format!("{{{{{}}}}}", converted)),
+            None => output.push_str(&rest[start..start + 2 + length + 2]),
+        }
+        rest = &rest[start + 2 + length + 2..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Environments from `http-client.env.json`, with values from
+/// `http-client.private.env.json` taking precedence. Variables under
+/// `$shared` are in every environment.
+fn environments(dir: &Path) -> Result<Vec<Environment>> {
+    let mut merged: Map<String, JsonValue> = Map::new();
+    for file in ["http-client.env.json", "http-client.private.env.json"] {
+        let path = dir.join(file);
+        if !path.is_file() {
+            continue;
+        }
+        let text = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", file))?;
+        let environments: Map<String, JsonValue> =
+            serde_json::from_str(&text).with_context(|| format!("Failed to parse {}", file))?;
+        for (name, values) in environments {
+            let entry = merged.entry(name).or_insert_with(|| json!({}));
+            if let (Some(entry), Some(values)) = (entry.as_object_mut(), values.as_object()) {
+                entry.extend(values.clone());
+            }
+        }
+    }
+
+    let shared = merged.remove("$shared").and_then(|shared| shared.as_object().cloned()).unwrap_or_default();
+    merged
+        .into_iter()
+        .map(|(name, values)| {
+            let mut variables = shared.clone();
+            variables.extend(values.as_object().cloned().unwrap_or_default());
+            // Objects configure the client (such as SSL settings) rather than
+            // define variables
+            let values: Vec<JsonValue> = variables
+                .into_iter()
+                .filter(|(_, value)| !value.is_object() && !value.is_array())
+                .map(|(key, value)| json!({ "key": key, "value": value, "type": "default", "enabled": true }))
+                .collect();
+            serde_json::from_value(json!({ "name": name, "values": values }))
+                .with_context(|| format!("Failed to convert environment {}", name))
+        })
+        .collect()
+}
+
+/// Writes a collection as a `.http` file: collection variables as `@name =
+/// value` lines, then every request, named by its folders and name. Auth
+/// becomes headers (or query parameters for API keys in the query), and
+/// path variables are filled in. Scripts and OAuth 2.0 flows cannot be
+/// written, which is noted in a comment.
+pub fn export(collection: &PostmanCollection) -> String {
+    let mut output = String::new();
+
+    let variables: Vec<_> = collection.variable.iter().flatten().filter(|variable| variable.is_enabled()).collect();
+    for variable in &variables {
+        output.push_str(&format!("@{} = {}\n", variable.key, variable.value_text()));
+    }
+    if !variables.is_empty() {
+        output.push('\n');
+    }
+
+    let contexts = collection.get_all_request_contexts();
+    for (index, context) in contexts.iter().enumerate() {
+        if index > 0 {
+            output.push('\n');
+        }
+        let mut names: Vec<&str> = context.folders.iter().map(|folder| folder.name.as_str()).collect();
+        names.push(&context.item.name);
+        output.push_str(&format!("### {}\n", names.join(" / ")));
+
+        let has_scripts = ["prerequest", "test"]
+            .iter()
+            .any(|listen| !collection.scripts(context, listen).is_empty());
+        if has_scripts {
+            output.push_str("# Pre-request and test scripts are not exported\n");
+        }
+        // Extraction rules read back as references to the named request
+        let reference = context.item.extract.iter().flatten().find_map(|(variable, _)| {
+            variable.split_once(".response.").map(|(name, _)| name)
+        });
+        if let Some(reference) = reference {
+            output.push_str(&format!("# @name {}\n", reference));
+        }
+
+        match &context.item.request {
+            Request::Simple(url) => output.push_str(&format!("GET {}\n", url)),
+            Request::Full(request) => write_request(&mut output, collection, context, request),
+        }
+    }
+
+    output
+}
+
+fn write_request(output: &mut String, collection: &PostmanCollection, context: &RequestContext, request: &FullRequest) {
+    let mut url = with_path_variables(&request.url);
+    let mut headers: Vec<(String, String)> = request
+        .header
+        .iter()
+        .flatten()
+        .filter(|header| !header.disabled.unwrap_or(false))
+        .map(|header| (header.key.clone(), header.value().to_string()))
+        .collect();
+
+    if let Some(auth) = collection.effective_auth(context) {
+        match auth_header(auth) {
+            Ok(Some((key, value))) => headers.push((key, value)),
+            Ok(None) => {}
+            Err(note) => output.push_str(&format!("# {}\n", note)),
+        }
+        if auth.auth_type == "apikey" && auth.param("in").as_deref() == Some("query") {
+            let separator = if url.contains('?') { '&' } else { '?' };
+            url = format!(
+                "{}{}{}={}",
+                url,
+                separator,
+                auth.param("key").unwrap_or_default(),
+                auth.param("value").unwrap_or_default()
+            );
+        }
+    }
+
+    let body = request.body.as_ref().and_then(|body| write_body(body, &mut headers));
+
+    output.push_str(&format!("{} {}\n", request.method.to_uppercase(), url));
+    for (key, value) in &headers {
+        output.push_str(&format!("{}: {}\n", key, value));
+    }
+    if let Some(body) = body {
+        output.push('\n');
+        output.push_str(&body);
+        output.push('\n');
+    }
+}
+
+/// The URL with `:name` path segments replaced by the path variables'
+/// values, or by `{{name}}` when they have none.
+pub(crate) fn with_path_variables(url: &Url) -> String {
+    let raw = url.raw();
+    let variables = match url {
+        Url::Object(object) => object.variable.as_deref().unwrap_or_default(),
+        Url::String(_) => &[],
+    };
+    if variables.is_empty() {
+        return raw;
+    }
+
+    let (raw, fragment) = match raw.split_once('#') {
+        Some((raw, fragment)) => (raw, Some(fragment)),
+        None => (raw.as_str(), None),
+    };
+    let (path, query) = match raw.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (raw, None),
+    };
+    let path: Vec<String> = path
+        .split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(name) => match variables.iter().find(|variable| variable.key == name) {
+                Some(variable) if !variable.value_text().is_empty() => variable.value_text(),
+                _ => format!("{{{{{}}}}}", name),
+            },
+            None => segment.to_string(),
+        })
+        .collect();
+    let mut url = path.join("/");
+    if let Some(query) = query {
+        url.push('?');
+        url.push_str(query);
+    }
+    if let Some(fragment) = fragment {
+        url.push('#');
+        url.push_str(fragment);
+    }
+    url
+}
+
+/// The header an auth block sends, or a note when it cannot be written.
+pub(crate) fn auth_header(auth: &Auth) -> std::result::Result<Option<(String, String)>, String> {
+    let param = |key: &str| auth.param(key).unwrap_or_default();
+    let authorization = |value: String| Ok(Some(("Authorization".to_string(), value)));
+
+    match auth.auth_type.as_str() {
+        "noauth" => Ok(None),
+        "bearer" => authorization(format!("Bearer {}", param("token"))),
+        // Both HTTP clients encode `Basic user password` themselves
+        "basic" => authorization(format!("Basic {} {}", param("username"), param("password"))),
+        "digest" => authorization(format!("Digest {} {}", param("username"), param("password"))),
+        "apikey" if param("in") == "query" => Ok(None),
+        "apikey" => Ok(Some((param("key"), param("value")))),
+        "oauth2" if !param("accessToken").is_empty() => {
+            let prefix = auth.param("headerPrefix").unwrap_or_else(|| "Bearer".to_string());
+            authorization(format!("{} {}", prefix, param("accessToken")).trim().to_string())
+        }
+        other => Err(format!("{} auth is not exported", other)),
+    }
+}
+
+/// Writes a body, adding the `Content-Type` header it needs.
+fn write_body(body: &Body, headers: &mut Vec<(String, String)>) -> Option<String> {
+    let has_content_type = headers.iter().any(|(key, _)| key.eq_ignore_ascii_case("content-type"));
+    let mut content_type = |value: &str| {
+        if !has_content_type {
+            headers.push(("Content-Type".to_string(), value.to_string()));
+        }
+    };
+
+    match body.mode.as_str() {
+        "raw" => {
+            let raw = body.raw.clone().filter(|raw| !raw.is_empty())?;
+            content_type(raw_content_type(body.raw_language().unwrap_or("text")));
+            Some(raw)
+        }
+        "urlencoded" => {
+            content_type("application/x-www-form-urlencoded");
+            let fields: Vec<String> = body
+                .urlencoded
+                .iter()
+                .flatten()
+                .filter(|field| !field.disabled.unwrap_or(false))
+                .map(|field| format!("{}={}", encode_query(&field.key), encode_query(field.value())))
+                .collect();
+            Some(fields.join("&"))
+        }
+        "formdata" => {
+            headers.retain(|(key, _)| !key.eq_ignore_ascii_case("content-type"));
+            headers.push(("Content-Type".to_string(), format!("multipart/form-data; boundary={}", BOUNDARY)));
+            let mut parts = String::new();
+            for field in body.formdata.iter().flatten().filter(|field| !field.disabled.unwrap_or(false)) {
+                let paths = match (&field.src, field.is_file()) {
+                    (Some(src), true) => src.paths().into_iter().map(Some).collect(),
+                    _ => vec![None],
+                };
+                for path in paths {
+                    parts.push_str(&format!("--{}\n", BOUNDARY));
+                    match path {
+                        Some(path) => {
+                            let file_name = Path::new(path).file_name().map(|name| name.to_string_lossy().into_owned());
+                            parts.push_str(&format!(
+                                "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\n",
+                                field.key,
+                                file_name.unwrap_or_default()
+                            ));
+                        }
+                        None => parts.push_str(&format!("Content-Disposition: form-data; name=\"{}\"\n", field.key)),
+                    }
+                    if let Some(content_type) = &field.content_type {
+                        parts.push_str(&format!("Content-Type: {}\n", content_type));
+                    }
+                    match path {
+                        Some(path) => parts.push_str(&format!("\n< {}\n", path)),
+                        None => parts.push_str(&format!("\n{}\n", field.value())),
+                    }
+                }
+            }
+            parts.push_str(&format!("--{}--", BOUNDARY));
+            Some(parts)
+        }
+        "file" => {
+            let src = body.file.as_ref()?.src.clone()?;
+            Some(format!("< {}", src))
+        }
+        "graphql" => {
+            let graphql = body.graphql.as_ref()?;
+            content_type("application/json");
+            let mut payload = json!({ "query": graphql.query });
+            if let Some(variables) = graphql.variables.as_deref().filter(|variables| !variables.trim().is_empty()) {
+                payload["variables"] = serde_json::from_str(variables).unwrap_or_else(|_| json!(variables));
+            }
+            serde_json::to_string_pretty(&payload).ok()
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = r#"@host = https://api.example.com
+@id = {{$uuid}}
+
+### Log in
+# @name login
+POST {{host}}/login HTTP/1.1
+Content-Type: application/json
+
+{"user": "ada", "at": "{{$datetime iso8601}}"}
+
+> {%
+    client.global.set("token", response.body.token);
+%}
+
+###
+GET {{host}}/users
+    ?page=1
+    &size={{$randomInt 1 10}}
+Authorization: Bearer {{login.response.body.$.token}}
+// A comment
+
+### Relative
+GET /status
+Host: status.example.com
+
+### Form
+POST {{host}}/form
+Content-Type: application/x-www-form-urlencoded
+
+name=Ada&
+email={{$random.email}}
+
+### Upload
+POST {{host}}/upload
+Content-Type: multipart/form-data; boundary=Bound
+
+--Bound
+Content-Disposition: form-data; name="title"
+
+Report
+--Bound
+Content-Disposition: form-data; name="file"; filename="a.csv"
+Content-Type: text/csv
+
+< ./a.csv
+--Bound--
+
+### Replay
+PUT {{host}}/users/{{id}}
+Location: {{login.response.headers.Location}}
+Content-Type: application/json
+
+< ./user.json
+"#;
+
+    fn parsed() -> (JsonValue, Vec<String>) {
+        let imported = parse(FILE, "api").unwrap();
+        (serde_json::to_value(&imported.collection).unwrap(), imported.warnings)
+    }
+
+    #[test]
+    fn parses_requests() {
+        let (collection, _) = parsed();
+        assert_eq!(collection["info"]["name"], "api");
+        assert_eq!(collection["variable"][0], json!({ "key": "host", "value": "https://api.example.com" }));
+        assert_eq!(collection["variable"][1]["value"], "{{$guid}}");
+
+        let names: Vec<&str> = collection["item"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["Log in", "GET {{host}}/users?page=1&size={{$randomInt}}", "Relative", "Form", "Upload", "Replay"]);
+
+        let login = &collection["item"][0]["request"];
+        assert_eq!(login["method"], "POST");
+        assert_eq!(login["body"]["raw"], r#"{"user": "ada", "at": "{{$isoTimestamp}}"}"#);
+        assert_eq!(login["body"]["options"]["raw"]["language"], "json");
+        assert_eq!(collection["item"][2]["request"]["url"]["raw"], "http://status.example.com/status");
+    }
+
+    #[test]
+    fn parses_bodies() {
+        let (collection, _) = parsed();
+
+        let form = &collection["item"][3]["request"];
+        assert_eq!(form["header"], json!([]));
+        assert_eq!(form["body"]["urlencoded"][0], json!({ "key": "name", "value": "Ada", "type": "text" }));
+        assert_eq!(form["body"]["urlencoded"][1]["value"], "{{$randomEmail}}");
+
+        let upload = &collection["item"][4]["request"]["body"];
+        assert_eq!(upload["mode"], "formdata");
+        assert_eq!(upload["formdata"][0], json!({ "key": "title", "value": "Report", "type": "text" }));
+        assert_eq!(upload["formdata"][1]["src"], "./a.csv");
+        assert_eq!(upload["formdata"][1]["contentType"], "text/csv");
+
+        let replay = &collection["item"][5]["request"];
+        assert_eq!(replay["body"], json!({ "mode": "file", "file": { "src": "./user.json" } }));
+    }
+
+    #[test]
+    fn turns_response_references_into_extraction_rules() {
+        let (collection, warnings) = parsed();
+        assert_eq!(
+            collection["item"][0]["extract"],
+            json!({
+                "login.response.body.$.token": "$.token",
+                "login.response.headers.Location": "header:Location",
+            })
+        );
+        assert_eq!(warnings, ["response handler scripts (`> {% ... %}`) are not supported and were skipped"]);
+
+        let imported = parse("# @name a\nGET http://localhost\n\n###\nGET http://localhost/{{a.response.body.token}}\n", "x").unwrap();
+        assert_eq!(imported.warnings, ["{{a.response.body.token}} is not supported: only JSONPath response references are"]);
+    }
+
+    #[test]
+    fn exports_requests() {
+        let collection: PostmanCollection = serde_json::from_value(json!({
+            "info": { "name": "api", "schema": SCHEMA_V2_1 },
+            "auth": { "type": "apikey", "apikey": [
+                { "key": "key", "value": "api_key" },
+                { "key": "value", "value": "{{key}}" },
+                { "key": "in", "value": "query" },
+            ] },
+            "variable": [{ "key": "host", "value": "http://localhost" }, { "key": "off", "value": "x", "disabled": true }],
+            "item": [{
+                "name": "Users",
+                "item": [{
+                    "name": "Get",
+                    "extract": { "get.response.body.$.id": "$.id" },
+                    "event": [{ "listen": "test", "script": { "exec": ["pm.test('ok', () => {});"] } }],
+                    "request": {
+                        "method": "get",
+                        "url": {
+                            "raw": "{{host}}/users/:id?x=1",
+                            "variable": [{ "key": "id", "value": "7" }],
+                        },
+                        "header": [{ "key": "Accept", "value": "*/*" }, { "key": "X-Off", "value": "1", "disabled": true }],
+                    },
+                }],
+            }, {
+                "name": "Create",
+                "request": {
+                    "method": "POST",
+                    "url": "{{host}}/users",
+                    "auth": { "type": "bearer", "bearer": [{ "key": "token", "value": "{{token}}" }] },
+                    "body": { "mode": "urlencoded", "urlencoded": [{ "key": "name", "value": "Ada" }] },
+                },
+            }],
+        }))
+        .unwrap();
+
+        assert_eq!(
+            export(&collection),
+            "@host = http://localhost\n\
+             \n\
+             ### Users / Get\n\
+             # Pre-request and test scripts are not exported\n\
+             # @name get\n\
+             GET {{host}}/users/7?x=1&api_key={{key}}\n\
+             Accept: */*\n\
+             \n\
+             ### Create\n\
+             POST {{host}}/users\n\
+             Authorization: Bearer {{token}}\n\
+             Content-Type: application/x-www-form-urlencoded\n\
+             \n\
+             name=Ada\n"
+        );
+    }
+
+    #[test]
+    fn reads_and_writes_basic_and_digest_auth() {
+        let text = "GET http://localhost/a\nAuthorization: Basic {{user}} p w\n\n\
+                    ###\nGET http://localhost/b\nauthorization: digest ada secret\n\n\
+                    ###\nGET http://localhost/c\nAuthorization: Basic ada:secret\n\n\
+                    ###\nGET http://localhost/d\nAuthorization: Basic YWRhOnNlY3JldA==\n";
+        let imported = parse(text, "auth").unwrap();
+        let collection = serde_json::to_value(&imported.collection).unwrap();
+        let request = |index: usize| &collection["item"][index]["request"];
+
+        assert_eq!(request(0)["header"], json!([]));
+        assert_eq!(
+            request(0)["auth"],
+            json!({ "type": "basic", "basic": [
+                { "key": "username", "value": "{{user}}", "type": "string" },
+                { "key": "password", "value": "p w", "type": "string" },
+            ] })
+        );
+        assert_eq!(request(1)["auth"]["type"], "digest");
+        assert_eq!(request(1)["auth"]["digest"][1]["value"], "secret");
+        assert_eq!(request(2)["auth"]["basic"][0]["value"], "ada");
+        assert_eq!(request(3)["auth"], JsonValue::Null);
+        assert_eq!(request(3)["header"][0]["value"], "Basic YWRhOnNlY3JldA==");
+
+        let exported = export(&imported.collection);
+        assert!(exported.contains("Authorization: Basic {{user}} p w\n"));
+        assert!(exported.contains("Authorization: Digest ada secret\n"));
+        let reparsed = serde_json::to_value(&parse(&exported, "auth").unwrap().collection).unwrap();
+        assert_eq!(reparsed["item"][0]["request"]["auth"], request(0)["auth"]);
+    }
+
+    #[test]
+    fn encodes_and_decodes_form_fields() {
+        let collection: PostmanCollection = serde_json::from_value(json!({
+            "info": { "name": "api", "schema": SCHEMA_V2_1 },
+            "item": [{
+                "name": "Form",
+                "request": {
+                    "method": "POST",
+                    "url": "http://localhost/form",
+                    "body": { "mode": "urlencoded", "urlencoded": [
+                        { "key": "q", "value": "a&b=c d" },
+                        { "key": "who am i", "value": "{{user name}}/ü" },
+                    ] },
+                },
+            }],
+        }))
+        .unwrap();
+
+        let exported = export(&collection);
+        assert!(exported.ends_with("\nq=a%26b%3Dc+d&who+am+i={{user name}}%2F%C3%BC\n"), "{}", exported);
+
+        let imported = serde_json::to_value(&parse(&exported, "api").unwrap().collection).unwrap();
+        assert_eq!(
+            imported["item"][0]["request"]["body"]["urlencoded"],
+            json!([
+                { "key": "q", "value": "a&b=c d", "type": "text" },
+                { "key": "who am i", "value": "{{user name}}/ü", "type": "text" },
+            ])
+        );
+    }
+
+    #[test]
+    fn exports_what_it_parses() {
+        let imported = parse(FILE, "api").unwrap();
+        let exported = export(&imported.collection);
+        let reparsed = parse(&exported, "api").unwrap();
+        let requests = |collection: &PostmanCollection| -> Vec<JsonValue> {
+            collection
+                .get_all_request_contexts()
+                .iter()
+                .map(|context| serde_json::to_value(&context.item.request).unwrap())
+                .collect()
+        };
+        assert_eq!(requests(&reparsed.collection), requests(&imported.collection));
+    }
+}
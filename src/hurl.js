@@ -0,0 +1,131 @@
+// Hurl captures and asserts. Entries imported from Hurl call these from
+// their test script with each parsed query, filters and predicate; the
+// module is evaluated after the prelude (`pm.js`), only for scripts that use it.
+
+const __hurl = (() => {
+  const response = pm.response;
+  const pattern = (value) => (value !== null && typeof value === "object" && "regex" in value ? new RegExp(value.regex) : value);
+  const resolve = (value) => (typeof value === "string" ? pm.variables.replaceIn(value) : value);
+  const parse = (text) => {
+    try {
+      return JSON.parse(text);
+    } catch (e) {
+      return undefined;
+    }
+  };
+  const match = (regex, text) => {
+    const found = new RegExp(pattern(regex)).exec(text);
+    return found ? (found[1] === undefined ? found[0] : found[1]) : undefined;
+  };
+  // The JSONPath subset Hurl files use: names, quoted names, indexes and
+  // wildcards; a wildcard makes the result a collection
+  const jsonpath = (value, path) => {
+    const tokens = path.replace(/^\$/, "").match(/\.[^.[]+|\[[^\]]*\]/g) || [];
+    let results = [value];
+    let collection = false;
+    for (const token of tokens) {
+      let key = token.startsWith("[") ? token.slice(1, -1).trim() : token.slice(1);
+      if (/^(['"]).*\1$/.test(key)) key = key.slice(1, -1);
+      const next = [];
+      for (const result of results) {
+        if (result === null || typeof result !== "object") continue;
+        if (key === "*") {
+          collection = true;
+          next.push(...Object.values(result));
+        } else if (Array.isArray(result) && /^-?\d+$/.test(key)) {
+          const index = Number(key);
+          const item = result[index < 0 ? result.length + index : index];
+          if (item !== undefined) next.push(item);
+        } else if (key in result) {
+          next.push(result[key]);
+        }
+      }
+      results = next;
+    }
+    return collection ? results : results[0];
+  };
+  const queries = {
+    status: () => response.code,
+    header: (name) => response.headers.get(name),
+    cookie: (name) => {
+      for (const header of response.headers.all()) {
+        if (header.key.toLowerCase() !== "set-cookie") continue;
+        const [pair] = header.value.split(";");
+        const index = pair.indexOf("=");
+        if (pair.slice(0, index).trim() === name) return pair.slice(index + 1).trim();
+      }
+      return undefined;
+    },
+    body: () => response.text(),
+    jsonpath: (path) => jsonpath(parse(response.text()), path),
+    regex: (regex) => match(regex, response.text()),
+    variable: (name) => pm.variables.get(name),
+    duration: () => response.responseTime,
+    url: () => __request.url.toString(),
+  };
+  const filters = {
+    count: (value) => value.length,
+    nth: (value, index) => value[index],
+    first: (value) => value[0],
+    last: (value) => value[value.length - 1],
+    toInt: (value) => parseInt(value, 10),
+    toFloat: (value) => parseFloat(value),
+    toString: (value) => String(value),
+    split: (value, separator) => String(value).split(separator),
+    replace: (value, old, replacement) =>
+      typeof old === "string" ? String(value).split(old).join(replacement) : String(value).replace(new RegExp(pattern(old), "g"), replacement),
+    regex: (value, regex) => match(regex, value),
+    jsonpath: (value, path) => jsonpath(typeof value === "string" ? parse(value) : value, path),
+    urlDecode: (value) => decodeURIComponent(value),
+    urlEncode: (value) => encodeURIComponent(value),
+  };
+  const isEmpty = (value) => (typeof value === "object" && value !== null ? Object.keys(value).length === 0 : String(value).length === 0);
+  const predicates = {
+    "==": (actual, expected) => __deepEqual(actual, expected),
+    "!=": (actual, expected) => !__deepEqual(actual, expected),
+    ">": (actual, expected) => actual > expected,
+    ">=": (actual, expected) => actual >= expected,
+    "<": (actual, expected) => actual < expected,
+    "<=": (actual, expected) => actual <= expected,
+    startsWith: (actual, expected) => String(actual).startsWith(expected),
+    endsWith: (actual, expected) => String(actual).endsWith(expected),
+    contains: (actual, expected) => actual != null && actual.includes(expected),
+    includes: (actual, expected) => Array.isArray(actual) && actual.some((value) => __deepEqual(value, expected)),
+    matches: (actual, expected) => new RegExp(pattern(expected)).test(actual),
+    exists: (actual) => actual !== undefined,
+    isBoolean: (actual) => typeof actual === "boolean",
+    isCollection: (actual) => actual !== null && typeof actual === "object",
+    isEmpty: (actual) => actual != null && isEmpty(actual),
+    isFloat: (actual) => typeof actual === "number" && !Number.isInteger(actual),
+    isInteger: (actual) => Number.isInteger(actual),
+    isNumber: (actual) => typeof actual === "number",
+    isString: (actual) => typeof actual === "string",
+    isIsoDate: (actual) => /^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(\.\d+)?(Z|[+-]\d{2}:\d{2})$/.test(actual),
+  };
+  const evaluate = (query, applied) => {
+    const get = queries[query.type];
+    if (!get) throw new AssertionError(`unsupported Hurl query ${query.type}`);
+    let value = get(resolve(query.arg));
+    for (const [name, ...args] of applied) {
+      if (value === undefined) break;
+      value = filters[name](value, ...args.map(resolve));
+    }
+    return value;
+  };
+  return {
+    capture: ({ name, query, filters }) => {
+      const value = evaluate(query, filters);
+      if (value !== undefined) pm.environment.set(name, value);
+    },
+    assert: ({ text, query, filters, not, predicate, value }) =>
+      pm.test(text, () => {
+        const actual = evaluate(query, filters);
+        const expected = resolve(value);
+        // A JSON body is compared with the parsed response
+        const compared = typeof actual === "string" && expected !== null && typeof expected === "object" && !("regex" in expected) ? parse(actual) : actual;
+        if (predicates[predicate](compared, expected) === not) {
+          throw new AssertionError(`actual ${__format(compared)}, expected ${not ? "not " : ""}${predicate} ${value === undefined ? "" : __format(expected)}`.trim());
+        }
+      }),
+  };
+})();
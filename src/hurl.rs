@@ -0,0 +1,1240 @@
+//! Reads and writes Hurl files.
+//!
+//! A Hurl file is a list of entries: a request (`GET {{host}}/users`, headers,
+//! `[QueryStringParams]`, `[FormParams]`, `[MultipartFormData]`, `[Cookies]`
+//! and `[BasicAuth]` sections and a body), optionally followed by the
+//! expected response (`HTTP 200`, headers, `[Captures]`, `[Asserts]` and a
+//! body). JSONPath, header and status captures become extraction rules; the
+//! other captures and every assert become a test script calling the
+//! `__hurl` helpers in `hurl.js`, so asserts are reported with the
+//! other test results.
+//!
+//! Exported files expect each request's status: the code of its first
+//! example response for collections, or the observed status for a HAR.
+
+use anyhow::{anyhow, Context, Result};
+use indexmap::IndexMap;
+use serde_json::{json, Value as JsonValue};
+use std::collections::VecDeque;
+use std::path::Path;
+
+use crate::collection::{Body, FullRequest, ImportedCollection, PostmanCollection, Request, RequestContext};
+use crate::extract::ExtractRules;
+use crate::http_file::{auth_header, with_path_variables};
+use crate::legacy::SCHEMA_V2_1;
+use crate::runner::{raw_content_type, Har};
+
+/// Queries the script helpers evaluate, and whether they take an argument.
+const QUERIES: &[(&str, bool)] = &[
+    ("status", false),
+    ("header", true),
+    ("cookie", true),
+    ("body", false),
+    ("jsonpath", true),
+    ("regex", true),
+    ("variable", true),
+    ("duration", false),
+    ("url", false),
+];
+
+/// Filters the script helpers apply, with their number of arguments.
+const FILTERS: &[(&str, usize)] = &[
+    ("count", 0),
+    ("nth", 1),
+    ("first", 0),
+    ("last", 0),
+    ("toInt", 0),
+    ("toFloat", 0),
+    ("toString", 0),
+    ("split", 1),
+    ("replace", 2),
+    ("regex", 1),
+    ("jsonpath", 1),
+    ("urlDecode", 0),
+    ("urlEncode", 0),
+];
+
+/// Predicates that compare the query with a value.
+const VALUE_PREDICATES: &[&str] = &[
+    "==", "!=", ">", ">=", "<", "<=", "startsWith", "endsWith", "contains", "includes", "matches",
+];
+
+/// Predicates that only test the query.
+const PREDICATES: &[&str] = &[
+    "exists", "isBoolean", "isCollection", "isEmpty", "isFloat", "isInteger", "isNumber", "isString", "isIsoDate",
+];
+
+/// Whether `path` is a `.hurl` file.
+pub fn is_hurl_file(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("hurl"))
+}
+
+/// Loads a Hurl file as a collection named after the file.
+pub fn load(path: &Path) -> Result<ImportedCollection> {
+    let text = std::fs::read_to_string(path).context("Failed to read Hurl file")?;
+    let name = path.file_stem().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    parse(&text, &name)
+}
+
+/// Parses the entries of a Hurl file into a collection called `name`.
+pub fn parse(text: &str, name: &str) -> Result<ImportedCollection> {
+    let mut parser = Parser {
+        lines: text.lines().collect(),
+        position: 0,
+        variables: IndexMap::new(),
+        warnings: Vec::new(),
+    };
+    let mut items = Vec::new();
+    while let Some(item) = parser.entry()? {
+        items.push(item);
+    }
+
+    let mut collection = json!({
+        "info": { "name": name, "schema": SCHEMA_V2_1 },
+        "item": items,
+    });
+    if !parser.variables.is_empty() {
+        let variables: Vec<JsonValue> = parser
+            .variables
+            .iter()
+            .map(|(key, value)| json!({ "key": key, "value": value }))
+            .collect();
+        collection["variable"] = json!(variables);
+    }
+
+    Ok(ImportedCollection {
+        collection: serde_json::from_value(collection).context("Failed to convert Hurl file")?,
+        environments: Vec::new(),
+        warnings: parser.warnings,
+    })
+}
+
+struct Parser<'a> {
+    lines: Vec<&'a str>,
+    position: usize,
+    /// Variables set with `variable: name=value` options
+    variables: IndexMap<String, String>,
+    warnings: Vec<String>,
+}
+
+/// The request or response half of an entry.
+#[derive(Default)]
+struct Part {
+    headers: Vec<(String, String)>,
+    /// Section name to its lines
+    sections: IndexMap<String, Vec<(usize, String)>>,
+    body: Option<BodyBlock>,
+}
+
+enum BodyBlock {
+    /// Text with its language: `json`, `xml`, `graphql` or `text`
+    Text(String, String),
+    File(String),
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.lines.get(self.position).copied()
+    }
+
+    /// Skips blank lines and comments, returning the first line of the last
+    /// comment block, which names the next entry.
+    fn skip_comments(&mut self) -> Option<String> {
+        let mut comment = None;
+        let mut block_start = true;
+        while let Some(line) = self.peek() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                block_start = true;
+            } else if let Some(text) = trimmed.strip_prefix('#') {
+                if block_start {
+                    comment = Some(text.trim().to_string());
+                    block_start = false;
+                }
+            } else {
+                break;
+            }
+            self.position += 1;
+        }
+        comment.filter(|comment| !comment.is_empty())
+    }
+
+    fn entry(&mut self) -> Result<Option<JsonValue>> {
+        let comment = self.skip_comments();
+        let Some(line) = self.peek() else {
+            return Ok(None);
+        };
+        let (method, url) = request_line(line).ok_or_else(|| {
+            anyhow!(
+                "Hurl file line {}: expected a request such as `GET https://example.org`, found `{}`",
+                self.position + 1,
+                line.trim()
+            )
+        })?;
+        self.position += 1;
+        let mut url = convert(url);
+        let name = comment.unwrap_or_else(|| format!("{} {}", method, url));
+
+        let part = self.part(true)?;
+        let mut headers = part.headers;
+        let mut request = json!({ "method": method });
+
+        for (section, lines) in &part.sections {
+            match section.as_str() {
+                "QueryStringParams" | "Query" => {
+                    for (key, value) in key_values(lines) {
+                        let separator = if url.contains('?') { '&' } else { '?' };
+                        url = format!("{}{}{}={}", url, separator, encode_query(&key), encode_query(&value));
+                    }
+                }
+                "FormParams" | "Form" => {
+                    let fields: Vec<JsonValue> = key_values(lines)
+                        .into_iter()
+                        .map(|(key, value)| json!({ "key": key, "value": value, "type": "text" }))
+                        .collect();
+                    request["body"] = json!({ "mode": "urlencoded", "urlencoded": fields });
+                }
+                "MultipartFormData" | "Multipart" => {
+                    let fields: Vec<JsonValue> = key_values(lines).into_iter().map(multipart_field).collect();
+                    request["body"] = json!({ "mode": "formdata", "formdata": fields });
+                }
+                "Cookies" => {
+                    let cookies: Vec<String> = key_values(lines)
+                        .into_iter()
+                        .map(|(key, value)| format!("{}={}", key, value))
+                        .collect();
+                    headers.push(("Cookie".to_string(), cookies.join("; ")));
+                }
+                "BasicAuth" => {
+                    if let Some((username, password)) = key_values(lines).into_iter().next() {
+                        request["auth"] = json!({
+                            "type": "basic",
+                            "basic": [
+                                { "key": "username", "value": username, "type": "string" },
+                                { "key": "password", "value": password, "type": "string" },
+                            ],
+                        });
+                    }
+                }
+                "Options" => {
+                    for (option, value) in key_values(lines) {
+                        match (option.as_str(), value.split_once('=')) {
+                            ("variable", Some((key, value))) => {
+                                self.variables.insert(key.trim().to_string(), value.trim().to_string());
+                            }
+                            _ => self.warn(format!("the Hurl option `{}` is not supported and was ignored", option)),
+                        }
+                    }
+                }
+                other => self.warn(format!("the Hurl section [{}] is not supported and was ignored", other)),
+            }
+        }
+
+        match part.body {
+            Some(BodyBlock::Text(text, language)) if language == "graphql" => {
+                let (query, variables) = match text.find("\nvariables") {
+                    Some(index) => (&text[..index], Some(text[index + "\nvariables".len()..].trim())),
+                    None => (text.as_str(), None),
+                };
+                request["body"] = json!({ "mode": "graphql", "graphql": { "query": query, "variables": variables } });
+            }
+            Some(BodyBlock::Text(text, language)) => {
+                request["body"] = json!({ "mode": "raw", "raw": text, "options": { "raw": { "language": language } } });
+            }
+            Some(BodyBlock::File(path)) => request["body"] = json!({ "mode": "file", "file": { "src": path } }),
+            None => {}
+        }
+
+        let header: Vec<JsonValue> = headers
+            .iter()
+            .map(|(key, value)| json!({ "key": key, "value": value }))
+            .collect();
+        request["header"] = json!(header);
+        request["url"] = json!({ "raw": url });
+        let mut item = json!({ "name": name, "request": request });
+
+        if let Some(status) = self.peek().and_then(status_line) {
+            self.position += 1;
+            let (rules, script) = self.response(status)?;
+            if !rules.is_empty() {
+                item["extract"] = json!(rules);
+            }
+            if !script.is_empty() {
+                item["event"] = json!([{ "listen": "test", "script": { "type": "text/javascript", "exec": script } }]);
+            }
+        }
+
+        Ok(Some(item))
+    }
+
+    /// Converts the expected response into extraction rules and the lines of
+    /// a test script.
+    fn response(&mut self, status: Option<u16>) -> Result<(ExtractRules, Vec<String>)> {
+        let part = self.part(false)?;
+        let mut rules = ExtractRules::new();
+        let mut captures = Vec::new();
+        let mut asserts = Vec::new();
+
+        if let Some(status) = status {
+            asserts.push(json!({
+                "text": format!("status == {}", status),
+                "query": { "type": "status" },
+                "filters": [],
+                "not": false,
+                "predicate": "==",
+                "value": status,
+            }));
+        }
+        for (key, value) in &part.headers {
+            asserts.push(json!({
+                "text": format!("header \"{}\" == \"{}\"", key, value),
+                "query": { "type": "header", "arg": key },
+                "filters": [],
+                "not": false,
+                "predicate": "==",
+                "value": value,
+            }));
+        }
+
+        for (section, lines) in &part.sections {
+            match section.as_str() {
+                "Captures" => {
+                    for (number, line) in lines {
+                        match capture(line) {
+                            Ok(Capture::Rule(name, source)) => {
+                                rules.insert(name, source);
+                            }
+                            Ok(Capture::Script(capture)) => captures.push(capture),
+                            Err(problem) => self.warn(format!("line {}: {}; the capture was skipped", number, problem)),
+                        }
+                    }
+                }
+                "Asserts" => {
+                    for (number, line) in lines {
+                        match assertion(line) {
+                            Ok(assertion) => asserts.push(assertion),
+                            Err(problem) => self.warn(format!("line {}: {}; the assert was skipped", number, problem)),
+                        }
+                    }
+                }
+                other => self.warn(format!("the Hurl section [{}] is not supported and was ignored", other)),
+            }
+        }
+
+        match part.body {
+            Some(BodyBlock::Text(text, language)) => {
+                let value = match language.as_str() {
+                    "json" => serde_json::from_str(&text).unwrap_or(json!(text)),
+                    _ => json!(text),
+                };
+                asserts.push(json!({
+                    "text": "body",
+                    "query": { "type": "body" },
+                    "filters": [],
+                    "not": false,
+                    "predicate": "==",
+                    "value": value,
+                }));
+            }
+            Some(BodyBlock::File(_)) => self.warn("file response bodies are not supported and were not asserted".to_string()),
+            None => {}
+        }
+
+        let script = captures
+            .iter()
+            .map(|capture| format!("__hurl.capture({});", capture))
+            .chain(asserts.iter().map(|assertion| format!("__hurl.assert({});", assertion)))
+            .collect();
+        Ok((rules, script))
+    }
+
+    /// Reads headers, sections and a body, up to the response status line
+    /// (for requests) or the next entry.
+    fn part(&mut self, request: bool) -> Result<Part> {
+        let mut part = Part::default();
+        let mut section: Option<String> = None;
+
+        while let Some(line) = self.peek() {
+            let trimmed = line.trim();
+            if request_line(line).is_some() || (request && status_line(line).is_some()) {
+                break;
+            }
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                // Comments before the next entry name it
+                if self.entry_follows() {
+                    break;
+                }
+                self.position += 1;
+                continue;
+            }
+            if let Some(name) = section_header(trimmed) {
+                section = Some(name.to_string());
+                part.sections.entry(name.to_string()).or_default();
+                self.position += 1;
+                continue;
+            }
+            if part.body.is_none() && is_body_start(trimmed, section.is_some()) {
+                part.body = self.body()?;
+                continue;
+            }
+            let number = self.position + 1;
+            self.position += 1;
+            match &section {
+                Some(name) => part.sections[name].push((number, convert(trimmed))),
+                None => match trimmed.split_once(':') {
+                    Some((key, value)) => part.headers.push((key.trim().to_string(), convert(value.trim()))),
+                    None => anyhow::bail!("Hurl file line {}: unexpected `{}`", number, trimmed),
+                },
+            }
+        }
+
+        Ok(part)
+    }
+
+    /// Whether only blank lines and comments come before the next request.
+    fn entry_follows(&self) -> bool {
+        self.lines[self.position..]
+            .iter()
+            .find(|line| !line.trim().is_empty() && !line.trim().starts_with('#'))
+            .is_some_and(|line| request_line(line).is_some())
+    }
+
+    /// Reads the body starting on the current line.
+    fn body(&mut self) -> Result<Option<BodyBlock>> {
+        let start = self.position;
+        let line = self.lines[start].trim();
+
+        if let Some(rest) = line.strip_prefix("```") {
+            self.position += 1;
+            // One line form: ```text```
+            if let Some(text) = rest.strip_suffix("```").filter(|_| !rest.is_empty()) {
+                return Ok(Some(BodyBlock::Text(convert(text), "text".to_string())));
+            }
+            let language = match rest.trim() {
+                "" => "text",
+                language => language,
+            };
+            let mut lines = Vec::new();
+            loop {
+                let Some(line) = self.peek() else {
+                    anyhow::bail!("Hurl file line {}: unclosed ``` body", start + 1);
+                };
+                self.position += 1;
+                if line.trim() == "```" {
+                    break;
+                }
+                lines.push(line);
+            }
+            return Ok(Some(BodyBlock::Text(convert(&lines.join("\n")), language.to_string())));
+        }
+
+        if line.starts_with('{') || line.starts_with('[') {
+            let mut depth = 0usize;
+            let mut in_string = false;
+            let mut escaped = false;
+            let mut lines = Vec::new();
+            while let Some(line) = self.peek() {
+                self.position += 1;
+                lines.push(line);
+                for character in line.chars() {
+                    match character {
+                        _ if escaped => escaped = false,
+                        '\\' if in_string => escaped = true,
+                        '"' => in_string = !in_string,
+                        '{' | '[' if !in_string => depth += 1,
+                        '}' | ']' if !in_string => depth = depth.saturating_sub(1),
+                        _ => {}
+                    }
+                }
+                if depth == 0 {
+                    return Ok(Some(BodyBlock::Text(convert(&lines.join("\n")), "json".to_string())));
+                }
+            }
+            anyhow::bail!("Hurl file line {}: unclosed JSON body", start + 1);
+        }
+
+        self.position += 1;
+        if line.starts_with('<') {
+            let mut lines = vec![line];
+            while let Some(line) = self.peek() {
+                let trimmed = line.trim();
+                if trimmed.is_empty() || request_line(line).is_some() || status_line(line).is_some() || section_header(trimmed).is_some() {
+                    break;
+                }
+                lines.push(line);
+                self.position += 1;
+            }
+            return Ok(Some(BodyBlock::Text(convert(&lines.join("\n")), "xml".to_string())));
+        }
+        if let Some(path) = line.strip_prefix("file,") {
+            return Ok(Some(BodyBlock::File(path.trim_end_matches(';').trim().to_string())));
+        }
+        if line.starts_with("base64,") || line.starts_with("hex,") {
+            self.warn(format!("line {}: base64 and hex bodies are not supported and were skipped", start + 1));
+            return Ok(None);
+        }
+        let text = match line.strip_prefix('`').and_then(|line| line.strip_suffix('`')) {
+            Some(text) => text.to_string(),
+            None => unquote(line),
+        };
+        Ok(Some(BodyBlock::Text(convert(&text), "text".to_string())))
+    }
+
+    fn warn(&mut self, warning: String) {
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+    }
+}
+
+/// The method and URL of a request line such as `GET https://example.org`.
+fn request_line(line: &str) -> Option<(&str, &str)> {
+    let (method, url) = line.trim().split_once(char::is_whitespace)?;
+    let url = url.trim();
+    let is_method = !method.is_empty() && method != "HTTP" && method.chars().all(|character| character.is_ascii_uppercase());
+    (is_method && !url.is_empty())
+        .then_some((method, url))
+}
+
+/// The status of an `HTTP 200` / `HTTP/1.1 200` line, `None` for `HTTP *`.
+fn status_line(line: &str) -> Option<Option<u16>> {
+    let mut words = line.split_whitespace();
+    let version = words.next()?;
+    if version != "HTTP" && !version.starts_with("HTTP/") {
+        return None;
+    }
+    match words.next()? {
+        "*" => Some(None),
+        status => status.parse().ok().map(Some),
+    }
+}
+
+fn section_header(line: &str) -> Option<&str> {
+    line.strip_prefix('[')?
+        .strip_suffix(']')
+        .filter(|name| !name.is_empty() && name.chars().all(|character| character.is_ascii_alphabetic()))
+}
+
+fn is_body_start(line: &str, in_section: bool) -> bool {
+    ["{", "[", "```", "`", "<", "file,", "base64,", "hex,"]
+        .iter()
+        .any(|start| line.starts_with(start))
+        || (!in_section && line.starts_with('"'))
+}
+
+/// The `key: value` lines of a section, with quotes removed.
+fn key_values(lines: &[(usize, String)]) -> Vec<(String, String)> {
+    lines
+        .iter()
+        .filter_map(|(_, line)| {
+            let (key, value) = split_key_value(line)?;
+            Some((key, unquote(value)))
+        })
+        .collect()
+}
+
+fn split_key_value(line: &str) -> Option<(String, &str)> {
+    if line.starts_with('"') {
+        let end = closing_quote(line)?;
+        let (key, rest) = line.split_at(end + 1);
+        return Some((unquote(key), rest.trim_start().strip_prefix(':')?.trim()));
+    }
+    let (key, value) = line.split_once(':')?;
+    Some((key.trim().to_string(), value.trim()))
+}
+
+/// The index of the quote closing the string that starts `text`.
+fn closing_quote(text: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (index, character) in text.char_indices().skip(1) {
+        match character {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn unquote(value: &str) -> String {
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        serde_json::from_str(value).unwrap_or_else(|_| value[1..value.len() - 1].to_string())
+    } else {
+        value.to_string()
+    }
+}
+
+/// A multipart field; `file,path; type` values upload a file.
+fn multipart_field((key, value): (String, String)) -> JsonValue {
+    match value.strip_prefix("file,") {
+        Some(file) => {
+            let (path, content_type) = file.split_once(';').unwrap_or((file, ""));
+            let mut field = json!({ "key": key, "type": "file", "src": path.trim() });
+            if !content_type.trim().is_empty() {
+                field["contentType"] = json!(content_type.trim());
+            }
+            field
+        }
+        None => json!({ "key": key, "value": value, "type": "text" }),
+    }
+}
+
+/// Rewrites Hurl's generated values as Postman dynamic variables.
+fn convert(text: &str) -> String {
+    text.replace("{{newUuid}}", "{{$guid}}").replace("{{newDate}}", "{{$isoTimestamp}}")
+}
+
+/// Percent-encodes a query parameter name or value, which Hurl gives
+/// unencoded, keeping `{{variable}}` placeholders as they are.
+pub(crate) fn encode_query(text: &str) -> String {
+    let mut encoded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(length) = rest[start..].find("}}") else {
+            break;
+        };
+        let end = start + length + 2;
+        encoded.extend(url::form_urlencoded::byte_serialize(&rest.as_bytes()[..start]));
+        encoded.push_str(&rest[start..end]);
+        rest = &rest[end..];
+    }
+    encoded.extend(url::form_urlencoded::byte_serialize(rest.as_bytes()));
+    encoded
+}
+
+enum Token {
+    Word(String),
+    Quoted(String),
+    Regex(String),
+}
+
+/// Splits a capture or assert into words, quoted strings and `/regexes/`.
+fn tokens(text: &str) -> Result<VecDeque<Token>, String> {
+    let mut tokens = VecDeque::new();
+    let mut rest = text.trim_start();
+
+    while !rest.is_empty() {
+        if rest.starts_with('#') {
+            break;
+        }
+        let (token, length) = if rest.starts_with('"') {
+            let end = closing_quote(rest).ok_or("unclosed string")?;
+            (Token::Quoted(unquote(&rest[..=end])), end + 1)
+        } else if let Some(quoted) = rest.strip_prefix('`') {
+            let end = quoted.find('`').ok_or("unclosed string")?;
+            (Token::Quoted(quoted[..end].to_string()), end + 2)
+        } else if let Some(regex) = rest.strip_prefix('/') {
+            let mut escaped = false;
+            let end = regex
+                .char_indices()
+                .find(|&(_, character)| match character {
+                    _ if escaped => {
+                        escaped = false;
+                        false
+                    }
+                    '\\' => {
+                        escaped = true;
+                        false
+                    }
+                    character => character == '/',
+                })
+                .map(|(index, _)| index)
+                .ok_or("unclosed regex")?;
+            (Token::Regex(regex[..end].replace("\\/", "/")), end + 2)
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            (Token::Word(rest[..end].to_string()), end)
+        };
+        tokens.push_back(token);
+        rest = rest[length..].trim_start();
+    }
+
+    Ok(tokens)
+}
+
+/// A token as a JSON value for the script helpers: regexes are
+/// `{"regex": ...}` and bare words are numbers, booleans, `null` or text.
+fn token_value(token: Token) -> JsonValue {
+    match token {
+        Token::Quoted(text) => json!(text),
+        Token::Regex(regex) => json!({ "regex": regex }),
+        Token::Word(word) => match serde_json::from_str::<JsonValue>(&word) {
+            Ok(value) if !value.is_string() && !value.is_object() && !value.is_array() => value,
+            _ => json!(word),
+        },
+    }
+}
+
+fn query(tokens: &mut VecDeque<Token>) -> Result<JsonValue, String> {
+    let Some(Token::Word(kind)) = tokens.pop_front() else {
+        return Err("expected a query".to_string());
+    };
+    let Some(&(_, takes_argument)) = QUERIES.iter().find(|(name, _)| *name == kind) else {
+        return Err(format!("the `{}` query is not supported", kind));
+    };
+    if !takes_argument {
+        return Ok(json!({ "type": kind }));
+    }
+    let argument = tokens.pop_front().ok_or_else(|| format!("the `{}` query needs an argument", kind))?;
+    Ok(json!({ "type": kind, "arg": token_value(argument) }))
+}
+
+fn filters(tokens: &mut VecDeque<Token>) -> Result<Vec<JsonValue>, String> {
+    let mut filters = Vec::new();
+    while let Some(Token::Word(name)) = tokens.front() {
+        if name == "not" || name == "redact" || VALUE_PREDICATES.contains(&name.as_str()) || PREDICATES.contains(&name.as_str()) {
+            break;
+        }
+        let Some(&(name, arity)) = FILTERS.iter().find(|(filter, _)| filter == name) else {
+            return Err(format!("the `{}` filter is not supported", name));
+        };
+        tokens.pop_front();
+        let mut filter = vec![json!(name)];
+        for _ in 0..arity {
+            let argument = tokens.pop_front().ok_or_else(|| format!("the `{}` filter needs an argument", name))?;
+            filter.push(token_value(argument));
+        }
+        filters.push(json!(filter));
+    }
+    Ok(filters)
+}
+
+enum Capture {
+    /// An extraction rule: variable name and source
+    Rule(String, String),
+    /// Arguments for `__hurl.capture`
+    Script(JsonValue),
+}
+
+fn capture(line: &str) -> Result<Capture, String> {
+    let (name, rest) = split_key_value(line).ok_or("expected `name: query`")?;
+    let mut tokens = tokens(rest)?;
+    let query = query(&mut tokens)?;
+    let filters = filters(&mut tokens)?;
+
+    if filters.is_empty() {
+        let argument = query.get("arg").and_then(JsonValue::as_str).unwrap_or_default();
+        let source = match query["type"].as_str() {
+            Some("jsonpath") if argument.starts_with('$') => Some(argument.to_string()),
+            Some("header") => Some(format!("header:{}", argument)),
+            Some("status") => Some("status".to_string()),
+            _ => None,
+        };
+        if let Some(source) = source {
+            return Ok(Capture::Rule(name, source));
+        }
+    }
+    Ok(Capture::Script(json!({ "text": line, "name": name, "query": query, "filters": filters })))
+}
+
+fn assertion(line: &str) -> Result<JsonValue, String> {
+    let mut tokens = tokens(line)?;
+    let query = query(&mut tokens)?;
+    let filters = filters(&mut tokens)?;
+    let not = matches!(tokens.front(), Some(Token::Word(word)) if word == "not");
+    if not {
+        tokens.pop_front();
+    }
+
+    let Some(Token::Word(predicate)) = tokens.pop_front() else {
+        return Err("expected a predicate".to_string());
+    };
+    let mut assertion = json!({ "text": line, "query": query, "filters": filters, "not": not, "predicate": predicate });
+    if VALUE_PREDICATES.contains(&predicate.as_str()) {
+        let value = tokens.pop_front().ok_or_else(|| format!("`{}` needs a value", predicate))?;
+        assertion["value"] = token_value(value);
+    } else if !PREDICATES.contains(&predicate.as_str()) {
+        return Err(format!("the `{}` predicate is not supported", predicate));
+    }
+    Ok(assertion)
+}
+
+/// Writes a collection as a Hurl file. Each entry is preceded by a comment
+/// with the request's folders and name; auth is written as headers or
+/// `[BasicAuth]`, extraction rules as `[Captures]`, and asserts and
+/// captures imported from Hurl are written back. Other scripts cannot be
+/// exported, which is noted in a comment.
+pub fn export(collection: &PostmanCollection) -> String {
+    let mut output = String::new();
+
+    // Hurl has no file level variables, so the first entry sets them
+    let mut options: String = collection
+        .variable
+        .iter()
+        .flatten()
+        .filter(|variable| variable.is_enabled())
+        .map(|variable| format!("variable: {}={}\n", variable.key, variable.value_text()))
+        .collect();
+    if !options.is_empty() {
+        options.insert_str(0, "[Options]\n");
+    }
+
+    for context in collection.get_all_request_contexts() {
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        let mut names: Vec<&str> = context.folders.iter().map(|folder| folder.name.as_str()).collect();
+        names.push(&context.item.name);
+        output.push_str(&format!("# {}\n", names.join(" / ")));
+
+        let mut hurl_lines = Vec::new();
+        let mut has_scripts = false;
+        for listen in ["prerequest", "test"] {
+            for source in collection.scripts(&context, listen) {
+                for line in source.lines().map(str::trim).filter(|line| !line.is_empty()) {
+                    match hurl_call(line) {
+                        Some(call) if listen == "test" => hurl_lines.push(call),
+                        _ => has_scripts = true,
+                    }
+                }
+            }
+        }
+        if has_scripts {
+            output.push_str("# Pre-request and test scripts are not exported\n");
+        }
+
+        match &context.item.request {
+            Request::Simple(url) => output.push_str(&format!("GET {}\n{}", url, options)),
+            Request::Full(request) => write_request(&mut output, collection, &context, request, &options),
+        }
+        options.clear();
+
+        let text = |arguments: &JsonValue| arguments.get("text").and_then(JsonValue::as_str).unwrap_or_default().to_string();
+
+        // The status comes from an example response, or an imported assert
+        let mut status = context
+            .item
+            .response
+            .iter()
+            .flatten()
+            .find_map(|response| response.code)
+            .map(|code| code.to_string());
+        if status.is_none() {
+            if let Some(index) = hurl_lines.iter().position(|(kind, arguments)| *kind == "assert" && text(arguments).starts_with("status == ")) {
+                status = Some(text(&hurl_lines.remove(index).1).trim_start_matches("status == ").to_string());
+            }
+        }
+        output.push_str(&format!("\nHTTP {}\n", status.as_deref().unwrap_or("*")));
+
+        let mut captures: Vec<String> = context
+            .item
+            .extract
+            .iter()
+            .flatten()
+            .filter_map(|(name, source)| capture_line(name, source))
+            .collect();
+        captures.extend(hurl_lines.iter().filter(|(kind, _)| *kind == "capture").map(|(_, arguments)| text(arguments)));
+        if !captures.is_empty() {
+            output.push_str("[Captures]\n");
+            for capture in captures {
+                output.push_str(&format!("{}\n", capture));
+            }
+        }
+
+        let (body, asserts): (Vec<_>, Vec<_>) = hurl_lines
+            .iter()
+            .filter(|(kind, _)| *kind == "assert")
+            .map(|(_, arguments)| arguments)
+            .partition(|arguments| text(arguments) == "body");
+        if !asserts.is_empty() {
+            output.push_str("[Asserts]\n");
+            for assertion in asserts {
+                output.push_str(&format!("{}\n", text(assertion)));
+            }
+        }
+        // The expected body, from an imported implicit body assert
+        match body.first().and_then(|arguments| arguments.get("value")) {
+            Some(JsonValue::String(body)) => output.push_str(&format!("```\n{}\n```\n", body)),
+            Some(body) => output.push_str(&format!("{}\n", serde_json::to_string_pretty(body).unwrap_or_default())),
+            None => {}
+        }
+    }
+
+    output
+}
+
+/// The kind (`assert` or `capture`) and arguments of a line calling the
+/// `__hurl` helpers.
+fn hurl_call(line: &str) -> Option<(&'static str, JsonValue)> {
+    let (kind, arguments) = if let Some(arguments) = line.strip_prefix("__hurl.assert(") {
+        ("assert", arguments)
+    } else {
+        ("capture", line.strip_prefix("__hurl.capture(")?)
+    };
+    let arguments = serde_json::from_str(arguments.strip_suffix(");")?).ok()?;
+    Some((kind, arguments))
+}
+
+/// An extraction rule as a Hurl capture.
+fn capture_line(name: &str, source: &str) -> Option<String> {
+    let quote = |text: &str| serde_json::to_string(text).unwrap_or_default();
+    let query = if source == "status" {
+        "status".to_string()
+    } else if let Some(header) = source.strip_prefix("header:") {
+        format!("header {}", quote(header))
+    } else if source.starts_with('$') {
+        format!("jsonpath {}", quote(source))
+    } else if let Some(pointer) = source.strip_prefix('/') {
+        let path: String = pointer
+            .split('/')
+            .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+            .map(|segment| match segment.parse::<usize>() {
+                Ok(index) => format!("[{}]", index),
+                Err(_) => format!("['{}']", segment),
+            })
+            .collect();
+        format!("jsonpath {}", quote(&format!("${}", path)))
+    } else {
+        return None;
+    };
+    Some(format!("{}: {}", name, query))
+}
+
+fn write_request(
+    output: &mut String,
+    collection: &PostmanCollection,
+    context: &RequestContext,
+    request: &FullRequest,
+    options: &str,
+) {
+    // The fragment is not sent, and `#` starts a comment in Hurl
+    let mut url = with_path_variables(&request.url);
+    url.truncate(url.find('#').unwrap_or(url.len()));
+    let mut headers: Vec<(String, String)> = request
+        .header
+        .iter()
+        .flatten()
+        .filter(|header| !header.disabled.unwrap_or(false))
+        .map(|header| (header.key.clone(), header.value().to_string()))
+        .collect();
+    let mut sections = options.to_string();
+
+    if let Some(auth) = collection.effective_auth(context) {
+        match auth.auth_type.as_str() {
+            "basic" => sections.push_str(&format!(
+                "[BasicAuth]\n{}: {}\n",
+                auth.param("username").unwrap_or_default(),
+                auth.param("password").unwrap_or_default()
+            )),
+            "digest" => output.push_str("# digest auth is not exported\n"),
+            "apikey" if auth.param("in").as_deref() == Some("query") => {
+                let separator = if url.contains('?') { '&' } else { '?' };
+                url = format!(
+                    "{}{}{}={}",
+                    url,
+                    separator,
+                    auth.param("key").unwrap_or_default(),
+                    auth.param("value").unwrap_or_default()
+                );
+            }
+            _ => match auth_header(auth) {
+                Ok(Some(header)) => headers.push(header),
+                Ok(None) => {}
+                Err(note) => output.push_str(&format!("# {}\n", note)),
+            },
+        }
+    }
+
+    let body = request.body.as_ref().and_then(|body| write_body(body, &mut headers, &mut sections));
+
+    output.push_str(&format!("{} {}\n", request.method.to_uppercase(), url));
+    for (key, value) in &headers {
+        output.push_str(&format!("{}: {}\n", key, value));
+    }
+    output.push_str(&sections);
+    if let Some(body) = body {
+        output.push_str(&body);
+        output.push('\n');
+    }
+}
+
+/// Writes a body, adding the `Content-Type` header or the form section it
+/// needs.
+fn write_body(body: &Body, headers: &mut Vec<(String, String)>, sections: &mut String) -> Option<String> {
+    let fields = |fields: &Option<Vec<crate::collection::KeyValue>>| -> Vec<_> {
+        fields
+            .iter()
+            .flatten()
+            .filter(|field| !field.disabled.unwrap_or(false))
+            .cloned()
+            .collect()
+    };
+
+    match body.mode.as_str() {
+        "raw" => {
+            let raw = body.raw.clone().filter(|raw| !raw.is_empty())?;
+            let language = body.raw_language().unwrap_or("text");
+            if !headers.iter().any(|(key, _)| key.eq_ignore_ascii_case("content-type")) {
+                headers.push(("Content-Type".to_string(), raw_content_type(language).to_string()));
+            }
+            match raw.trim_start().chars().next() {
+                Some('{' | '[') if language == "json" => Some(raw),
+                _ => Some(format!("```{}\n{}\n```", language, raw)),
+            }
+        }
+        "urlencoded" => {
+            headers.retain(|(key, _)| !key.eq_ignore_ascii_case("content-type"));
+            sections.push_str("[FormParams]\n");
+            for field in fields(&body.urlencoded) {
+                sections.push_str(&format!("{}: {}\n", field.key, field.value()));
+            }
+            None
+        }
+        "formdata" => {
+            headers.retain(|(key, _)| !key.eq_ignore_ascii_case("content-type"));
+            sections.push_str("[MultipartFormData]\n");
+            for field in fields(&body.formdata) {
+                match (&field.src, field.is_file()) {
+                    (Some(src), true) => {
+                        for path in src.paths() {
+                            match &field.content_type {
+                                Some(content_type) => {
+                                    sections.push_str(&format!("{}: file,{}; {}\n", field.key, path, content_type))
+                                }
+                                None => sections.push_str(&format!("{}: file,{};\n", field.key, path)),
+                            }
+                        }
+                    }
+                    _ => sections.push_str(&format!("{}: {}\n", field.key, field.value())),
+                }
+            }
+            None
+        }
+        "file" => Some(format!("file,{};", body.file.as_ref()?.src.as_deref()?)),
+        "graphql" => {
+            let graphql = body.graphql.as_ref()?;
+            let variables = graphql
+                .variables
+                .as_deref()
+                .filter(|variables| !variables.trim().is_empty())
+                .map(|variables| format!("\nvariables {}", variables.trim()))
+                .unwrap_or_default();
+            Some(format!("```graphql\n{}{}\n```", graphql.query.trim_end(), variables))
+        }
+        _ => None,
+    }
+}
+
+/// Writes the requests of a run as a Hurl file, expecting the status each
+/// request got.
+pub fn export_har(har: &Har) -> String {
+    let mut output = String::new();
+
+    for entry in &har.log.entries {
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        if let Some(comment) = &entry.comment {
+            output.push_str(&format!("# {}\n", comment));
+        }
+        let request = &entry.request;
+        if let Some(auth) = request.auth.as_ref().filter(|auth| auth.auth_type != "noauth") {
+            output.push_str(&format!("# Sent with {} auth; its credentials are not recorded\n", auth.auth_type));
+        }
+        output.push_str(&format!("{} {}\n", request.method, request.url));
+
+        let post_data = request.post_data.as_ref();
+        let mime_type = post_data.map(|data| data.mime_type.to_lowercase()).unwrap_or_default();
+        let form = post_data.and_then(|data| data.params.as_ref()).filter(|_| {
+            mime_type.starts_with("application/x-www-form-urlencoded") || mime_type.starts_with("multipart/form-data")
+        });
+
+        for header in &request.headers {
+            let name = header.name.to_lowercase();
+            // Hurl computes these itself
+            if name == "host" || name == "content-length" || (name == "content-type" && form.is_some()) {
+                continue;
+            }
+            output.push_str(&format!("{}: {}\n", header.name, header.value));
+        }
+
+        match (post_data, form) {
+            (_, Some(params)) if mime_type.starts_with("multipart/form-data") => {
+                output.push_str("[MultipartFormData]\n");
+                for param in params {
+                    match (&param.file_name, &param.content_type) {
+                        (Some(file_name), Some(content_type)) => {
+                            output.push_str(&format!("{}: file,{}; {}\n", param.name, file_name, content_type))
+                        }
+                        (Some(file_name), None) => output.push_str(&format!("{}: file,{};\n", param.name, file_name)),
+                        _ => output.push_str(&format!("{}: {}\n", param.name, param.value.as_deref().unwrap_or_default())),
+                    }
+                }
+            }
+            (_, Some(params)) => {
+                output.push_str("[FormParams]\n");
+                for param in params {
+                    output.push_str(&format!("{}: {}\n", param.name, param.value.as_deref().unwrap_or_default()));
+                }
+            }
+            (Some(data), None) if !data.text.is_empty() => {
+                let json = mime_type.contains("json") && serde_json::from_str::<JsonValue>(&data.text).is_ok();
+                match data.text.trim_start().chars().next() {
+                    Some('{' | '[') if json => output.push_str(&format!("{}\n", data.text)),
+                    _ => {
+                        let language = if mime_type.contains("xml") { "xml" } else if json { "json" } else { "" };
+                        output.push_str(&format!("```{}\n{}\n```\n", language, data.text));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        output.push_str(&format!("\nHTTP {}\n", entry.response.status));
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = r#"# Create a thing
+POST {{host}}/things
+Content-Type: application/json
+[Options]
+variable: who=bob
+{
+  "name": "{{who}}",
+  "id": "{{newUuid}}"
+}
+HTTP 201
+[Captures]
+thing_id: jsonpath "$.id"
+location: header "Location"
+slug: jsonpath "$.path" regex /\/(\w+)/
+[Asserts]
+jsonpath "$.tags" count > 2
+jsonpath "$.missing" not exists
+xpath "//a" exists
+
+GET {{host}}/things/{{thing_id}}
+[QueryStringParams]
+q: a b&c
+tag: {{slug}}
+[Cookies]
+session: abc
+[BasicAuth]
+ada: secret
+HTTP *
+
+POST {{host}}/upload
+[MultipartFormData]
+field: value
+file: file,a.txt; text/plain
+[FormParams]
+b: "two words"
+HTTP 200
+```
+done
+```
+"#;
+
+    fn parsed() -> (JsonValue, Vec<String>) {
+        let imported = parse(FILE, "suite").unwrap();
+        (serde_json::to_value(&imported.collection).unwrap(), imported.warnings)
+    }
+
+    /// The `__hurl` calls of an item's test script.
+    fn calls(item: &JsonValue) -> Vec<(&'static str, JsonValue)> {
+        item["event"][0]["script"]["exec"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|line| hurl_call(line.as_str().unwrap()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn parses_requests() {
+        let (collection, _) = parsed();
+        assert_eq!(collection["variable"][0], json!({ "key": "who", "value": "bob" }));
+
+        let create = &collection["item"][0];
+        assert_eq!(create["name"], "Create a thing");
+        assert_eq!(create["request"]["body"]["options"]["raw"]["language"], "json");
+        assert!(create["request"]["body"]["raw"].as_str().unwrap().contains("{{$guid}}"));
+
+        let get = &collection["item"][1];
+        assert_eq!(get["name"], "GET {{host}}/things/{{thing_id}}");
+        assert_eq!(get["request"]["url"]["raw"], "{{host}}/things/{{thing_id}}?q=a+b%26c&tag={{slug}}");
+        assert_eq!(get["request"]["header"][0], json!({ "key": "Cookie", "value": "session=abc" }));
+        assert_eq!(get["request"]["auth"]["basic"][1]["value"], "secret");
+
+        // The last form section wins
+        let upload = &collection["item"][2]["request"];
+        assert_eq!(upload["body"]["urlencoded"][0], json!({ "key": "b", "value": "two words", "type": "text" }));
+    }
+
+    #[test]
+    fn converts_captures_and_asserts() {
+        let (collection, warnings) = parsed();
+        let create = &collection["item"][0];
+        assert_eq!(create["extract"], json!({ "thing_id": "$.id", "location": "header:Location" }));
+
+        let create_calls = calls(create);
+        let kinds: Vec<(&str, &str)> = create_calls
+            .iter()
+            .map(|(kind, arguments)| (*kind, arguments["text"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("capture", r#"slug: jsonpath "$.path" regex /\/(\w+)/"#),
+                ("assert", "status == 201"),
+                ("assert", r#"jsonpath "$.tags" count > 2"#),
+                ("assert", r#"jsonpath "$.missing" not exists"#),
+            ]
+        );
+        assert_eq!(create_calls[0].1["filters"], json!([["regex", { "regex": "/(\\w+)" }]]));
+        assert_eq!(create_calls[2].1["value"], 2);
+        assert_eq!(create_calls[3].1["not"], true);
+
+        // `HTTP *` expects nothing
+        assert!(collection["item"][1].get("event").is_none());
+        let upload = calls(&collection["item"][2]);
+        assert_eq!(upload[1].1["text"], "body");
+        assert_eq!(upload[1].1["value"], "done");
+
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("xpath"));
+    }
+
+    #[test]
+    fn rejects_what_is_not_a_request() {
+        let Err(error) = parse("HTTP 200\n", "x") else {
+            panic!("parsed a response line as a request");
+        };
+        assert!(error.to_string().starts_with("Hurl file line 1: expected a request"));
+    }
+
+    #[test]
+    fn encodes_query_parameters() {
+        assert_eq!(encode_query("a b&c=d"), "a+b%26c%3Dd");
+        assert_eq!(encode_query("{{a b}}/{{c}}"), "{{a b}}%2F{{c}}");
+        assert_eq!(encode_query("{{open"), "%7B%7Bopen");
+    }
+
+    #[test]
+    fn exports_what_it_parses() {
+        let imported = parse(FILE, "suite").unwrap();
+        let exported = export(&imported.collection);
+        assert!(exported.starts_with("# Create a thing\nPOST {{host}}/things\n"));
+        assert!(exported.contains("\nHTTP 201\n[Captures]\nlocation: header \"Location\"\nthing_id: jsonpath \"$.id\"\n"));
+
+        let reparsed = parse(&exported, "suite").unwrap();
+        let items = |collection: &PostmanCollection| -> Vec<JsonValue> {
+            collection
+                .get_all_request_contexts()
+                .iter()
+                .map(|context| {
+                    let item = serde_json::to_value(context.item).unwrap();
+                    json!([item["request"], item["extract"], item["event"]])
+                })
+                .collect()
+        };
+        assert_eq!(items(&reparsed.collection), items(&imported.collection));
+    }
+}
@@ -0,0 +1,717 @@
+//! Converts Insomnia v4 exports to the Postman collection model.
+//!
+//! An export is a flat list of resources linked by `parentId`: workspaces,
+//! request groups (folders), requests and environments. The workspace's base
+//! environment becomes the collection variables, and its sub-environments
+//! become Postman environments to pick from by name. Postman has no folder
+//! scope, so a folder environment becomes a folder pre-request script that
+//! sets its values with `pm.variables`, overriding the base environment for
+//! the folder's requests.
+//!
+//! Nunjucks templates are rewritten as Postman placeholders: `{{ _.host }}`
+//! becomes `{{host}}` (nested environment objects are flattened, so
+//! `{{ _.api.url }}` becomes `{{api.url}}`), and the `uuid`, `now` and
+//! `faker` tags become dynamic variables. Other tags, such as response
+//! chaining, are left as they are and reported as warnings.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Map, Value as JsonValue};
+
+use crate::collection::{Environment, ImportedCollection};
+use crate::dynamic::DynamicVariables;
+use crate::legacy::SCHEMA_V2_1;
+
+/// Whether `value` is an Insomnia export, of any format version.
+pub fn is_export(value: &JsonValue) -> bool {
+    value.get("_type").and_then(JsonValue::as_str) == Some("export") && value.get("__export_format").is_some()
+}
+
+/// Converts an Insomnia v4 export. Its environments are the sub-environments
+/// of the base environment.
+pub fn convert(export: &JsonValue) -> Result<ImportedCollection> {
+    let format = export.get("__export_format").and_then(JsonValue::as_u64);
+    if format != Some(4) {
+        return Err(anyhow!(
+            "Unsupported Insomnia export format {}; only v4 exports are supported",
+            export.get("__export_format").cloned().unwrap_or_default()
+        ));
+    }
+    let resources = export
+        .get("resources")
+        .and_then(JsonValue::as_array)
+        .context("Insomnia export has no `resources`")?;
+
+    let mut converter = Converter {
+        resources,
+        variables: Vec::new(),
+        warnings: Vec::new(),
+    };
+    let workspaces = converter.children_of_type(None, "workspace");
+    if workspaces.is_empty() {
+        return Err(anyhow!("Insomnia export has no workspace"));
+    }
+
+    let mut environments = Vec::new();
+    for workspace in &workspaces {
+        for base in converter.children_of_type(Some(id(workspace)), "environment") {
+            converter.add_variables(base);
+            for environment in converter.children_of_type(Some(id(base)), "environment") {
+                environments.push(converter.environment(environment)?);
+            }
+        }
+    }
+
+    // A single workspace is the collection; several become its top-level folders
+    let (info, item) = match workspaces.as_slice() {
+        [workspace] => (
+            converter.info(workspace),
+            converter.items(id(workspace), &[]),
+        ),
+        _ => (
+            json!({ "name": "Insomnia export", "schema": SCHEMA_V2_1 }),
+            workspaces
+                .iter()
+                .map(|workspace| {
+                    let mut folder = converter.info(workspace);
+                    folder["item"] = json!(converter.items(id(workspace), &[]));
+                    if let Some(object) = folder.as_object_mut() {
+                        object.remove("schema");
+                        object.remove("_postman_id");
+                    }
+                    folder
+                })
+                .collect(),
+        ),
+    };
+
+    let mut collection = json!({ "info": info, "item": item });
+    if !converter.variables.is_empty() {
+        collection["variable"] = json!(converter.variables);
+    }
+    let collection = serde_json::from_value(collection).context("Failed to convert Insomnia export")?;
+
+    Ok(ImportedCollection {
+        collection,
+        environments,
+        warnings: converter.warnings,
+    })
+}
+
+struct Converter<'a> {
+    resources: &'a [JsonValue],
+    /// Collection variables from the base environments
+    variables: Vec<JsonValue>,
+    warnings: Vec<String>,
+}
+
+impl<'a> Converter<'a> {
+    /// The resources of `resource_type` under `parent` (or at the top level),
+    /// in Insomnia's sort order.
+    fn children_of_type(&self, parent: Option<&str>, resource_type: &str) -> Vec<&'a JsonValue> {
+        let mut children: Vec<&JsonValue> = self
+            .resources
+            .iter()
+            .filter(|resource| resource.get("_type").and_then(JsonValue::as_str) == Some(resource_type))
+            .filter(|resource| match parent {
+                Some(parent) => resource.get("parentId").and_then(JsonValue::as_str) == Some(parent),
+                None => true,
+            })
+            .collect();
+        children.sort_by(|a, b| sort_key(a).total_cmp(&sort_key(b)));
+        children
+    }
+
+    fn info(&self, workspace: &JsonValue) -> JsonValue {
+        let mut info = json!({
+            "_postman_id": id(workspace),
+            "name": text(workspace, "name"),
+            "schema": SCHEMA_V2_1,
+        });
+        if let Some(description) = non_empty(workspace, "description") {
+            info["description"] = json!(description);
+        }
+        info
+    }
+
+    /// The folders and requests under `parent`. `headers` are inherited from
+    /// the enclosing folders, since Postman has no folder headers.
+    fn items(&mut self, parent: &str, headers: &[JsonValue]) -> Vec<JsonValue> {
+        let mut children: Vec<&JsonValue> = self
+            .resources
+            .iter()
+            .filter(|resource| resource.get("parentId").and_then(JsonValue::as_str) == Some(parent))
+            .collect();
+        children.sort_by(|a, b| sort_key(a).total_cmp(&sort_key(b)));
+
+        let mut items = Vec::new();
+        for child in children {
+            match child.get("_type").and_then(JsonValue::as_str).unwrap_or_default() {
+                "request_group" => items.push(self.folder(child, headers)),
+                "request" => items.push(self.request(child, headers)),
+                "environment" | "cookie_jar" | "api_spec" => {}
+                other => self.warnings.push(format!(
+                    "Skipped Insomnia {} '{}': only HTTP requests are supported",
+                    other.replace('_', " "),
+                    text(child, "name")
+                )),
+            }
+        }
+        items
+    }
+
+    fn folder(&mut self, group: &JsonValue, headers: &[JsonValue]) -> JsonValue {
+        let mut inherited = headers.to_vec();
+        inherited.extend(self.headers(group));
+        let mut folder = json!({
+            "id": id(group),
+            "name": text(group, "name"),
+            "item": self.items(id(group), &inherited),
+        });
+        if let Some(description) = non_empty(group, "description") {
+            folder["description"] = json!(description);
+        }
+        if let Some(auth) = self.auth(group) {
+            folder["auth"] = auth;
+        }
+        let mut events: Vec<JsonValue> = self.environment_script(group).into_iter().collect();
+        if let Some(JsonValue::Array(own)) = self.events(group) {
+            events.extend(own);
+        }
+        if !events.is_empty() {
+            folder["event"] = json!(events);
+        }
+        folder
+    }
+
+    /// A pre-request event that sets the variables of a folder's environment.
+    fn environment_script(&mut self, group: &JsonValue) -> Option<JsonValue> {
+        let exec: Vec<String> = self
+            .environment_variables(group.get("environment").unwrap_or(&JsonValue::Null))
+            .iter()
+            .map(|variable| format!("pm.variables.set({}, {});", variable["key"], variable["value"]))
+            .collect();
+        if exec.is_empty() {
+            return None;
+        }
+        Some(json!({
+            "listen": "prerequest",
+            "script": { "type": "text/javascript", "exec": exec },
+        }))
+    }
+
+    fn request(&mut self, request: &JsonValue, inherited_headers: &[JsonValue]) -> JsonValue {
+        let mut headers: Vec<JsonValue> = inherited_headers
+            .iter()
+            .filter(|inherited| {
+                let key = inherited["key"].as_str().unwrap_or_default();
+                !self
+                    .headers(request)
+                    .iter()
+                    .any(|header| header["key"].as_str().is_some_and(|own| own.eq_ignore_ascii_case(key)))
+            })
+            .cloned()
+            .collect();
+        headers.extend(self.headers(request));
+
+        let mut converted = json!({
+            "method": non_empty(request, "method").unwrap_or("GET"),
+            "header": headers,
+            "url": self.url(request),
+        });
+        if let Some(body) = self.body(request) {
+            converted["body"] = body;
+        }
+        if let Some(auth) = self.auth(request) {
+            converted["auth"] = auth;
+        }
+
+        let mut item = json!({
+            "id": id(request),
+            "name": text(request, "name"),
+            "request": converted,
+        });
+        if let Some(description) = non_empty(request, "description") {
+            item["description"] = json!(description);
+        }
+        if let Some(events) = self.events(request) {
+            item["event"] = events;
+        }
+        item
+    }
+
+    /// The URL with the `parameters` list and `pathParameters` of a request.
+    fn url(&mut self, request: &JsonValue) -> JsonValue {
+        let raw = self.template(text(request, "url"));
+        let (base, query) = match raw.split_once('?') {
+            Some((base, query)) => (base.to_string(), Some(query.to_string())),
+            None => (raw.clone(), None),
+        };
+
+        // The query array replaces the raw query string when the request is
+        // sent, so it lists the URL's own parameters too
+        let mut params: Vec<JsonValue> = query
+            .iter()
+            .flat_map(|query| query.split('&'))
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((key, value)) => json!({ "key": key, "value": value }),
+                None => json!({ "key": pair, "value": null }),
+            })
+            .collect();
+        for param in array(request, "parameters") {
+            let mut converted = json!({
+                "key": self.template(text(param, "name")),
+                "value": self.template(text(param, "value")),
+            });
+            if is_disabled(param) {
+                converted["disabled"] = json!(true);
+            }
+            params.push(converted);
+        }
+
+        let enabled: Vec<String> = params
+            .iter()
+            .filter(|param| param.get("disabled").is_none())
+            .map(|param| match param["value"].as_str() {
+                Some(value) => format!("{}={}", param["key"].as_str().unwrap_or_default(), value),
+                None => param["key"].as_str().unwrap_or_default().to_string(),
+            })
+            .collect();
+        let raw = if enabled.is_empty() { base } else { format!("{}?{}", base, enabled.join("&")) };
+
+        let mut url = json!({ "raw": raw });
+        if !params.is_empty() {
+            url["query"] = json!(params);
+        }
+        let variables: Vec<JsonValue> = array(request, "pathParameters")
+            .iter()
+            .map(|param| json!({ "key": text(param, "name"), "value": self.template(text(param, "value")) }))
+            .collect();
+        if !variables.is_empty() {
+            url["variable"] = json!(variables);
+        }
+        url
+    }
+
+    fn headers(&mut self, resource: &JsonValue) -> Vec<JsonValue> {
+        array(resource, "headers")
+            .iter()
+            .filter(|header| !text(header, "name").is_empty())
+            .map(|header| {
+                let mut converted = json!({
+                    "key": text(header, "name"),
+                    "value": self.template(text(header, "value")),
+                });
+                if is_disabled(header) {
+                    converted["disabled"] = json!(true);
+                }
+                converted
+            })
+            .collect()
+    }
+
+    /// The body, by its MIME type: forms, GraphQL, a file, or raw text.
+    fn body(&mut self, request: &JsonValue) -> Option<JsonValue> {
+        let body = request.get("body").filter(|body| body.as_object().is_some_and(|body| !body.is_empty()))?;
+        let mime_type = text(body, "mimeType");
+
+        match mime_type {
+            "application/x-www-form-urlencoded" | "multipart/form-data" => {
+                let fields: Vec<JsonValue> = array(body, "params")
+                    .iter()
+                    .map(|param| {
+                        let mut field = json!({ "key": self.template(text(param, "name")) });
+                        if text(param, "type") == "file" {
+                            field["type"] = json!("file");
+                            field["src"] = json!(text(param, "fileName"));
+                        } else {
+                            field["value"] = json!(self.template(text(param, "value")));
+                            field["type"] = json!("text");
+                        }
+                        if is_disabled(param) {
+                            field["disabled"] = json!(true);
+                        }
+                        field
+                    })
+                    .collect();
+                let mode = if mime_type == "multipart/form-data" { "formdata" } else { "urlencoded" };
+                Some(json!({ "mode": mode, mode: fields }))
+            }
+            "application/graphql" => {
+                let graphql: JsonValue = serde_json::from_str(text(body, "text")).unwrap_or_default();
+                let variables = match graphql.get("variables") {
+                    Some(JsonValue::Null) | None => String::new(),
+                    Some(variables) => serde_json::to_string_pretty(variables).unwrap_or_default(),
+                };
+                Some(json!({
+                    "mode": "graphql",
+                    "graphql": {
+                        "query": self.template(text(&graphql, "query")),
+                        "variables": self.template(&variables),
+                    },
+                }))
+            }
+            _ if !text(body, "fileName").is_empty() => Some(json!({
+                "mode": "file",
+                "file": { "src": text(body, "fileName") },
+            })),
+            _ => {
+                let language = match mime_type {
+                    mime if mime.contains("json") => "json",
+                    mime if mime.contains("xml") => "xml",
+                    mime if mime.contains("html") => "html",
+                    mime if mime.contains("javascript") => "javascript",
+                    _ => "text",
+                };
+                Some(json!({
+                    "mode": "raw",
+                    "raw": self.template(text(body, "text")),
+                    "options": { "raw": { "language": language } },
+                }))
+            }
+        }
+    }
+
+    /// Insomnia auth as a Postman auth block. An empty block inherits the
+    /// folder's auth.
+    fn auth(&mut self, resource: &JsonValue) -> Option<JsonValue> {
+        let auth = resource.get("authentication").filter(|auth| auth.get("type").is_some())?;
+        if is_disabled(auth) {
+            return Some(json!({ "type": "noauth" }));
+        }
+
+        let mut param = |key: &str| self.template(text(auth, key));
+        let block = |auth_type: &str, params: Vec<(&str, String)>| {
+            let params: Vec<JsonValue> = params
+                .into_iter()
+                .map(|(key, value)| json!({ "key": key, "value": value, "type": "string" }))
+                .collect();
+            json!({ "type": auth_type, auth_type: params })
+        };
+
+        Some(match text(auth, "type") {
+            "none" => json!({ "type": "noauth" }),
+            "basic" => block("basic", vec![("username", param("username")), ("password", param("password"))]),
+            "digest" => block("digest", vec![("username", param("username")), ("password", param("password"))]),
+            "bearer" => {
+                let prefix = param("prefix");
+                if prefix.is_empty() || prefix.eq_ignore_ascii_case("bearer") {
+                    block("bearer", vec![("token", param("token"))])
+                } else {
+                    // Postman's bearer auth always says `Bearer`
+                    let value = format!("{} {}", prefix, param("token"));
+                    block("apikey", vec![("key", "Authorization".to_string()), ("value", value), ("in", "header".to_string())])
+                }
+            }
+            "apikey" => {
+                let location = if text(auth, "addTo") == "queryParams" { "query" } else { "header" };
+                block("apikey", vec![("key", param("key")), ("value", param("value")), ("in", location.to_string())])
+            }
+            "oauth2" => {
+                let client_authentication = if auth.get("credentialsInBody").and_then(JsonValue::as_bool) == Some(true) {
+                    "body"
+                } else {
+                    "header"
+                };
+                let mut params = vec![
+                    ("grant_type", text(auth, "grantType").to_string()),
+                    ("accessTokenUrl", param("accessTokenUrl")),
+                    ("authUrl", param("authorizationUrl")),
+                    ("clientId", param("clientId")),
+                    ("clientSecret", param("clientSecret")),
+                    ("scope", param("scope")),
+                    ("username", param("username")),
+                    ("password", param("password")),
+                    ("client_authentication", client_authentication.to_string()),
+                ];
+                let prefix = param("tokenPrefix");
+                if !prefix.is_empty() {
+                    params.push(("headerPrefix", prefix));
+                }
+                block("oauth2", params)
+            }
+            other => {
+                self.warnings.push(format!(
+                    "Insomnia {} auth of '{}' is not supported; it is sent without auth",
+                    other,
+                    text(resource, "name")
+                ));
+                json!({ "type": "noauth" })
+            }
+        })
+    }
+
+    /// Pre-request and after-response scripts. Insomnia's `insomnia` script
+    /// API mirrors Postman's `pm`.
+    fn events(&self, resource: &JsonValue) -> Option<JsonValue> {
+        let events: Vec<JsonValue> = [("preRequestScript", "prerequest"), ("afterResponseScript", "test")]
+            .into_iter()
+            .filter_map(|(field, listen)| {
+                let source = non_empty(resource, field)?;
+                Some(json!({
+                    "listen": listen,
+                    "script": { "type": "text/javascript", "exec": source.lines().collect::<Vec<_>>() },
+                }))
+            })
+            .collect();
+        (!events.is_empty()).then(|| json!(events))
+    }
+
+    fn environment(&mut self, environment: &JsonValue) -> Result<Environment> {
+        let mut values = self.environment_variables(environment.get("data").unwrap_or(&JsonValue::Null));
+        for value in &mut values {
+            value["enabled"] = json!(true);
+        }
+        serde_json::from_value(json!({
+            "id": id(environment),
+            "name": text(environment, "name"),
+            "values": values,
+        }))
+        .context("Failed to convert Insomnia environment")
+    }
+
+    /// Adds an environment's variables to the collection variables, keeping
+    /// ones already defined.
+    fn add_variables(&mut self, environment: &JsonValue) {
+        for variable in self.environment_variables(environment.get("data").unwrap_or(&JsonValue::Null)) {
+            if !self.variables.iter().any(|existing| existing["key"] == variable["key"]) {
+                self.variables.push(variable);
+            }
+        }
+    }
+
+    fn environment_variables(&mut self, data: &JsonValue) -> Vec<JsonValue> {
+        let mut values = Vec::new();
+        if let Some(data) = data.as_object() {
+            flatten("", data, &mut values);
+        }
+        values
+            .into_iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    JsonValue::String(text) => json!(self.template(&text)),
+                    other => other,
+                };
+                json!({ "key": key, "value": value, "type": "default" })
+            })
+            .collect()
+    }
+
+    /// Rewrites Nunjucks variables and tags as Postman placeholders.
+    fn template(&mut self, text: &str) -> String {
+        let mut output = String::new();
+        let mut rest = text;
+
+        loop {
+            let variable = rest.find("{{");
+            let tag = rest.find("{%");
+            let (start, close) = match (variable, tag) {
+                (Some(variable), Some(tag)) if tag < variable => (tag, "%}"),
+                (Some(variable), _) => (variable, "}}"),
+                (None, Some(tag)) => (tag, "%}"),
+                (None, None) => break,
+            };
+            let Some(length) = rest[start + 2..].find(close) else {
+                break;
+            };
+            let expression = &rest[start..start + 2 + length + 2];
+            let inner = rest[start + 2..start + 2 + length].trim();
+
+            output.push_str(&rest[..start]);
+            let converted = if close == "}}" { variable_name(inner) } else { tag_placeholder(inner) };
+            match converted {
+                Some(converted) => output.push_str(&converted),
+                None => {
+                    let warning = format!("Unsupported Insomnia template {} left as it is", expression);
+                    if !self.warnings.contains(&warning) {
+                        self.warnings.push(warning);
+                    }
+                    output.push_str(expression);
+                }
+            }
+            rest = &rest[start + expression.len()..];
+        }
+
+        output.push_str(rest);
+        output
+    }
+}
+
+/// `_.name` or `name` as `{{name}}`; other expressions, such as filters, are
+/// not converted.
+fn variable_name(expression: &str) -> Option<String> {
+    let name = expression.strip_prefix("_.").unwrap_or(expression).trim();
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '$'));
+    valid.then(|| format!("{{{{{}}}}}", name))
+}
+
+/// The dynamic variable for a `uuid`, `now` or `faker` tag.
+fn tag_placeholder(tag: &str) -> Option<String> {
+    let (name, arguments) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+    let arguments: Vec<&str> = arguments
+        .split(',')
+        .map(|argument| argument.trim().trim_matches(|c| c == '\'' || c == '"'))
+        .collect();
+
+    let variable = match (name, arguments.first().copied().unwrap_or_default()) {
+        ("uuid", _) => "$guid".to_string(),
+        ("now", "iso-8601") => "$isoTimestamp".to_string(),
+        ("now", "unix") => "$timestamp".to_string(),
+        ("faker", faker) if DynamicVariables::is_defined(&format!("${}", faker)) => format!("${}", faker),
+        _ => return None,
+    };
+    Some(format!("{{{{{}}}}}", variable))
+}
+
+/// Flattens nested environment objects into dotted names, as Nunjucks
+/// accesses them.
+fn flatten(prefix: &str, data: &Map<String, JsonValue>, values: &mut Vec<(String, JsonValue)>) {
+    for (key, value) in data {
+        let name = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        match value {
+            JsonValue::Object(nested) => flatten(&name, nested, values),
+            JsonValue::Array(_) => values.push((name, json!(value.to_string()))),
+            other => values.push((name, other.clone())),
+        }
+    }
+}
+
+fn id(resource: &JsonValue) -> &str {
+    text(resource, "_id")
+}
+
+fn sort_key(resource: &JsonValue) -> f64 {
+    resource.get("metaSortKey").and_then(JsonValue::as_f64).unwrap_or_default()
+}
+
+fn text<'v>(value: &'v JsonValue, key: &str) -> &'v str {
+    value.get(key).and_then(JsonValue::as_str).unwrap_or_default()
+}
+
+fn non_empty<'v>(value: &'v JsonValue, key: &str) -> Option<&'v str> {
+    Some(text(value, key)).filter(|text| !text.trim().is_empty())
+}
+
+fn array<'v>(value: &'v JsonValue, key: &str) -> &'v [JsonValue] {
+    value.get(key).and_then(JsonValue::as_array).map(Vec::as_slice).unwrap_or_default()
+}
+
+fn is_disabled(value: &JsonValue) -> bool {
+    value.get("disabled").and_then(JsonValue::as_bool).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export() -> JsonValue {
+        json!({
+            "_type": "export",
+            "__export_format": 4,
+            "resources": [
+                { "_id": "wrk_1", "parentId": null, "_type": "workspace", "name": "Frontend API" },
+                { "_id": "env_base", "parentId": "wrk_1", "_type": "environment", "name": "Base", "data": { "base_url": "http://localhost", "api": { "version": "v2" } } },
+                { "_id": "env_dev", "parentId": "env_base", "_type": "environment", "name": "Dev", "data": { "token": "dev-token" } },
+                {
+                    "_id": "fld_users", "parentId": "wrk_1", "_type": "request_group", "name": "Users", "metaSortKey": -2,
+                    "environment": { "page_size": 20, "base_url": "http://users.local" },
+                    "headers": [{ "name": "X-Team", "value": "frontend" }],
+                    "authentication": { "type": "bearer", "token": "{{ _.token }}" },
+                },
+                {
+                    "_id": "req_list", "parentId": "fld_users", "_type": "request", "name": "List users", "method": "GET",
+                    "url": "{{ _.base_url }}/{{_.api.version}}/users?sort=name",
+                    "parameters": [{ "name": "limit", "value": "{{ _.page_size }}" }, { "name": "skip", "value": "1", "disabled": true }],
+                    "headers": [{ "name": "X-Request-Id", "value": "{% uuid 'v4' %}" }],
+                    "authentication": {},
+                    "afterResponseScript": "insomnia.test('ok', () => {});",
+                },
+                {
+                    "_id": "req_create", "parentId": "wrk_1", "_type": "request", "name": "Create", "method": "POST", "metaSortKey": 1,
+                    "url": "{{ _.base_url }}/users",
+                    "body": { "mimeType": "application/json", "text": "{\"at\": \"{% now 'iso-8601', '' %}\", \"id\": \"{% response 'body', 'req_list', '$.id' %}\"}" },
+                    "authentication": { "type": "apikey", "key": "api_key", "value": "secret", "addTo": "queryParams" },
+                },
+                {
+                    "_id": "req_signed", "parentId": "wrk_1", "_type": "request", "name": "Signed", "metaSortKey": 2,
+                    "url": "{{ _.base_url }}/signed",
+                    "authentication": { "type": "hawk", "id": "id", "key": "key" },
+                },
+                { "_id": "grpc_1", "parentId": "wrk_1", "_type": "grpc_request", "name": "Stream" },
+            ],
+        })
+    }
+
+    #[test]
+    fn detects_exports() {
+        assert!(is_export(&export()));
+        assert!(!is_export(&json!({ "info": {}, "item": [] })));
+        assert!(convert(&json!({ "_type": "export", "__export_format": 3, "resources": [] })).is_err());
+    }
+
+    #[test]
+    fn converts_folders_and_requests() {
+        let imported = convert(&export()).unwrap();
+        let collection = serde_json::to_value(&imported.collection).unwrap();
+        assert_eq!(collection["info"]["name"], "Frontend API");
+
+        let folder = &collection["item"][0];
+        assert_eq!(folder["name"], "Users");
+        assert_eq!(folder["auth"]["bearer"][0]["value"], "{{token}}");
+        // The folder environment overrides the base environment for the folder
+        assert_eq!(folder["event"][0]["listen"], "prerequest");
+        assert_eq!(
+            folder["event"][0]["script"]["exec"],
+            json!(["pm.variables.set(\"base_url\", \"http://users.local\");", "pm.variables.set(\"page_size\", 20);"])
+        );
+
+        let list = &folder["item"][0];
+        assert_eq!(list["request"]["url"]["raw"], "{{base_url}}/{{api.version}}/users?sort=name&limit={{page_size}}");
+        assert_eq!(list["request"]["url"]["query"][2]["disabled"], true);
+        // Folder headers are copied into their requests
+        assert_eq!(list["request"]["header"][0]["key"], "X-Team");
+        assert_eq!(list["request"]["header"][1]["value"], "{{$guid}}");
+        assert!(list["request"].get("auth").is_none());
+        assert_eq!(list["event"][0]["listen"], "test");
+
+        let create = &collection["item"][1];
+        assert_eq!(create["request"]["auth"]["type"], "apikey");
+        assert_eq!(create["request"]["auth"]["apikey"][2]["value"], "query");
+        let raw = create["request"]["body"]["raw"].as_str().unwrap();
+        assert!(raw.contains("{{$isoTimestamp}}"));
+
+        // Unsupported auth is sent without auth
+        assert_eq!(collection["item"][2]["request"]["auth"], json!({ "type": "noauth" }));
+    }
+
+    #[test]
+    fn converts_environments_and_variables() {
+        let imported = convert(&export()).unwrap();
+        let collection = serde_json::to_value(&imported.collection).unwrap();
+        let keys: Vec<&str> = collection["variable"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|variable| variable["key"].as_str().unwrap())
+            .collect();
+        // Environment data is read in key order
+        assert_eq!(keys, ["api.version", "base_url"]);
+        assert_eq!(collection["variable"][1]["value"], "http://localhost");
+
+        let dev = imported.environment("Dev").unwrap();
+        assert_eq!(dev.values[0].key, "token");
+        assert!(imported.environment("Prod").is_err());
+    }
+
+    #[test]
+    fn reports_what_it_cannot_convert() {
+        let imported = convert(&export()).unwrap();
+        assert!(imported.warnings.iter().any(|warning| warning.contains("{% response 'body', 'req_list', '$.id' %}")));
+        assert!(imported.warnings.iter().any(|warning| warning.contains("grpc request 'Stream'")));
+        assert!(imported.warnings.contains(&"Insomnia hawk auth of 'Signed' is not supported; it is sent without auth".to_string()));
+        assert_eq!(imported.warnings.len(), 3);
+    }
+}
@@ -0,0 +1,119 @@
+//! Data files for data-driven runs: each row feeds the data variable scope
+//! for one iteration of the collection.
+//!
+//! CSV files (`.csv`) have a header row naming the variables. Anything else
+//! is read as a JSON array of objects.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// One iteration's variables.
+pub type DataRow = HashMap<String, String>;
+
+/// Loads the rows of a CSV or JSON data file.
+pub fn load(path: &Path) -> Result<Vec<DataRow>> {
+    let is_csv = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+    let text = std::fs::read_to_string(path).context("Failed to read iteration data file")?;
+
+    if is_csv {
+        parse_csv(&text)
+    } else {
+        parse_json(&text)
+    }
+}
+
+fn parse_csv(text: &str) -> Result<Vec<DataRow>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::Headers)
+        .from_reader(text.trim_start_matches('\u{feff}').as_bytes());
+    let headers = reader.headers().context("Failed to parse iteration data CSV")?.clone();
+
+    reader
+        .records()
+        .map(|record| {
+            let record = record.context("Failed to parse iteration data CSV")?;
+            Ok(headers
+                .iter()
+                .zip(record.iter())
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect())
+        })
+        .collect()
+}
+
+fn parse_json(text: &str) -> Result<Vec<DataRow>> {
+    let rows: Vec<JsonValue> = serde_json::from_str(text).context("Failed to parse iteration data JSON")?;
+
+    rows.into_iter()
+        .enumerate()
+        .map(|(index, row)| match row {
+            JsonValue::Object(values) => Ok(values
+                .into_iter()
+                .map(|(name, value)| {
+                    let value = match value {
+                        JsonValue::String(text) => text,
+                        JsonValue::Null => String::new(),
+                        other => other.to_string(),
+                    };
+                    (name, value)
+                })
+                .collect()),
+            _ => Err(anyhow!("Iteration data row {} is not an object", index + 1)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(values: &[(&str, &str)]) -> DataRow {
+        values.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn reads_csv_rows() {
+        let rows = parse_csv("\u{feff}user , note\nada,\"hello, world\"\nbob,\"say \"\"hi\"\"\"\n").unwrap();
+        assert_eq!(rows, [
+            row(&[("user", "ada"), ("note", "hello, world")]),
+            row(&[("user", "bob"), ("note", "say \"hi\"")]),
+        ]);
+
+        // Rows with the wrong number of fields are an error
+        assert!(parse_csv("a,b\n1,2,3\n").is_err());
+        assert!(parse_csv("a,b\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn reads_json_rows() {
+        let rows = parse_json(r#"[{"user": "ada", "id": 7, "admin": true, "note": null, "tags": ["a"]}, {}]"#).unwrap();
+        assert_eq!(rows, [
+            row(&[("user", "ada"), ("id", "7"), ("admin", "true"), ("note", ""), ("tags", "[\"a\"]")]),
+            row(&[]),
+        ]);
+
+        let Err(error) = parse_json(r#"[{"user": "ada"}, "bob"]"#) else { panic!("a string row was read") };
+        assert_eq!(error.to_string(), "Iteration data row 2 is not an object");
+        assert!(parse_json(r#"{"user": "ada"}"#).is_err());
+    }
+
+    #[test]
+    fn picks_the_format_from_the_extension() {
+        let dir = std::env::temp_dir().join(format!("harbinger-data-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let csv_path = dir.join("users.CSV");
+        std::fs::write(&csv_path, "user\nada\n").unwrap();
+        let json_path = dir.join("users.data");
+        std::fs::write(&json_path, r#"[{"user": "bob"}]"#).unwrap();
+
+        assert_eq!(load(&csv_path).unwrap(), [row(&[("user", "ada")])]);
+        assert_eq!(load(&json_path).unwrap(), [row(&[("user", "bob")])]);
+        assert!(load(&dir.join("missing.csv")).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,573 @@
+//! Converts collections in Postman's older formats to the v2.1 shape the
+//! collection model reads.
+//!
+//! v1 collections keep every request in one flat `requests` list and build
+//! the tree from `order` / `folders_order` id lists. v2.0 collections already
+//! have the v2.1 tree, but may give headers as a single string and identify
+//! variables by `id` instead of `key`.
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Map, Value as JsonValue};
+use std::collections::HashSet;
+
+pub const SCHEMA_V2_1: &str = "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+
+/// Converts a v1 collection to v2.1.
+pub fn convert_v1(collection: &JsonValue) -> Result<JsonValue> {
+    let requests = collection
+        .get("requests")
+        .and_then(JsonValue::as_array)
+        .ok_or_else(|| anyhow!("v1 collection has no requests list"))?;
+    let folders = collection
+        .get("folders")
+        .and_then(JsonValue::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let converter = V1Converter { requests, folders };
+    let mut visited = HashSet::new();
+    let mut item = Vec::new();
+
+    // Folders that are not nested in another one sit at the top
+    let nested: HashSet<&str> = folders
+        .iter()
+        .flat_map(|folder| ids(folder, "folders_order"))
+        .collect();
+    let top_folders: Vec<&str> = match collection.get("folders_order") {
+        Some(_) => ids(collection, "folders_order"),
+        None => folders
+            .iter()
+            .filter_map(|folder| folder.get("id").and_then(JsonValue::as_str))
+            .filter(|id| !nested.contains(id))
+            .collect(),
+    };
+    for id in top_folders {
+        item.extend(converter.folder(id, &mut visited));
+    }
+
+    // Requests outside any folder; exports without `order` put each request
+    // in a folder through its `folder` id
+    let top_requests: Vec<&str> = match collection.get("order") {
+        Some(_) => ids(collection, "order"),
+        None => requests
+            .iter()
+            .filter(|request| request.get("folder").is_none_or(JsonValue::is_null))
+            .filter_map(|request| request.get("id").and_then(JsonValue::as_str))
+            .collect(),
+    };
+    for id in top_requests {
+        item.extend(converter.request(id));
+    }
+
+    let mut info = Map::new();
+    info.insert("name".to_string(), collection.get("name").cloned().unwrap_or(json!("")));
+    if let Some(description) = description(collection) {
+        info.insert("description".to_string(), json!(description));
+    }
+    if let Some(id) = collection.get("id") {
+        info.insert("_postman_id".to_string(), id.clone());
+    }
+    info.insert("schema".to_string(), json!(SCHEMA_V2_1));
+
+    let mut converted = json!({ "info": info, "item": item });
+    if let Some(variables) = collection.get("variables").and_then(JsonValue::as_array) {
+        converted["variable"] = json!(variables.iter().map(variable).collect::<Vec<_>>());
+    }
+    if let Some(auth) = collection.get("auth").filter(|auth| !auth.is_null()) {
+        converted["auth"] = auth.clone();
+    }
+    if let Some(events) = collection.get("events").filter(|events| !events.is_null()) {
+        converted["event"] = events.clone();
+    }
+
+    Ok(converted)
+}
+
+/// Brings a v2.0 collection in line with v2.1.
+pub fn normalize_v2_0(collection: &mut JsonValue) {
+    if let Some(variables) = collection.get_mut("variable").and_then(JsonValue::as_array_mut) {
+        variables.iter_mut().for_each(normalize_variable);
+    }
+    if let Some(items) = collection.get_mut("item").and_then(JsonValue::as_array_mut) {
+        items.iter_mut().for_each(normalize_item);
+    }
+}
+
+fn normalize_item(item: &mut JsonValue) {
+    if let Some(items) = item.get_mut("item").and_then(JsonValue::as_array_mut) {
+        items.iter_mut().for_each(normalize_item);
+    }
+
+    let Some(request) = item.get_mut("request").filter(|request| request.is_object()) else {
+        return;
+    };
+    if let Some(headers) = request.get("header").and_then(JsonValue::as_str) {
+        request["header"] = json!(parse_headers(headers));
+    }
+    if let Some(variables) = request
+        .get_mut("url")
+        .and_then(|url| url.get_mut("variable"))
+        .and_then(JsonValue::as_array_mut)
+    {
+        variables.iter_mut().for_each(normalize_variable);
+    }
+}
+
+/// v2.0 variables may only have an `id`.
+fn normalize_variable(variable: &mut JsonValue) {
+    if variable.get("key").is_none() {
+        if let Some(id) = variable.get("id").cloned() {
+            variable["key"] = id;
+        }
+    }
+}
+
+struct V1Converter<'a> {
+    requests: &'a [JsonValue],
+    folders: &'a [JsonValue],
+}
+
+impl V1Converter<'_> {
+    fn folder(&self, id: &str, visited: &mut HashSet<String>) -> Option<JsonValue> {
+        // A folder listed twice, or inside itself, is only converted once
+        if !visited.insert(id.to_string()) {
+            return None;
+        }
+        let folder = find(self.folders, id)?;
+
+        let mut item: Vec<JsonValue> = ids(folder, "folders_order")
+            .into_iter()
+            .filter_map(|id| self.folder(id, visited))
+            .collect();
+        let order = match folder.get("order") {
+            Some(_) => ids(folder, "order"),
+            None => self
+                .requests
+                .iter()
+                .filter(|request| request.get("folder").and_then(JsonValue::as_str) == Some(id))
+                .filter_map(|request| request.get("id").and_then(JsonValue::as_str))
+                .collect(),
+        };
+        item.extend(order.into_iter().filter_map(|id| self.request(id)));
+
+        let mut converted = json!({
+            "name": folder.get("name").cloned().unwrap_or(json!("")),
+            "item": item,
+        });
+        if let Some(description) = description(folder) {
+            converted["description"] = json!(description);
+        }
+        if let Some(auth) = folder.get("auth").filter(|auth| !auth.is_null()) {
+            converted["auth"] = auth.clone();
+        }
+        if let Some(events) = folder.get("events").filter(|events| !events.is_null()) {
+            converted["event"] = events.clone();
+        }
+        Some(converted)
+    }
+
+    fn request(&self, id: &str) -> Option<JsonValue> {
+        let request = find(self.requests, id)?;
+        let text = |key: &str| request.get(key).and_then(JsonValue::as_str).unwrap_or_default();
+
+        let mut url = json!({ "raw": text("url") });
+        // Older exports only have the `pathVariables` object
+        if let Some(variables) = request.get("pathVariableData").and_then(JsonValue::as_array) {
+            url["variable"] = json!(variables.iter().map(variable).collect::<Vec<_>>());
+        } else if let Some(variables) = request.get("pathVariables").and_then(JsonValue::as_object) {
+            let variables: Vec<JsonValue> = variables
+                .iter()
+                .map(|(key, value)| json!({ "key": key, "value": value }))
+                .collect();
+            url["variable"] = json!(variables);
+        }
+
+        let headers = match request.get("headerData").and_then(JsonValue::as_array) {
+            Some(headers) => headers.iter().map(key_value).collect(),
+            None => parse_headers(text("headers")),
+        };
+
+        let mut converted_request = json!({
+            "method": if text("method").is_empty() { "GET" } else { text("method") },
+            "url": url,
+            "header": headers,
+        });
+        if let Some(body) = body(request) {
+            converted_request["body"] = body;
+        }
+        if let Some(auth) = auth(request) {
+            converted_request["auth"] = auth;
+        }
+        if let Some(description) = description(request) {
+            converted_request["description"] = json!(description);
+        }
+
+        let mut converted = json!({ "name": text("name"), "request": converted_request });
+        match request.get("events").filter(|events| !events.is_null()) {
+            Some(events) => converted["event"] = events.clone(),
+            None => {
+                let events: Vec<JsonValue> = [("prerequest", "preRequestScript"), ("test", "tests")]
+                    .into_iter()
+                    .filter(|(_, key)| !text(key).trim().is_empty())
+                    .map(|(listen, key)| {
+                        json!({
+                            "listen": listen,
+                            "script": { "type": "text/javascript", "exec": text(key).lines().collect::<Vec<_>>() },
+                        })
+                    })
+                    .collect();
+                if !events.is_empty() {
+                    converted["event"] = json!(events);
+                }
+            }
+        }
+        if let Some(responses) = request.get("responses").and_then(JsonValue::as_array) {
+            converted["response"] = json!(responses.iter().map(response).collect::<Vec<_>>());
+        }
+
+        Some(converted)
+    }
+}
+
+fn body(request: &JsonValue) -> Option<JsonValue> {
+    let fields = || -> Vec<JsonValue> {
+        request
+            .get("data")
+            .and_then(JsonValue::as_array)
+            .map(|fields| fields.iter().map(key_value).collect())
+            .unwrap_or_default()
+    };
+
+    match request.get("dataMode").and_then(JsonValue::as_str)? {
+        "raw" => {
+            let raw = request.get("rawModeData").and_then(JsonValue::as_str).unwrap_or_default();
+            if raw.is_empty() {
+                return None;
+            }
+            let mut body = json!({ "mode": "raw", "raw": raw });
+            if let Some(language) = request.pointer("/dataOptions/raw/language") {
+                body["options"] = json!({ "raw": { "language": language } });
+            }
+            Some(body)
+        }
+        "urlencoded" => Some(json!({ "mode": "urlencoded", "urlencoded": fields() })),
+        "params" => Some(json!({ "mode": "formdata", "formdata": fields() })),
+        "graphql" => {
+            let graphql = request.get("graphqlModeData").cloned().unwrap_or(json!({}));
+            let variables = match graphql.get("variables") {
+                Some(JsonValue::String(variables)) => json!(variables),
+                Some(JsonValue::Null) | None => JsonValue::Null,
+                Some(variables) => json!(variables.to_string()),
+            };
+            Some(json!({
+                "mode": "graphql",
+                "graphql": { "query": graphql.get("query").cloned().unwrap_or(json!("")), "variables": variables },
+            }))
+        }
+        // v1 exports do not record the file of binary bodies
+        _ => None,
+    }
+}
+
+/// A v1 example response, with `responseCode`, `headers` and `text`, as a
+/// v2.1 one. The request it was saved for is kept when it is given inline
+/// rather than by id.
+fn response(response: &JsonValue) -> JsonValue {
+    let mut converted = Map::new();
+    let mut copy = |key: &str, value: Option<&JsonValue>| {
+        if let Some(value) = value.filter(|value| !value.is_null()) {
+            converted.insert(key.to_string(), value.clone());
+        }
+    };
+    copy("id", response.get("id"));
+    copy("name", response.get("name"));
+    copy("code", response.pointer("/responseCode/code"));
+    copy("status", response.pointer("/responseCode/name"));
+    copy("_postman_previewlanguage", response.get("language"));
+    copy("body", response.get("text"));
+    copy("responseTime", response.get("time"));
+    copy("cookie", response.get("cookies").filter(|cookies| cookies.is_array()));
+
+    match response.get("headers") {
+        Some(JsonValue::Array(headers)) => {
+            let headers: Vec<JsonValue> = headers
+                .iter()
+                .map(|header| {
+                    let mut header = key_value(header);
+                    // Headers carry a `name`, and older ones have no `key`
+                    if let Some(object) = header.as_object_mut() {
+                        if let Some(name) = object.remove("name") {
+                            object.entry("key").or_insert(name);
+                        }
+                    }
+                    header
+                })
+                .collect();
+            converted.insert("header".to_string(), json!(headers));
+        }
+        Some(JsonValue::String(headers)) => {
+            converted.insert("header".to_string(), json!(parse_headers(headers)));
+        }
+        _ => {}
+    }
+
+    if let Some(request) = response.get("request").filter(|request| request.get("url").is_some()) {
+        let text = |key: &str| request.get(key).and_then(JsonValue::as_str).unwrap_or_default();
+        let headers = match request.get("headers") {
+            Some(JsonValue::Array(headers)) => headers.iter().map(key_value).collect(),
+            _ => parse_headers(text("headers")),
+        };
+        converted.insert(
+            "originalRequest".to_string(),
+            json!({
+                "method": if text("method").is_empty() { "GET" } else { text("method") },
+                "url": { "raw": text("url") },
+                "header": headers,
+            }),
+        );
+    }
+
+    JsonValue::Object(converted)
+}
+
+/// v1 requests have a v2 style `auth`, or the older `currentHelper` and
+/// `helperAttributes`.
+fn auth(request: &JsonValue) -> Option<JsonValue> {
+    if let Some(auth) = request.get("auth").filter(|auth| !auth.is_null()) {
+        return Some(auth.clone());
+    }
+
+    let attributes = request.get("helperAttributes").cloned().unwrap_or(json!({}));
+    let auth_type = match request.get("currentHelper").and_then(JsonValue::as_str)? {
+        "basicAuth" => "basic",
+        "bearerAuth" => "bearer",
+        "digestAuth" => "digest",
+        "oAuth2" => "oauth2",
+        _ => return None,
+    };
+    Some(json!({ "type": auth_type, auth_type: attributes }))
+}
+
+/// A v1 header, form field or variable as a v2.1 key/value pair.
+fn key_value(field: &JsonValue) -> JsonValue {
+    let mut converted = field.clone();
+    if let Some(object) = converted.as_object_mut() {
+        if let Some(enabled) = object.remove("enabled").and_then(|enabled| enabled.as_bool()) {
+            if !enabled {
+                object.insert("disabled".to_string(), json!(true));
+            }
+        }
+        object.remove("equals");
+        if object.get("description").is_some_and(JsonValue::is_null) {
+            object.remove("description");
+        }
+    }
+    converted
+}
+
+fn variable(variable: &JsonValue) -> JsonValue {
+    let mut converted = key_value(variable);
+    normalize_variable(&mut converted);
+    converted
+}
+
+/// Parses headers given as `Name: value` lines. Lines starting with `//` are
+/// disabled headers.
+fn parse_headers(headers: &str) -> Vec<JsonValue> {
+    headers
+        .lines()
+        .filter_map(|line| {
+            let (line, disabled) = match line.trim().strip_prefix("//") {
+                Some(line) => (line.trim(), true),
+                None => (line.trim(), false),
+            };
+            let (key, value) = line.split_once(':')?;
+            let mut header = json!({ "key": key.trim(), "value": value.trim() });
+            if disabled {
+                header["disabled"] = json!(true);
+            }
+            Some(header)
+        })
+        .collect()
+}
+
+/// Descriptions are strings, or `{"content": ..., "type": ...}` objects.
+fn description(value: &JsonValue) -> Option<String> {
+    match value.get("description")? {
+        JsonValue::String(description) => Some(description.clone()),
+        JsonValue::Object(description) => description.get("content")?.as_str().map(str::to_string),
+        _ => None,
+    }
+}
+
+fn find<'a>(values: &'a [JsonValue], id: &str) -> Option<&'a JsonValue> {
+    values
+        .iter()
+        .find(|value| value.get("id").and_then(JsonValue::as_str) == Some(id))
+}
+
+fn ids<'a>(value: &'a JsonValue, key: &str) -> Vec<&'a str> {
+    value
+        .get(key)
+        .and_then(JsonValue::as_array)
+        .map(|ids| ids.iter().filter_map(JsonValue::as_str).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::PostmanCollection;
+
+    fn v1_collection() -> JsonValue {
+        json!({
+            "id": "c1",
+            "name": "Old API",
+            "order": ["r3"],
+            "folders_order": ["f1"],
+            "folders": [
+                { "id": "f1", "name": "Users", "order": ["r1"], "folders_order": ["f2"] },
+                { "id": "f2", "name": "Admin", "order": ["r2"], "auth": { "type": "bearer", "bearer": [{ "key": "token", "value": "adm" }] } },
+            ],
+            "variables": [{ "key": "host", "value": "localhost" }],
+            "requests": [
+                {
+                    "id": "r1", "name": "Create user", "url": "http://{{host}}/users/:id", "method": "POST",
+                    "headers": "Content-Type: application/json\n//X-Off: 1\n",
+                    "dataMode": "raw", "rawModeData": "{\"a\":1}",
+                    "pathVariableData": [{ "key": "id", "value": "7" }],
+                    "tests": "pm.test('ok', () => {});", "folder": "f1",
+                },
+                {
+                    "id": "r2", "name": "Form", "url": "http://{{host}}/form", "method": "POST",
+                    "headerData": [{ "key": "X-A", "value": "1", "enabled": false }],
+                    "dataMode": "params", "data": [{ "key": "f", "value": "v", "type": "text", "enabled": true }],
+                    "folder": "f2",
+                },
+                {
+                    "id": "r3", "name": "Basic", "url": "http://{{host}}/b", "method": "GET",
+                    "currentHelper": "basicAuth", "helperAttributes": { "username": "u", "password": "p" },
+                },
+            ],
+        })
+    }
+
+    #[test]
+    fn converts_v1_tree() {
+        let converted = convert_v1(&v1_collection()).unwrap();
+        assert_eq!(converted["info"]["name"], "Old API");
+        assert_eq!(converted["info"]["schema"], SCHEMA_V2_1);
+        assert_eq!(converted["variable"][0]["key"], "host");
+
+        let users = &converted["item"][0];
+        assert_eq!(users["name"], "Users");
+        let admin = &users["item"][0];
+        assert_eq!(admin["name"], "Admin");
+        assert_eq!(admin["auth"]["type"], "bearer");
+        assert_eq!(admin["item"][0]["name"], "Form");
+        assert_eq!(users["item"][1]["name"], "Create user");
+        assert_eq!(converted["item"][1]["name"], "Basic");
+
+        // The result is a collection the model reads
+        let collection: PostmanCollection = serde_json::from_value(converted).unwrap();
+        assert_eq!(collection.item.len(), 2);
+    }
+
+    #[test]
+    fn converts_v1_requests() {
+        let converted = convert_v1(&v1_collection()).unwrap();
+        let create = &converted["item"][0]["item"][1];
+        let request = &create["request"];
+        assert_eq!(request["header"], json!([
+            { "key": "Content-Type", "value": "application/json" },
+            { "key": "X-Off", "value": "1", "disabled": true },
+        ]));
+        assert_eq!(request["body"], json!({ "mode": "raw", "raw": "{\"a\":1}" }));
+        assert_eq!(request["url"]["variable"][0]["key"], "id");
+        assert_eq!(create["event"][0]["listen"], "test");
+
+        let form = &converted["item"][0]["item"][0]["item"][0]["request"];
+        assert_eq!(form["header"][0]["disabled"], true);
+        assert_eq!(form["body"]["mode"], "formdata");
+        assert_eq!(form["body"]["formdata"][0], json!({ "key": "f", "value": "v", "type": "text" }));
+
+        let basic = &converted["item"][1]["request"];
+        assert_eq!(basic["auth"], json!({ "type": "basic", "basic": { "username": "u", "password": "p" } }));
+    }
+
+    #[test]
+    fn folders_without_order_collect_their_requests() {
+        let collection = json!({
+            "name": "Unordered",
+            "folders": [{ "id": "f1", "name": "Folder" }],
+            "requests": [
+                { "id": "r1", "name": "In folder", "url": "http://x/1", "folder": "f1" },
+                { "id": "r2", "name": "Top", "url": "http://x/2", "folder": null },
+            ],
+        });
+        let converted = convert_v1(&collection).unwrap();
+        assert_eq!(converted["item"][0]["item"][0]["name"], "In folder");
+        assert_eq!(converted["item"][1]["name"], "Top");
+        assert_eq!(converted["item"][1]["request"]["method"], "GET");
+
+        assert!(convert_v1(&json!({ "name": "No requests" })).is_err());
+    }
+
+    #[test]
+    fn converts_v1_responses_and_path_variables() {
+        let collection = json!({
+            "name": "Examples",
+            "requests": [{
+                "id": "r1", "name": "Get user", "url": "http://x/users/:id", "method": "GET",
+                "pathVariables": { "id": "7" },
+                "responses": [{
+                    "id": "e1", "name": "Found",
+                    "responseCode": { "code": 200, "name": "OK", "detail": "" },
+                    "headers": [{ "name": "Content-Type", "key": "Content-Type", "value": "application/json" }, { "name": "X-Id", "value": "7" }],
+                    "cookies": [],
+                    "text": "{\"id\":7}", "language": "json", "time": 12,
+                    "request": { "url": "http://x/users/7", "method": "GET", "headers": "Accept: */*" },
+                }, {
+                    "name": "Missing", "responseCode": { "code": 404, "name": "Not Found" },
+                    "headers": "Content-Type: text/plain", "text": "none", "request": "r1",
+                }],
+            }],
+        });
+        let converted = convert_v1(&collection).unwrap();
+        let item = &converted["item"][0];
+        assert_eq!(item["request"]["url"]["variable"], json!([{ "key": "id", "value": "7" }]));
+
+        let found = &item["response"][0];
+        assert_eq!(found["code"], 200);
+        assert_eq!(found["status"], "OK");
+        assert_eq!(found["body"], "{\"id\":7}");
+        assert_eq!(found["_postman_previewlanguage"], "json");
+        assert_eq!(found["responseTime"], 12);
+        assert_eq!(found["header"], json!([
+            { "key": "Content-Type", "value": "application/json" },
+            { "key": "X-Id", "value": "7" },
+        ]));
+        assert_eq!(found["originalRequest"]["url"]["raw"], "http://x/users/7");
+        assert_eq!(found["originalRequest"]["header"], json!([{ "key": "Accept", "value": "*/*" }]));
+
+        let missing = &item["response"][1];
+        assert_eq!(missing["code"], 404);
+        assert_eq!(missing["header"], json!([{ "key": "Content-Type", "value": "text/plain" }]));
+        assert!(missing.get("originalRequest").is_none());
+
+        let collection: PostmanCollection = serde_json::from_value(converted).unwrap();
+        assert_eq!(collection.item.len(), 1);
+    }
+
+    #[test]
+    fn normalizes_v2_0() {
+        let mut collection = json!({
+            "variable": [{ "id": "host", "value": "localhost" }],
+            "item": [{ "name": "r", "request": { "url": { "raw": "http://x/:id", "variable": [{ "id": "id" }] }, "header": "A: 1\nB: 2" } }],
+        });
+        normalize_v2_0(&mut collection);
+        assert_eq!(collection["variable"][0]["key"], "host");
+        assert_eq!(collection["item"][0]["request"]["url"]["variable"][0]["key"], "id");
+        assert_eq!(collection["item"][0]["request"]["header"][1], json!({ "key": "B", "value": "2" }));
+    }
+}
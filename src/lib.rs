@@ -3,10 +3,28 @@
 //! A Rust library for generating OpenAPI specifications from Postman collections
 //! by running the collections against live APIs and capturing the responses.
 
+pub mod auth;
+pub mod bruno;
 pub mod collection;
+pub mod components;
+pub mod curl;
+pub mod dynamic;
+pub mod extract;
+pub mod http_file;
+pub mod hurl;
+pub mod insomnia;
+pub mod iteration_data;
+pub mod legacy;
 pub mod runner;
 pub mod openapi;
+pub mod schema;
+pub mod scripts;
+pub mod security;
+pub mod string_format;
+pub mod variables;
+pub mod xml;
 
 pub use collection::{PostmanCollection, Environment};
 pub use runner::{CollectionRunner, Har, HarEntry};
-pub use openapi::{OpenApiGenerator, OpenApiSpec};
\ No newline at end of file
+pub use openapi::{OpenApiGenerator, OpenApiSpec};
+pub use schema::Schema;
\ No newline at end of file
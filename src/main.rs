@@ -1,14 +1,12 @@
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand};
+use serde_json::Value as JsonValue;
+use std::path::{Path, PathBuf};
 
-mod collection;
-mod runner;
-mod openapi;
-
-use collection::PostmanCollection;
-use runner::CollectionRunner;
-use openapi::OpenApiGenerator;
+use harbinger::collection::{Environment, ImportedCollection, Item, PostmanCollection};
+use harbinger::{bruno, curl, extract, http_file, hurl, insomnia, iteration_data};
+use harbinger::runner::{CollectionRunner, Har, UnresolvedMode};
+use harbinger::openapi::OpenApiGenerator;
 
 #[derive(Parser)]
 #[command(name = "api-specs")]
@@ -22,13 +20,8 @@ struct Cli {
 enum Commands {
     /// Run a Postman collection and capture responses
     Run {
-        /// Path to the Postman collection JSON file
-        #[arg(short, long)]
-        collection: PathBuf,
-
-        /// Path to the Postman environment JSON file (optional)
-        #[arg(short, long)]
-        environment: Option<PathBuf>,
+        #[command(flatten)]
+        run: RunOptions,
 
         /// Output HAR file path
         #[arg(short, long, default_value = "api-run.har")]
@@ -36,48 +29,249 @@ enum Commands {
     },
     /// Generate OpenAPI spec from collection run
     Generate {
-        /// Path to the Postman collection JSON file
+        #[command(flatten)]
+        run: RunOptions,
+
+        /// Output OpenAPI spec file path
+        #[arg(short, long, default_value = "openapi-spec.yaml")]
+        output: PathBuf,
+
+        /// Add a `default` response built from the observed error responses
+        #[arg(long)]
+        default_response: bool,
+
+        /// Record observed numeric bounds as `x-observed-minimum`/`x-observed-maximum`
+        #[arg(long)]
+        observed_bounds: bool,
+    },
+    /// Convert a collection to a Postman collection, a `.http` file or a Hurl file
+    Convert {
+        /// Collection to convert, in any format `run` accepts
         #[arg(short, long)]
         collection: PathBuf,
 
-        /// Path to the Postman environment JSON file (optional)
+        /// Output file: `.http` and `.rest` files are written as HTTP client
+        /// requests, `.hurl` files as Hurl entries, anything else as a Postman
+        /// v2.1 collection
         #[arg(short, long)]
-        environment: Option<PathBuf>,
-
-        /// Output OpenAPI spec file path
-        #[arg(short, long, default_value = "openapi-spec.yaml")]
         output: PathBuf,
     },
 }
 
+/// Options for running a collection, shared by all commands
+#[derive(Args)]
+struct RunOptions {
+    /// Path to the Postman collection JSON file, an Insomnia v4 export, a
+    /// Bruno collection directory, a `.http` file or a Hurl file
+    #[arg(short, long, required_unless_present = "curl_file")]
+    collection: Option<PathBuf>,
+
+    /// File of `curl` commands (such as browsers' "Copy as cURL") to run
+    /// after the collection's requests, or on their own
+    #[arg(long)]
+    curl_file: Option<PathBuf>,
+
+    /// Path to the Postman environment JSON file (optional)
+    #[arg(short, long)]
+    environment: Option<PathBuf>,
+
+    /// Environment to use from the collection, for formats that bundle their
+    /// environments (Insomnia exports, Bruno collections, `.http` files with
+    /// `http-client.env.json`)
+    #[arg(long)]
+    environment_name: Option<String>,
+
+    /// Path to the Postman globals JSON file (optional)
+    #[arg(short, long)]
+    globals: Option<PathBuf>,
+
+    /// CSV or JSON file with one row of data variables per iteration
+    #[arg(short = 'd', long)]
+    iteration_data: Option<PathBuf>,
+
+    /// Number of iterations (default: one per data row, or one)
+    #[arg(short = 'n', long)]
+    iteration_count: Option<usize>,
+
+    /// Seed for dynamic variables such as `{{$guid}}`, making their values reproducible
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// What to do with requests that use variables no scope defines
+    #[arg(long, value_enum, default_value_t = UnresolvedMode::Warn)]
+    unresolved: UnresolvedMode,
+
+    /// JSON or YAML file mapping request names to variables to extract from their responses
+    #[arg(long)]
+    extract: Option<PathBuf>,
+
+    /// Write the environment, with the values set during the run, to this file
+    #[arg(long)]
+    export_environment: Option<PathBuf>,
+
+    /// Write the globals, with the values set during the run, to this file
+    #[arg(long)]
+    export_globals: Option<PathBuf>,
+
+    /// Write the collection, with the variable values set during the run, to this file
+    #[arg(long)]
+    export_collection: Option<PathBuf>,
+
+    /// Write the requests sent to this Hurl file, expecting the status codes they got
+    #[arg(long)]
+    export_hurl: Option<PathBuf>,
+}
+
+impl RunOptions {
+    fn load_runner(&self) -> Result<CollectionRunner> {
+        let (collection, bundled_environment) = self.load_collection()?;
+
+        let environment = if let Some(env_path) = &self.environment {
+            let env_data = std::fs::read_to_string(env_path)
+                .context("Failed to read environment file")?;
+            Some(serde_json::from_str(&env_data)
+                .context("Failed to parse environment JSON")?)
+        } else {
+            bundled_environment
+        };
+
+        let globals: Environment = if let Some(globals_path) = &self.globals {
+            let globals_data = std::fs::read_to_string(globals_path)
+                .context("Failed to read globals file")?;
+            serde_json::from_str(&globals_data)
+                .context("Failed to parse globals JSON")?
+        } else {
+            Environment::default()
+        };
+
+        let extract_rules = match &self.extract {
+            Some(path) => extract::load(path)?,
+            None => Default::default(),
+        };
+
+        let iteration_data = match &self.iteration_data {
+            Some(path) => iteration_data::load(path)?,
+            None => Vec::new(),
+        };
+
+        // Files to upload are resolved relative to the collection
+        let source = self.source();
+        let working_dir = if source.is_dir() {
+            source.to_path_buf()
+        } else {
+            source.parent().map(PathBuf::from).unwrap_or_default()
+        };
+        Ok(CollectionRunner::new(collection, environment)
+            .with_globals(globals)
+            .with_working_dir(working_dir)
+            .with_extract_rules(extract_rules)
+            .with_iteration_data(iteration_data)
+            .with_iteration_count(self.iteration_count)
+            .with_seed(self.seed)
+            .with_unresolved_mode(self.unresolved))
+    }
+
+    /// The collection, or the cURL file when there is none.
+    fn source(&self) -> &Path {
+        self.collection
+            .as_deref()
+            .or(self.curl_file.as_deref())
+            .expect("clap requires a collection or a cURL file")
+    }
+
+    /// Reads the collection file, converting other tools' formats, with the
+    /// environment selected from the file if it bundles its environments.
+    /// Requests from the cURL file are added at the end.
+    fn load_collection(&self) -> Result<(PostmanCollection, Option<Environment>)> {
+        let imported = match &self.collection {
+            Some(path) => load_collection(path)?,
+            None => ImportedCollection {
+                collection: PostmanCollection::named(&self.source().file_stem().unwrap_or_default().to_string_lossy()),
+                environments: Vec::new(),
+                warnings: Vec::new(),
+            },
+        };
+        let environment = match &self.environment_name {
+            Some(name) => Some(imported.environment(name)?),
+            None => None,
+        };
+
+        let mut collection = imported.collection;
+        if let Some(path) = &self.curl_file {
+            let commands = std::fs::read_to_string(path)
+                .context("Failed to read cURL file")?;
+            let dir = path.parent().unwrap_or(Path::new(""));
+            collection.item.extend(curl::parse(&commands, dir)?.into_iter().map(Item::Request));
+        }
+        Ok((collection, environment))
+    }
+
+    /// Writes the variable state and the requests sent to the requested
+    /// files.
+    fn export(&self, runner: &CollectionRunner, har: &Har) -> Result<()> {
+        if let Some(path) = &self.export_environment {
+            std::fs::write(path, serde_json::to_string_pretty(&runner.export_environment())?)
+                .context("Failed to write environment file")?;
+            println!("✓ Environment saved to: {}", path.display());
+        }
+        if let Some(path) = &self.export_globals {
+            std::fs::write(path, serde_json::to_string_pretty(&runner.export_globals())?)
+                .context("Failed to write globals file")?;
+            println!("✓ Globals saved to: {}", path.display());
+        }
+        if let Some(path) = &self.export_collection {
+            std::fs::write(path, serde_json::to_string_pretty(&runner.export_collection())?)
+                .context("Failed to write collection file")?;
+            println!("✓ Collection saved to: {}", path.display());
+        }
+        if let Some(path) = &self.export_hurl {
+            std::fs::write(path, hurl::export_har(har))
+                .context("Failed to write Hurl file")?;
+            println!("✓ Hurl file saved to: {}", path.display());
+        }
+        Ok(())
+    }
+}
+
+/// Reads a collection in any supported format, printing the warnings from
+/// converting other tools' formats.
+fn load_collection(path: &Path) -> Result<ImportedCollection> {
+    let imported = if bruno::is_collection(path) {
+        bruno::load(path)?
+    } else if http_file::is_http_file(path) {
+        http_file::load(path)?
+    } else if hurl::is_hurl_file(path) {
+        hurl::load(path)?
+    } else {
+        let collection_data = std::fs::read_to_string(path)
+            .context("Failed to read collection file")?;
+        match serde_json::from_str::<JsonValue>(&collection_data) {
+            Ok(export) if insomnia::is_export(&export) => insomnia::convert(&export)?,
+            _ => ImportedCollection {
+                collection: PostmanCollection::from_json(&collection_data)?,
+                environments: Vec::new(),
+                warnings: Vec::new(),
+            },
+        }
+    };
+
+    for warning in &imported.warnings {
+        eprintln!("  ! {}", warning);
+    }
+    Ok(imported)
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Run {
-            collection,
-            environment,
-            output,
-        } => {
-            println!("Running collection: {}", collection.display());
-            
-            let collection_data = std::fs::read_to_string(&collection)
-                .context("Failed to read collection file")?;
-            let collection: PostmanCollection = serde_json::from_str(&collection_data)
-                .context("Failed to parse collection JSON")?;
-
-            let environment = if let Some(env_path) = environment {
-                let env_data = std::fs::read_to_string(&env_path)
-                    .context("Failed to read environment file")?;
-                Some(serde_json::from_str(&env_data)
-                    .context("Failed to parse environment JSON")?)
-            } else {
-                None
-            };
+        Commands::Run { run, output } => {
+            println!("Running collection: {}", run.source().display());
 
-            let runner = CollectionRunner::new(collection, environment);
+            let mut runner = run.load_runner()?;
             let har = runner.run().await?;
+            run.export(&runner, &har)?;
 
             let har_json = serde_json::to_string_pretty(&har)?;
             std::fs::write(&output, har_json)
@@ -86,30 +280,20 @@ async fn main() -> Result<()> {
             println!("✓ HAR file saved to: {}", output.display());
         }
         Commands::Generate {
-            collection,
-            environment,
+            run,
             output,
+            default_response,
+            observed_bounds,
         } => {
-            println!("Generating OpenAPI spec from: {}", collection.display());
-            
-            let collection_data = std::fs::read_to_string(&collection)
-                .context("Failed to read collection file")?;
-            let collection: PostmanCollection = serde_json::from_str(&collection_data)
-                .context("Failed to parse collection JSON")?;
-
-            let environment = if let Some(env_path) = environment {
-                let env_data = std::fs::read_to_string(&env_path)
-                    .context("Failed to read environment file")?;
-                Some(serde_json::from_str(&env_data)
-                    .context("Failed to parse environment JSON")?)
-            } else {
-                None
-            };
+            println!("Generating OpenAPI spec from: {}", run.source().display());
 
-            let runner = CollectionRunner::new(collection, environment);
+            let mut runner = run.load_runner()?;
             let har = runner.run().await?;
+            run.export(&runner, &har)?;
 
-            let generator = OpenApiGenerator::new();
+            let generator = OpenApiGenerator::new()
+                .with_default_response(default_response)
+                .with_observed_bounds(observed_bounds);
             let openapi_spec = generator.from_har(&har)?;
 
             let yaml = serde_yaml::to_string(&openapi_spec)?;
@@ -118,6 +302,20 @@ async fn main() -> Result<()> {
 
             println!("✓ OpenAPI spec saved to: {}", output.display());
         }
+        Commands::Convert { collection, output } => {
+            let imported = load_collection(&collection)?;
+            let converted = if http_file::is_http_file(&output) {
+                http_file::export(&imported.collection)
+            } else if hurl::is_hurl_file(&output) {
+                hurl::export(&imported.collection)
+            } else {
+                serde_json::to_string_pretty(&imported.collection)?
+            };
+            std::fs::write(&output, converted)
+                .context("Failed to write converted collection")?;
+
+            println!("✓ Collection saved to: {}", output.display());
+        }
     }
 
     Ok(())
@@ -4,7 +4,14 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use std::collections::HashSet;
 
-use crate::runner::{Har, HarEntry};
+use crate::components;
+use crate::runner::{Har, HarEntry, HarParam};
+use crate::security;
+use crate::string_format;
+use crate::xml;
+
+pub use crate::schema::Schema;
+use crate::schema::{OBSERVED_MAXIMUM, OBSERVED_MINIMUM};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OpenApiSpec {
@@ -45,6 +52,21 @@ pub struct PathItem {
     pub patch: Option<Operation>,
 }
 
+impl PathItem {
+    /// The operations defined on this path.
+    pub fn operations_mut(&mut self) -> impl Iterator<Item = &mut Operation> {
+        [
+            self.get.as_mut(),
+            self.post.as_mut(),
+            self.put.as_mut(),
+            self.delete.as_mut(),
+            self.patch.as_mut(),
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Operation {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -59,17 +81,26 @@ pub struct Operation {
     #[serde(rename = "requestBody")]
     pub request_body: Option<RequestBody>,
     pub responses: IndexMap<String, Response>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub security: Option<Vec<SecurityRequirement>>,
 }
 
+/// Maps security scheme names to the scopes an operation needs.
+pub type SecurityRequirement = IndexMap<String, Vec<String>>;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Parameter {
     pub name: String,
     #[serde(rename = "in")]
     pub location: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub required: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub schema: Option<Schema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub example: Option<JsonValue>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -81,14 +112,14 @@ pub struct RequestBody {
     pub required: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Response {
     pub description: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<IndexMap<String, MediaType>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MediaType {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub schema: Option<Schema>,
@@ -96,45 +127,94 @@ pub struct MediaType {
     pub example: Option<JsonValue>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum Schema {
-    Simple {
-        #[serde(rename = "type")]
-        schema_type: String,
-    },
-    Object {
-        #[serde(rename = "type")]
-        schema_type: String,
-        #[serde(skip_serializing_if = "Option::is_none")]
-        properties: Option<IndexMap<String, Box<Schema>>>,
-        #[serde(skip_serializing_if = "Option::is_none")]
-        required: Option<Vec<String>>,
-    },
-    Array {
-        #[serde(rename = "type")]
-        schema_type: String,
-        #[serde(skip_serializing_if = "Option::is_none")]
-        items: Option<Box<Schema>>,
-    },
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OpenApiComponents {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub schemas: Option<IndexMap<String, Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "securitySchemes")]
+    pub security_schemes: Option<IndexMap<String, SecurityScheme>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SecurityScheme {
+    /// `apiKey`, `http`, `oauth2` or `openIdConnect`
+    #[serde(rename = "type")]
+    pub scheme_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "in")]
+    pub location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "bearerFormat")]
+    pub bearer_format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flows: Option<OAuthFlows>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OAuthFlows {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub implicit: Option<OAuthFlow>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<OAuthFlow>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "clientCredentials")]
+    pub client_credentials: Option<OAuthFlow>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "authorizationCode")]
+    pub authorization_code: Option<OAuthFlow>,
 }
 
-pub struct OpenApiGenerator;
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OAuthFlow {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "authorizationUrl")]
+    pub authorization_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "tokenUrl")]
+    pub token_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "refreshUrl")]
+    pub refresh_url: Option<String>,
+    pub scopes: IndexMap<String, String>,
+}
+
+#[derive(Default)]
+pub struct OpenApiGenerator {
+    default_response: bool,
+    observed_bounds: bool,
+}
 
 impl OpenApiGenerator {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Adds a `default` response to every operation, merging the error
+    /// responses (4xx/5xx) that were observed for it.
+    pub fn with_default_response(mut self, enabled: bool) -> Self {
+        self.default_response = enabled;
+        self
+    }
+
+    /// Records the smallest and largest value seen for every numeric schema
+    /// as `x-observed-minimum` / `x-observed-maximum` extensions.
+    pub fn with_observed_bounds(mut self, enabled: bool) -> Self {
+        self.observed_bounds = enabled;
+        self
     }
 
     pub fn from_har(&self, har: &Har) -> Result<OpenApiSpec> {
         let mut paths: IndexMap<String, PathItem> = IndexMap::new();
         let mut servers: HashSet<String> = HashSet::new();
+        let mut security_schemes: IndexMap<String, SecurityScheme> = IndexMap::new();
+        let mut samples: IndexMap<(String, String), Vec<&HarEntry>> = IndexMap::new();
 
         for entry in &har.log.entries {
             // Extract server URL
@@ -147,32 +227,49 @@ impl OpenApiGenerator {
                 servers.insert(base_url);
 
                 // Extract path
-                let path = self.normalize_path(parsed_url.path());
-
-                // Get or create path item
-                let path_item = paths.entry(path.clone()).or_insert_with(|| PathItem {
-                    get: None,
-                    post: None,
-                    put: None,
-                    delete: None,
-                    patch: None,
-                });
-
-                // Create operation from entry
-                let operation = self.create_operation(entry)?;
-
-                // Assign to appropriate method
-                match entry.request.method.to_lowercase().as_str() {
-                    "get" => path_item.get = Some(operation),
-                    "post" => path_item.post = Some(operation),
-                    "put" => path_item.put = Some(operation),
-                    "delete" => path_item.delete = Some(operation),
-                    "patch" => path_item.patch = Some(operation),
-                    _ => {}
-                }
+                let (path, _) = self.path_template(entry, parsed_url.path());
+
+                // Group every sample hitting the same path and method
+                samples
+                    .entry((path, entry.request.method.to_lowercase()))
+                    .or_default()
+                    .push(entry);
+            }
+        }
+
+        for ((path, method), entries) in samples {
+            // Get or create path item
+            let path_item = paths.entry(path).or_insert_with(|| PathItem {
+                get: None,
+                post: None,
+                put: None,
+                delete: None,
+                patch: None,
+            });
+
+            // Create operation from all samples
+            let operation = self.create_operation(&entries, &mut security_schemes)?;
+
+            // Assign to appropriate method
+            match method.as_str() {
+                "get" => path_item.get = Some(operation),
+                "post" => path_item.post = Some(operation),
+                "put" => path_item.put = Some(operation),
+                "delete" => path_item.delete = Some(operation),
+                "patch" => path_item.patch = Some(operation),
+                _ => {}
+            }
+        }
+
+        // Once any scheme is known, operations without one are explicitly open
+        if !security_schemes.is_empty() {
+            for operation in paths.values_mut().flat_map(PathItem::operations_mut) {
+                operation.security.get_or_insert_with(Vec::new);
             }
         }
 
+        let schemas = components::extract(&mut paths);
+
         Ok(OpenApiSpec {
             openapi: "3.0.0".to_string(),
             info: OpenApiInfo {
@@ -188,99 +285,479 @@ impl OpenApiGenerator {
                 })
                 .collect(),
             paths,
-            components: None,
+            components: if schemas.is_empty() && security_schemes.is_empty() {
+                None
+            } else {
+                Some(OpenApiComponents {
+                    schemas: if schemas.is_empty() { None } else { Some(schemas) },
+                    security_schemes: if security_schemes.is_empty() {
+                        None
+                    } else {
+                        Some(security_schemes)
+                    },
+                })
+            },
         })
     }
 
-    fn normalize_path(&self, path: &str) -> String {
-        // Convert paths like /users/123 to /users/{id}
-        let parts: Vec<&str> = path.split('/').collect();
-        let normalized_parts: Vec<String> = parts
-            .iter()
-            .map(|part| {
-                if part.parse::<i64>().is_ok() {
-                    "{id}".to_string()
-                } else if part.len() > 20 {
-                    // Likely a UUID or hash
-                    "{id}".to_string()
-                } else {
-                    part.to_string()
+    /// Turns a request path into a template such as `/orgs/{orgId}/users/{userId}`
+    /// and returns the value each parameter had in this request. The template
+    /// recorded by the runner from Postman path variables wins; otherwise
+    /// segments that look like identifiers become parameters named after the
+    /// preceding resource.
+    fn path_template(&self, entry: &HarEntry, path: &str) -> (String, Vec<(String, String)>) {
+        let actual: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+
+        if let Some(template) = &entry.request.path_template {
+            let segments: Vec<&str> = template.split('/').filter(|segment| !segment.is_empty()).collect();
+            if segments.len() == actual.len() {
+                let values = segments
+                    .iter()
+                    .zip(&actual)
+                    .filter_map(|(segment, value)| {
+                        let name = segment.strip_prefix('{')?.strip_suffix('}')?;
+                        Some((name.to_string(), value.to_string()))
+                    })
+                    .collect();
+                return (template.clone(), values);
+            }
+        }
+
+        let mut segments: Vec<String> = Vec::new();
+        let mut values: Vec<(String, String)> = Vec::new();
+        let mut previous: Option<&str> = None;
+
+        for segment in actual {
+            if !self.is_identifier(segment) {
+                segments.push(segment.to_string());
+                previous = Some(segment);
+                continue;
+            }
+
+            let base = match previous.map(components::type_name) {
+                Some(resource) if !resource.is_empty() => {
+                    let mut chars = resource.chars();
+                    let first = chars.next().map(|c| c.to_lowercase().to_string()).unwrap_or_default();
+                    format!("{}{}Id", first, chars.as_str())
                 }
-            })
-            .collect();
+                _ => "id".to_string(),
+            };
+            let mut name = base.clone();
+            let mut suffix = 2;
+            while values.iter().any(|(existing, _)| *existing == name) {
+                name = format!("{}{}", base, suffix);
+                suffix += 1;
+            }
+
+            segments.push(format!("{{{}}}", name));
+            values.push((name, segment.to_string()));
+            previous = None;
+        }
 
-        normalized_parts.join("/")
+        (format!("/{}", segments.join("/")), values)
     }
 
-    fn create_operation(&self, entry: &HarEntry) -> Result<Operation> {
-        let mut responses: IndexMap<String, Response> = IndexMap::new();
+    /// Numeric IDs, UUIDs, ULIDs and hex hashes or object IDs.
+    fn is_identifier(&self, segment: &str) -> bool {
+        let is_hex_hash = segment.len() >= 16
+            && segment.chars().all(|c| c.is_ascii_hexdigit())
+            && segment.chars().any(|c| c.is_ascii_digit());
 
-        // Create response from the HAR entry
-        let status_code = entry.response.status.to_string();
-        let content_type = &entry.response.content.mime_type;
+        segment.chars().all(|c| c.is_ascii_digit())
+            || is_hex_hash
+            || matches!(string_format::detect(segment), Some("uuid" | "ulid" | "objectid"))
+    }
 
-        let mut response_content: IndexMap<String, MediaType> = IndexMap::new();
+    fn create_operation(
+        &self,
+        entries: &[&HarEntry],
+        security_schemes: &mut IndexMap<String, SecurityScheme>,
+    ) -> Result<Operation> {
+        let mut responses: IndexMap<String, Response> = IndexMap::new();
 
-        if !entry.response.content.text.is_empty() {
-            let schema = self.infer_schema(&entry.response.content.text, content_type)?;
-            let example = if content_type.contains("json") {
-                serde_json::from_str(&entry.response.content.text).ok()
-            } else {
-                None
-            };
+        // Merge the responses of every sample, keyed by status code
+        for entry in entries {
+            let status_code = entry.response.status.to_string();
+            let response = responses.entry(status_code).or_insert_with(|| Response {
+                description: self.status_description(entry.response.status, &entry.response.status_text),
+                content: None,
+            });
 
-            response_content.insert(
-                content_type.clone(),
-                MediaType { schema, example },
-            );
+            if !entry.response.content.text.is_empty() {
+                let content = response.content.get_or_insert_with(IndexMap::new);
+                self.add_sample(
+                    content,
+                    &entry.response.content.mime_type,
+                    &entry.response.content.text,
+                )?;
+            }
         }
 
-        responses.insert(
-            status_code,
-            Response {
-                description: entry.response.status_text.clone(),
-                content: if response_content.is_empty() {
-                    None
-                } else {
-                    Some(response_content)
-                },
-            },
-        );
+        responses.sort_keys();
 
-        // Create request body if present
-        let request_body = if let Some(post_data) = &entry.request.post_data {
-            let schema = self.infer_schema(&post_data.text, &post_data.mime_type)?;
-            let example = if post_data.mime_type.contains("json") {
-                serde_json::from_str(&post_data.text).ok()
-            } else {
-                None
-            };
+        if self.default_response {
+            let default = self.default_response(&responses);
+            responses.insert("default".to_string(), default);
+        }
 
-            let mut content: IndexMap<String, MediaType> = IndexMap::new();
-            content.insert(
-                post_data.mime_type.clone(),
-                MediaType { schema, example },
-            );
+        // Merge request bodies; the body is only required if every sample sent one
+        let mut request_content: IndexMap<String, MediaType> = IndexMap::new();
+        let mut body_samples = 0;
+        for entry in entries {
+            if let Some(post_data) = &entry.request.post_data {
+                match &post_data.params {
+                    Some(params) => self.add_form_sample(&mut request_content, &post_data.mime_type, params),
+                    None => self.add_sample(&mut request_content, &post_data.mime_type, &post_data.text)?,
+                }
+                body_samples += 1;
+            }
+        }
 
+        let request_body = if request_content.is_empty() {
+            None
+        } else {
             Some(RequestBody {
                 description: None,
-                content,
-                required: Some(true),
+                content: request_content,
+                required: Some(body_samples == entries.len()),
             })
-        } else {
-            None
         };
 
+        let mut parameters = self.path_parameters(entries);
+        parameters.extend(self.query_parameters(entries));
+
         Ok(Operation {
-            summary: None,
+            // The Postman request name recorded by the runner
+            summary: entries.iter().find_map(|entry| entry.comment.clone()),
             description: None,
             tags: None,
-            parameters: None,
+            parameters: if parameters.is_empty() { None } else { Some(parameters) },
             request_body,
             responses,
+            security: self.security_requirements(entries, security_schemes),
         })
     }
 
+    /// Lists the security schemes the samples used, registering them as
+    /// components. If only some samples were authenticated, an empty
+    /// requirement marks authentication as optional.
+    fn security_requirements(
+        &self,
+        entries: &[&HarEntry],
+        security_schemes: &mut IndexMap<String, SecurityScheme>,
+    ) -> Option<Vec<SecurityRequirement>> {
+        let mut requirements: Vec<SecurityRequirement> = Vec::new();
+        let mut unauthenticated = false;
+
+        for entry in entries {
+            let Some(credential) = security::infer(entry) else {
+                unauthenticated = true;
+                continue;
+            };
+
+            let name = security::register(security_schemes, credential.name, credential.scheme);
+            match requirements.iter_mut().find_map(|requirement| requirement.get_mut(&name)) {
+                Some(scopes) => {
+                    for scope in credential.scopes {
+                        if !scopes.contains(&scope) {
+                            scopes.push(scope);
+                        }
+                    }
+                }
+                None => {
+                    requirements.push(IndexMap::from([(name, credential.scopes)]));
+                }
+            }
+        }
+
+        if requirements.is_empty() {
+            return None;
+        }
+        if unauthenticated {
+            requirements.push(IndexMap::new());
+        }
+        Some(requirements)
+    }
+
+    /// Documents the parameters of a path template, with schemas and examples
+    /// taken from the values each sample used.
+    fn path_parameters(&self, entries: &[&HarEntry]) -> Vec<Parameter> {
+        let mut observed: IndexMap<String, (Schema, JsonValue)> = IndexMap::new();
+
+        for entry in entries {
+            let Ok(parsed_url) = url::Url::parse(&entry.request.url) else {
+                continue;
+            };
+            let (_, values) = self.path_template(entry, parsed_url.path());
+            for (name, value) in values {
+                let schema = self.string_to_schema(&value);
+                match observed.get_mut(&name) {
+                    Some((existing, _)) => *existing = std::mem::take(existing).merge(schema),
+                    None => {
+                        observed.insert(name, (schema, self.string_to_example(&value)));
+                    }
+                }
+            }
+        }
+
+        observed
+            .into_iter()
+            .map(|(name, (schema, example))| Parameter {
+                name,
+                location: "path".to_string(),
+                description: None,
+                required: Some(true),
+                schema: Some(schema),
+                example: Some(example),
+            })
+            .collect()
+    }
+
+    /// Documents the query parameters seen across all samples. A parameter is
+    /// required if every sample sent it, and an array if a sample repeated it.
+    /// Credentials are left to the security schemes.
+    fn query_parameters(&self, entries: &[&HarEntry]) -> Vec<Parameter> {
+        let mut observed: IndexMap<String, (usize, Schema, JsonValue)> = IndexMap::new();
+
+        for entry in entries {
+            let mut values: IndexMap<&str, Vec<&str>> = IndexMap::new();
+            for param in &entry.request.query_string {
+                if security::is_query_credential(entry, &param.name) {
+                    continue;
+                }
+                values.entry(param.name.as_str()).or_default().push(param.value.as_str());
+            }
+
+            for (name, values) in values {
+                let schema = values
+                    .iter()
+                    .map(|value| self.string_to_schema(value))
+                    .reduce(Schema::merge)
+                    .unwrap_or_default();
+                let (schema, example) = if values.len() > 1 || name.ends_with("[]") {
+                    (
+                        Schema {
+                            items: Some(Box::new(schema)),
+                            ..Schema::of_type("array")
+                        },
+                        JsonValue::Array(values.iter().map(|value| self.string_to_example(value)).collect()),
+                    )
+                } else {
+                    (schema, self.string_to_example(values[0]))
+                };
+
+                match observed.get_mut(name) {
+                    Some((count, existing, _)) => {
+                        *count += 1;
+                        *existing = self.merge_parameter_schemas(std::mem::take(existing), schema);
+                    }
+                    None => {
+                        observed.insert(name.to_string(), (1, schema, example));
+                    }
+                }
+            }
+        }
+
+        observed
+            .into_iter()
+            .map(|(name, (count, schema, example))| Parameter {
+                name,
+                location: "query".to_string(),
+                description: None,
+                required: Some(count == entries.len()),
+                schema: Some(schema),
+                example: Some(example),
+            })
+            .collect()
+    }
+
+    /// Merges parameter schemas; a parameter that was repeated in any sample
+    /// is an array, and single values from other samples become its items.
+    fn merge_parameter_schemas(&self, left: Schema, right: Schema) -> Schema {
+        let is_array = |schema: &Schema| schema.schema_type.as_deref() == Some("array");
+        let as_array = |schema: Schema| {
+            if is_array(&schema) {
+                schema
+            } else {
+                Schema {
+                    items: Some(Box::new(schema)),
+                    ..Schema::of_type("array")
+                }
+            }
+        };
+
+        if is_array(&left) || is_array(&right) {
+            as_array(left).merge(as_array(right))
+        } else {
+            left.merge(right)
+        }
+    }
+
+    /// Infers the schema of a value that was sent as text, such as a query
+    /// or path parameter: numbers and booleans are recognised, anything
+    /// else is a string.
+    fn string_to_schema(&self, value: &str) -> Schema {
+        match serde_json::from_str::<JsonValue>(value) {
+            Ok(json @ (JsonValue::Number(_) | JsonValue::Bool(_))) => self.json_to_schema(&json),
+            _ => self.json_to_schema(&JsonValue::String(value.to_string())),
+        }
+    }
+
+    fn string_to_example(&self, value: &str) -> JsonValue {
+        match serde_json::from_str::<JsonValue>(value) {
+            Ok(json @ (JsonValue::Number(_) | JsonValue::Bool(_))) => json,
+            _ => JsonValue::String(value.to_string()),
+        }
+    }
+
+    fn status_description(&self, status: u16, status_text: &str) -> String {
+        if !status_text.is_empty() {
+            return status_text.to_string();
+        }
+
+        // HTTP/2 responses carry no reason phrase
+        reqwest::StatusCode::from_u16(status)
+            .ok()
+            .and_then(|code| code.canonical_reason())
+            .unwrap_or("Response")
+            .to_string()
+    }
+
+    /// Builds the `default` response from the merged error responses.
+    fn default_response(&self, responses: &IndexMap<String, Response>) -> Response {
+        let mut content: IndexMap<String, MediaType> = IndexMap::new();
+
+        for (status, response) in responses {
+            if !(status.starts_with('4') || status.starts_with('5')) {
+                continue;
+            }
+            for (content_type, media_type) in response.content.iter().flatten() {
+                self.merge_media_type(&mut content, content_type, media_type.clone());
+            }
+        }
+
+        Response {
+            description: "Unexpected error".to_string(),
+            content: if content.is_empty() { None } else { Some(content) },
+        }
+    }
+
+    /// Infers a schema for one body sample and merges it into the media type
+    /// already recorded for that content type.
+    fn add_sample(
+        &self,
+        content: &mut IndexMap<String, MediaType>,
+        content_type: &str,
+        text: &str,
+    ) -> Result<()> {
+        let schema = self.infer_schema(text, content_type)?;
+        let example = if content_type.contains("json") {
+            serde_json::from_str(text).ok()
+        } else if content_type.contains("xml") && !text.trim().is_empty() {
+            Some(JsonValue::String(text.to_string()))
+        } else {
+            None
+        };
+
+        self.merge_media_type(content, content_type, MediaType { schema, example });
+        Ok(())
+    }
+
+    /// Infers an object schema for a urlencoded or multipart body sample from
+    /// its fields: uploaded files are binary strings and repeated fields arrays.
+    fn add_form_sample(&self, content: &mut IndexMap<String, MediaType>, content_type: &str, params: &[HarParam]) {
+        let mut fields: IndexMap<&str, Vec<&HarParam>> = IndexMap::new();
+        for param in params {
+            fields.entry(param.name.as_str()).or_default().push(param);
+        }
+
+        let mut properties = IndexMap::new();
+        let mut example = serde_json::Map::new();
+        for (name, values) in &fields {
+            let schema = values
+                .iter()
+                .map(|param| match &param.file_name {
+                    Some(_) => Schema {
+                        format: Some("binary".to_string()),
+                        ..Schema::of_type("string")
+                    },
+                    None => self.string_to_schema(param.value.as_deref().unwrap_or_default()),
+                })
+                .reduce(Schema::merge)
+                .unwrap_or_default();
+            let examples: Vec<JsonValue> = values
+                .iter()
+                .filter_map(|param| param.value.as_deref())
+                .map(|value| self.string_to_example(value))
+                .collect();
+
+            if values.len() > 1 || name.ends_with("[]") {
+                properties.insert(
+                    name.to_string(),
+                    Schema {
+                        items: Some(Box::new(schema)),
+                        ..Schema::of_type("array")
+                    },
+                );
+                if !examples.is_empty() {
+                    example.insert(name.to_string(), JsonValue::Array(examples));
+                }
+            } else {
+                properties.insert(name.to_string(), schema);
+                if let Some(value) = examples.into_iter().next() {
+                    example.insert(name.to_string(), value);
+                }
+            }
+        }
+
+        let required: Vec<String> = fields.keys().map(|name| name.to_string()).collect();
+        let schema = Schema {
+            properties: Some(properties),
+            required: if required.is_empty() { None } else { Some(required) },
+            ..Schema::of_type("object")
+        };
+
+        self.merge_media_type(
+            content,
+            content_type,
+            MediaType {
+                schema: Some(schema),
+                example: Some(JsonValue::Object(example)),
+            },
+        );
+    }
+
+    /// Merges a media type into the content map under its essence (without
+    /// parameters such as `charset`), keeping the first example.
+    fn merge_media_type(
+        &self,
+        content: &mut IndexMap<String, MediaType>,
+        content_type: &str,
+        media_type: MediaType,
+    ) {
+        let key = content_type
+            .split(';')
+            .next()
+            .unwrap_or(content_type)
+            .trim()
+            .to_lowercase();
+
+        match content.get_mut(&key) {
+            Some(existing) => {
+                existing.schema = match (existing.schema.take(), media_type.schema) {
+                    (Some(left), Some(right)) => Some(left.merge(right)),
+                    (left, right) => left.or(right),
+                };
+                if existing.example.is_none() {
+                    existing.example = media_type.example;
+                }
+            }
+            None => {
+                content.insert(key, media_type);
+            }
+        }
+    }
+
     fn infer_schema(&self, text: &str, content_type: &str) -> Result<Option<Schema>> {
         if content_type.contains("json") {
             if let Ok(json) = serde_json::from_str::<JsonValue>(text) {
@@ -288,47 +765,311 @@ impl OpenApiGenerator {
             }
         }
 
-        Ok(Some(Schema::Simple {
-            schema_type: "string".to_string(),
-        }))
+        if content_type.contains("xml") {
+            if let Ok(schema) = xml::infer(text, &|value| self.string_to_schema(value)) {
+                return Ok(Some(schema));
+            }
+        }
+
+        // Images, archives and other binary payloads
+        let essence = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+        let is_text = essence.starts_with("text/")
+            || ["json", "xml", "javascript", "yaml", "x-www-form-urlencoded", "graphql"]
+                .iter()
+                .any(|marker| essence.contains(marker));
+        if !is_text && !essence.is_empty() {
+            return Ok(Some(Schema {
+                format: Some("binary".to_string()),
+                ..Schema::of_type("string")
+            }));
+        }
+
+        Ok(Some(Schema::of_type("string")))
     }
 
     fn json_to_schema(&self, json: &JsonValue) -> Schema {
         match json {
             JsonValue::Object(obj) => {
-                let mut properties: IndexMap<String, Box<Schema>> = IndexMap::new();
-                for (key, value) in obj {
-                    properties.insert(key.clone(), Box::new(self.json_to_schema(value)));
-                }
-                Schema::Object {
-                    schema_type: "object".to_string(),
+                let properties: IndexMap<String, Schema> = obj
+                    .iter()
+                    .map(|(key, value)| (key.clone(), self.json_to_schema(value)))
+                    .collect();
+                // A single sample requires every key it contains; merging
+                // narrows this down to the keys present in all samples
+                let required: Vec<String> = obj.keys().cloned().collect();
+                Schema {
                     properties: Some(properties),
-                    required: None,
-                }
-            }
-            JsonValue::Array(arr) => {
-                let items = if let Some(first) = arr.first() {
-                    Some(Box::new(self.json_to_schema(first)))
-                } else {
-                    None
-                };
-                Schema::Array {
-                    schema_type: "array".to_string(),
-                    items,
+                    required: if required.is_empty() { None } else { Some(required) },
+                    ..Schema::of_type("object")
                 }
             }
-            JsonValue::String(_) => Schema::Simple {
-                schema_type: "string".to_string(),
+            JsonValue::Array(arr) => Schema {
+                items: arr
+                    .iter()
+                    .map(|item| self.json_to_schema(item))
+                    .reduce(Schema::merge)
+                    .map(Box::new),
+                ..Schema::of_type("array")
             },
-            JsonValue::Number(_) => Schema::Simple {
-                schema_type: "number".to_string(),
+            JsonValue::String(value) => Schema {
+                format: string_format::detect(value).map(str::to_string),
+                ..Schema::of_type("string")
+            },
+            JsonValue::Number(number) => self.number_to_schema(number),
+            JsonValue::Bool(_) => Schema::of_type("boolean"),
+            JsonValue::Null => Schema::null(),
+        }
+    }
+
+    fn number_to_schema(&self, number: &serde_json::Number) -> Schema {
+        let integer = number.as_i64().or_else(|| {
+            // Values such as `1.0` have no fractional part and still count as integers
+            number
+                .as_f64()
+                .filter(|value| value.fract() == 0.0 && value.abs() < i64::MAX as f64)
+                .map(|value| value as i64)
+        });
+
+        let mut schema = match integer {
+            Some(value) => Schema {
+                format: Some(if i32::try_from(value).is_ok() { "int32" } else { "int64" }.to_string()),
+                ..Schema::of_type("integer")
             },
-            JsonValue::Bool(_) => Schema::Simple {
-                schema_type: "boolean".to_string(),
+            // Larger than i64::MAX, so no standard format applies
+            None if number.is_u64() => Schema::of_type("integer"),
+            None => Schema::of_type("number"),
+        };
+
+        if self.observed_bounds {
+            schema.extensions.insert(OBSERVED_MINIMUM.to_string(), JsonValue::Number(number.clone()));
+            schema.extensions.insert(OBSERVED_MAXIMUM.to_string(), JsonValue::Number(number.clone()));
+        }
+
+        schema
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::HarPostData;
+    use serde_json::json;
+
+    /// A sample for `url` that got a `status` response of `mime_type`.
+    fn entry(method: &str, url: &str, status: u16, mime_type: &str, body: &str) -> HarEntry {
+        let query: Vec<JsonValue> = url::Url::parse(url)
+            .unwrap()
+            .query_pairs()
+            .map(|(name, value)| json!({ "name": name, "value": value }))
+            .collect();
+        serde_json::from_value(json!({
+            "startedDateTime": "2024-01-01T00:00:00Z",
+            "time": 0.0,
+            "request": {
+                "method": method,
+                "url": url,
+                "httpVersion": "HTTP/1.1",
+                "headers": [],
+                "queryString": query,
+                "headersSize": -1,
+                "bodySize": 0,
             },
-            JsonValue::Null => Schema::Simple {
-                schema_type: "null".to_string(),
+            "response": {
+                "status": status,
+                "statusText": "",
+                "httpVersion": "HTTP/1.1",
+                "headers": [],
+                "content": { "size": body.len(), "mimeType": mime_type, "text": body },
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": body.len(),
             },
+            "cache": {},
+            "timings": { "send": 0.0, "wait": 0.0, "receive": 0.0 },
+        }))
+        .unwrap()
+    }
+
+    fn with_body(mut entry: HarEntry, mime_type: &str, text: &str) -> HarEntry {
+        entry.request.post_data = Some(HarPostData {
+            mime_type: mime_type.to_string(),
+            text: text.to_string(),
+            params: None,
+        });
+        entry
+    }
+
+    fn operation(generator: &OpenApiGenerator, entries: &[HarEntry]) -> JsonValue {
+        let entries: Vec<&HarEntry> = entries.iter().collect();
+        let operation = generator.create_operation(&entries, &mut IndexMap::new()).unwrap();
+        serde_json::to_value(operation).unwrap()
+    }
+
+    fn schema(value: &Schema) -> JsonValue {
+        serde_json::to_value(value).unwrap()
+    }
+
+    #[test]
+    fn merges_request_body_samples() {
+        let generator = OpenApiGenerator::new();
+        let url = "https://api.example.com/users";
+        let ada = with_body(entry("POST", url, 201, "", ""), "application/json", r#"{"name": "Ada", "age": 36}"#);
+        let bob = with_body(entry("POST", url, 201, "", ""), "application/json; charset=utf-8", r#"{"name": "Bob"}"#);
+
+        let merged = operation(&generator, &[ada, bob]);
+        let body = &merged["requestBody"];
+        assert_eq!(body["required"], true);
+        assert_eq!(body["content"]["application/json"]["example"], json!({ "name": "Ada", "age": 36 }));
+        let schema = &body["content"]["application/json"]["schema"];
+        assert_eq!(schema["properties"]["age"], json!({ "type": "integer", "format": "int32" }));
+        assert_eq!(schema["required"], json!(["name"]));
+
+        // Only required when every sample sent a body
+        let ada = with_body(entry("POST", url, 201, "", ""), "application/json", r#"{"name": "Ada"}"#);
+        let merged = operation(&generator, &[ada, entry("POST", url, 400, "", "")]);
+        assert_eq!(merged["requestBody"]["required"], false);
+        assert!(operation(&generator, &[entry("GET", url, 200, "", "")]).get("requestBody").is_none());
+    }
+
+    #[test]
+    fn merges_responses_by_status() {
+        let url = "https://api.example.com/users/1";
+        let entries = [
+            entry("GET", url, 404, "application/json", r#"{"error": "missing"}"#),
+            entry("GET", url, 200, "application/json", r#"{"id": 1}"#),
+            entry("GET", url, 200, "application/json", r#"{"id": 2, "name": "Ada"}"#),
+            entry("GET", url, 503, "application/json", r#"{"error": "down", "retry": 5}"#),
+        ];
+
+        let merged = operation(&OpenApiGenerator::new(), &entries);
+        let statuses: Vec<&String> = merged["responses"].as_object().unwrap().keys().collect();
+        assert_eq!(statuses, ["200", "404", "503"]);
+        // Responses without a reason phrase are described by their status
+        assert_eq!(merged["responses"]["404"]["description"], "Not Found");
+        let ok = &merged["responses"]["200"]["content"]["application/json"];
+        assert_eq!(ok["schema"]["required"], json!(["id"]));
+        assert_eq!(ok["example"], json!({ "id": 1 }));
+
+        let merged = operation(&OpenApiGenerator::new().with_default_response(true), &entries);
+        let default = &merged["responses"]["default"];
+        assert_eq!(default["description"], "Unexpected error");
+        let schema = &default["content"]["application/json"]["schema"];
+        assert_eq!(schema["required"], json!(["error"]));
+        assert!(schema["properties"]["retry"].is_object());
+
+        // Without error samples the default response has no content
+        let merged = operation(&OpenApiGenerator::new().with_default_response(true), &entries[1..3]);
+        assert_eq!(merged["responses"]["default"], json!({ "description": "Unexpected error" }));
+    }
+
+    #[test]
+    fn detects_integer_formats() {
+        let generator = OpenApiGenerator::new();
+        let format = |value: JsonValue| {
+            let schema = generator.json_to_schema(&value);
+            (schema.schema_type.unwrap(), schema.format)
+        };
+        assert_eq!(format(json!(2_147_483_647)), ("integer".to_string(), Some("int32".to_string())));
+        assert_eq!(format(json!(-2_147_483_649_i64)), ("integer".to_string(), Some("int64".to_string())));
+        assert_eq!(format(json!(3.0)), ("integer".to_string(), Some("int32".to_string())));
+        assert_eq!(format(json!(u64::MAX)), ("integer".to_string(), None));
+        assert_eq!(format(json!(1.5)), ("number".to_string(), None));
+
+        // A larger sample widens the format, and bounds cover every sample
+        let generator = OpenApiGenerator::new().with_observed_bounds(true);
+        let items = generator.json_to_schema(&json!([7, -3, 5_000_000_000_i64]));
+        assert_eq!(
+            schema(&items.items.unwrap()),
+            json!({ "type": "integer", "format": "int64", "x-observed-minimum": -3, "x-observed-maximum": 5_000_000_000_i64 })
+        );
+    }
+
+    #[test]
+    fn documents_query_parameters_without_credentials() {
+        let entries = [
+            entry("GET", "https://api.example.com/items?page=1&tag=a&tag=b&api_key=secret", 200, "", ""),
+            entry("GET", "https://api.example.com/items?page=2&tag=c&access_token=secret", 200, "", ""),
+            entry("GET", "https://api.example.com/items?page=3&sort=name", 200, "", ""),
+        ];
+
+        let merged = operation(&OpenApiGenerator::new(), &entries);
+        let parameters = merged["parameters"].as_array().unwrap();
+        let names: Vec<&str> = parameters.iter().map(|parameter| parameter["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["page", "tag", "sort"]);
+
+        assert_eq!(parameters[0]["required"], true);
+        assert_eq!(parameters[0]["schema"], json!({ "type": "integer", "format": "int32" }));
+        assert_eq!(parameters[0]["example"], 1);
+        // Repeated in one sample, so an array; missing from one, so optional
+        assert_eq!(parameters[1]["required"], false);
+        assert_eq!(parameters[1]["schema"], json!({ "type": "array", "items": { "type": "string" } }));
+        assert_eq!(parameters[1]["example"], json!(["a", "b"]));
+        assert_eq!(parameters[2]["required"], false);
+    }
+
+    #[test]
+    fn templates_paths() {
+        let generator = OpenApiGenerator::new();
+        let template = |url: &str| {
+            let entry = entry("GET", url, 200, "", "");
+            generator.path_template(&entry, url::Url::parse(url).unwrap().path())
+        };
+
+        let (path, values) = template("https://api.example.com/users/42/posts/550e8400-e29b-41d4-a716-446655440000");
+        assert_eq!(path, "/users/{userId}/posts/{postId}");
+        assert_eq!(
+            values,
+            [
+                ("userId".to_string(), "42".to_string()),
+                ("postId".to_string(), "550e8400-e29b-41d4-a716-446655440000".to_string())
+            ]
+        );
+        assert_eq!(template("https://api.example.com/7/8").0, "/{id}/{id2}");
+        assert_eq!(template("https://api.example.com/v1/users/me").0, "/v1/users/me");
+
+        // The runner's template from Postman path variables wins
+        let mut entry = entry("GET", "https://api.example.com/users/ada", 200, "", "");
+        entry.request.path_template = Some("/users/{username}".to_string());
+        let (path, values) = generator.path_template(&entry, "/users/ada");
+        assert_eq!(path, "/users/{username}");
+        assert_eq!(values, [("username".to_string(), "ada".to_string())]);
+
+        for segment in ["42", "507f1f77bcf86cd799439011", "01ARZ3NDEKTSV4RRFFQ69G5FAV", "9f86d081884c7d65"] {
+            assert!(generator.is_identifier(segment), "{}", segment);
         }
+        for segment in ["v1", "me", "deadbeefdeadbeef", "users"] {
+            assert!(!generator.is_identifier(segment), "{}", segment);
+        }
+    }
+
+    #[test]
+    fn infers_bodies_by_content_type() {
+        let generator = OpenApiGenerator::new();
+        let infer = |text: &str, content_type: &str| schema(&generator.infer_schema(text, content_type).unwrap().unwrap());
+
+        assert_eq!(infer(r#"{"ok": true}"#, "application/problem+json")["properties"]["ok"], json!({ "type": "boolean" }));
+        assert_eq!(infer("<ok>true</ok>", "application/xml")["xml"]["name"], "ok");
+        assert_eq!(infer("not json", "application/json"), json!({ "type": "string" }));
+        assert_eq!(infer("hello", "text/plain; charset=utf-8"), json!({ "type": "string" }));
+        assert_eq!(infer("\u{89}PNG", "image/png"), json!({ "type": "string", "format": "binary" }));
+
+        // Form fields become properties; files are binary and repeated fields arrays
+        let field = |name: &str, value: Option<&str>, file_name: Option<&str>| HarParam {
+            name: name.to_string(),
+            value: value.map(str::to_string),
+            file_name: file_name.map(str::to_string),
+            content_type: None,
+        };
+        let mut content = IndexMap::new();
+        generator.add_form_sample(
+            &mut content,
+            "multipart/form-data; boundary=x",
+            &[field("count", Some("2"), None), field("file", None, Some("a.png")), field("tag", Some("a"), None), field("tag", Some("b"), None)],
+        );
+        let form = serde_json::to_value(&content["multipart/form-data"]).unwrap();
+        assert_eq!(form["schema"]["properties"]["count"], json!({ "type": "integer", "format": "int32" }));
+        assert_eq!(form["schema"]["properties"]["file"], json!({ "type": "string", "format": "binary" }));
+        assert_eq!(form["schema"]["properties"]["tag"]["type"], "array");
+        assert_eq!(form["example"], json!({ "count": 2, "tag": ["a", "b"] }));
     }
 }
@@ -0,0 +1,528 @@
+// The subset of the Postman sandbox API available to scripts.
+//
+// Expects `__state` (see `ScriptState` in scripts.rs) to be defined. Scripts
+// change it through `pm`, and `__finish()` returns it to Rust as JSON.
+
+const __format = (value) => {
+  if (typeof value === "string") return JSON.stringify(value);
+  if (value === undefined) return "undefined";
+  if (typeof value === "function") return "[Function]";
+  try {
+    return JSON.stringify(value);
+  } catch (e) {
+    return String(value);
+  }
+};
+
+const __stringify = (value) => {
+  if (typeof value === "string") return value;
+  if (value === undefined || value === null) return "";
+  if (typeof value === "object") return JSON.stringify(value);
+  return String(value);
+};
+
+const console = (() => {
+  const log = (...args) =>
+    __print(args.map((arg) => (typeof arg === "string" ? arg : __format(arg))).join(" "));
+  return { log, info: log, warn: log, error: log, debug: log };
+})();
+
+// Variable scopes
+
+class VariableScope {
+  constructor(values) {
+    this._values = values;
+  }
+  get(key) {
+    return Object.prototype.hasOwnProperty.call(this._values, key) ? this._values[key] : undefined;
+  }
+  set(key, value) {
+    this._values[key] = __stringify(value);
+  }
+  unset(key) {
+    delete this._values[key];
+  }
+  has(key) {
+    return Object.prototype.hasOwnProperty.call(this._values, key);
+  }
+  clear() {
+    for (const key of Object.keys(this._values)) delete this._values[key];
+  }
+  toObject() {
+    return Object.assign({}, this._values);
+  }
+  replaceIn(template) {
+    return __replaceIn(String(template), JSON.stringify(this._scopes()));
+  }
+  // The scopes `replaceIn` resolves from, as in `Variables`
+  _scopes() {
+    return { local: this._values };
+  }
+}
+
+class LocalScope extends VariableScope {
+  get(key) {
+    const { local, data, environment, collection, globals } = __state.variables;
+    for (const values of [local, data, environment, collection, globals]) {
+      if (Object.prototype.hasOwnProperty.call(values, key)) return values[key];
+    }
+    return undefined;
+  }
+  has(key) {
+    return this.get(key) !== undefined;
+  }
+  _scopes() {
+    return __state.variables;
+  }
+  toObject() {
+    const { local, data, environment, collection, globals } = __state.variables;
+    return Object.assign({}, globals, collection, environment, data, local);
+  }
+}
+
+// Headers and URLs
+
+class HeaderList {
+  constructor(headers) {
+    this.list = headers.map((header) => ({ key: header.key, value: header.value }));
+  }
+  _index(key) {
+    const lower = String(key).toLowerCase();
+    return this.list.findIndex((header) => header.key.toLowerCase() === lower);
+  }
+  get(key) {
+    const index = this._index(key);
+    return index === -1 ? undefined : this.list[index].value;
+  }
+  has(key) {
+    return this._index(key) !== -1;
+  }
+  add(header) {
+    this.list.push({ key: header.key, value: __stringify(header.value) });
+  }
+  upsert(header) {
+    const index = this._index(header.key);
+    if (index === -1) this.add(header);
+    else this.list[index].value = __stringify(header.value);
+  }
+  remove(key) {
+    const lower = String(key).toLowerCase();
+    this.list = this.list.filter((header) => header.key.toLowerCase() !== lower);
+  }
+  each(callback) {
+    this.list.forEach(callback);
+  }
+  all() {
+    return this.list.slice();
+  }
+  toObject() {
+    const object = {};
+    for (const header of this.list) object[header.key] = header.value;
+    return object;
+  }
+  count() {
+    return this.list.length;
+  }
+}
+
+class QueryList extends HeaderList {
+  constructor(query) {
+    super(
+      query
+        ? query
+            .split("&")
+            .filter((pair) => pair !== "")
+            .map((pair) => {
+              const index = pair.indexOf("=");
+              return index === -1
+                ? { key: pair, value: null }
+                : { key: pair.slice(0, index), value: pair.slice(index + 1) };
+            })
+        : []
+    );
+  }
+  get(key) {
+    const found = this.list.find((param) => param.key === key);
+    return found ? found.value : undefined;
+  }
+  has(key) {
+    return this.list.some((param) => param.key === key);
+  }
+  remove(key) {
+    this.list = this.list.filter((param) => param.key !== key);
+  }
+  upsert(param) {
+    const found = this.list.find((existing) => existing.key === param.key);
+    if (found) found.value = __stringify(param.value);
+    else this.add(param);
+  }
+  toString() {
+    return this.list.map((param) => (param.value === null ? param.key : `${param.key}=${param.value}`)).join("&");
+  }
+}
+
+class RequestUrl {
+  constructor(raw) {
+    this.update(raw);
+  }
+  update(raw) {
+    const text = String(raw);
+    const index = text.indexOf("?");
+    this._base = index === -1 ? text : text.slice(0, index);
+    this.query = new QueryList(index === -1 ? "" : text.slice(index + 1));
+  }
+  getHost() {
+    return this._base.replace(/^[a-z]+:\/\//i, "").split("/")[0];
+  }
+  getPath() {
+    const withoutScheme = this._base.replace(/^[a-z]+:\/\//i, "");
+    const index = withoutScheme.indexOf("/");
+    return index === -1 ? "/" : withoutScheme.slice(index);
+  }
+  getQueryString() {
+    return this.query.toString();
+  }
+  toString() {
+    const query = this.query.toString();
+    return query ? `${this._base}?${query}` : this._base;
+  }
+}
+
+// Assertions
+
+class AssertionError extends Error {
+  constructor(message) {
+    super(message);
+    this.name = "AssertionError";
+  }
+}
+
+const __deepEqual = (left, right) => {
+  if (left === right) return true;
+  if (typeof left !== "object" || typeof right !== "object" || left === null || right === null) {
+    return Number.isNaN(left) && Number.isNaN(right);
+  }
+  if (Array.isArray(left) !== Array.isArray(right)) return false;
+  const leftKeys = Object.keys(left);
+  const rightKeys = Object.keys(right);
+  return leftKeys.length === rightKeys.length && leftKeys.every((key) => __deepEqual(left[key], right[key]));
+};
+
+const __typeOf = (value) => {
+  if (value === null) return "null";
+  if (Array.isArray(value)) return "array";
+  if (value instanceof RegExp) return "regexp";
+  return typeof value;
+};
+
+class Assertion {
+  constructor(value, message) {
+    this._value = value;
+    this._message = message;
+    this._negate = false;
+    this._deep = false;
+  }
+  _assert(passed, message, negatedMessage) {
+    if (this._negate ? passed : !passed) {
+      const reason = this._negate ? negatedMessage : message;
+      throw new AssertionError(this._message ? `${this._message}: ${reason}` : reason);
+    }
+    return this;
+  }
+  get not() {
+    this._negate = !this._negate;
+    return this;
+  }
+  get deep() {
+    this._deep = true;
+    return this;
+  }
+  get ok() {
+    return this._assert(!!this._value, `expected ${__format(this._value)} to be truthy`, `expected ${__format(this._value)} to be falsy`);
+  }
+  get exist() {
+    return this._assert(this._value !== null && this._value !== undefined, `expected ${__format(this._value)} to exist`, `expected ${__format(this._value)} to not exist`);
+  }
+  get empty() {
+    const value = this._value;
+    const empty =
+      typeof value === "string" || Array.isArray(value)
+        ? value.length === 0
+        : value && typeof value === "object"
+          ? Object.keys(value).length === 0
+          : false;
+    return this._assert(empty, `expected ${__format(value)} to be empty`, `expected ${__format(value)} not to be empty`);
+  }
+  equal(expected) {
+    if (this._deep) return this.eql(expected);
+    return this._assert(this._value === expected, `expected ${__format(this._value)} to equal ${__format(expected)}`, `expected ${__format(this._value)} to not equal ${__format(expected)}`);
+  }
+  eql(expected) {
+    return this._assert(__deepEqual(this._value, expected), `expected ${__format(this._value)} to deeply equal ${__format(expected)}`, `expected ${__format(this._value)} to not deeply equal ${__format(expected)}`);
+  }
+  above(limit) {
+    return this._assert(this._value > limit, `expected ${__format(this._value)} to be above ${limit}`, `expected ${__format(this._value)} to be at most ${limit}`);
+  }
+  below(limit) {
+    return this._assert(this._value < limit, `expected ${__format(this._value)} to be below ${limit}`, `expected ${__format(this._value)} to be at least ${limit}`);
+  }
+  least(limit) {
+    return this._assert(this._value >= limit, `expected ${__format(this._value)} to be at least ${limit}`, `expected ${__format(this._value)} to be below ${limit}`);
+  }
+  most(limit) {
+    return this._assert(this._value <= limit, `expected ${__format(this._value)} to be at most ${limit}`, `expected ${__format(this._value)} to be above ${limit}`);
+  }
+  within(low, high) {
+    return this._assert(this._value >= low && this._value <= high, `expected ${__format(this._value)} to be within ${low}..${high}`, `expected ${__format(this._value)} to not be within ${low}..${high}`);
+  }
+  a(type) {
+    const actual = __typeOf(this._value);
+    return this._assert(actual === String(type).toLowerCase(), `expected ${__format(this._value)} to be a ${type}`, `expected ${__format(this._value)} not to be a ${type}`);
+  }
+  include(expected) {
+    const value = this._value;
+    let included;
+    if (typeof value === "string") included = value.includes(expected);
+    else if (Array.isArray(value)) included = value.some((item) => __deepEqual(item, expected));
+    else if (value && typeof value === "object" && expected && typeof expected === "object") {
+      included = Object.keys(expected).every((key) => __deepEqual(value[key], expected[key]));
+    } else included = false;
+    return this._assert(included, `expected ${__format(value)} to include ${__format(expected)}`, `expected ${__format(value)} to not include ${__format(expected)}`);
+  }
+  property(name, ...expected) {
+    const value = this._value;
+    const has = value !== null && value !== undefined && name in Object(value);
+    if (expected.length === 0 || !has) {
+      this._assert(has, `expected ${__format(value)} to have property ${__format(name)}`, `expected ${__format(value)} to not have property ${__format(name)}`);
+    } else {
+      const actual = value[name];
+      const matches = this._deep ? __deepEqual(actual, expected[0]) : actual === expected[0];
+      this._assert(matches, `expected ${__format(value)} to have property ${__format(name)} of ${__format(expected[0])}, but got ${__format(actual)}`, `expected ${__format(value)} to not have property ${__format(name)} of ${__format(expected[0])}`);
+    }
+    if (has && !this._negate) this._value = value[name];
+    return this;
+  }
+  keys(...keys) {
+    const expected = keys.length === 1 && Array.isArray(keys[0]) ? keys[0] : keys;
+    const actual = Object.keys(Object(this._value));
+    const matches = expected.length === actual.length && expected.every((key) => actual.includes(key));
+    return this._assert(matches, `expected ${__format(this._value)} to have keys ${__format(expected)}`, `expected ${__format(this._value)} to not have keys ${__format(expected)}`);
+  }
+  lengthOf(length) {
+    const actual = this._value === null || this._value === undefined ? undefined : this._value.length;
+    return this._assert(actual === length, `expected ${__format(this._value)} to have a length of ${length} but got ${actual}`, `expected ${__format(this._value)} to not have a length of ${length}`);
+  }
+  match(pattern) {
+    return this._assert(pattern.test(this._value), `expected ${__format(this._value)} to match ${pattern}`, `expected ${__format(this._value)} not to match ${pattern}`);
+  }
+  oneOf(list) {
+    return this._assert(list.some((item) => __deepEqual(item, this._value)), `expected ${__format(this._value)} to be one of ${__format(list)}`, `expected ${__format(this._value)} to not be one of ${__format(list)}`);
+  }
+}
+
+// Boa does not accept keywords as getter names in class bodies
+const __valueChecks = {
+  true: (value) => value === true,
+  false: (value) => value === false,
+  null: (value) => value === null,
+  undefined: (value) => value === undefined,
+};
+for (const [name, check] of Object.entries(__valueChecks)) {
+  Object.defineProperty(Assertion.prototype, name, {
+    get() {
+      return this._assert(check(this._value), `expected ${__format(this._value)} to be ${name}`, `expected ${__format(this._value)} to not be ${name}`);
+    },
+  });
+}
+
+for (const word of ["to", "be", "been", "is", "that", "which", "and", "has", "have", "with", "at", "of", "same", "does", "still", "also"]) {
+  Object.defineProperty(Assertion.prototype, word, {
+    get() {
+      return this;
+    },
+  });
+}
+const __aliases = {
+  equals: "equal",
+  eq: "equal",
+  eqls: "eql",
+  an: "a",
+  includes: "include",
+  contain: "include",
+  contains: "include",
+  gt: "above",
+  greaterThan: "above",
+  lt: "below",
+  lessThan: "below",
+  gte: "least",
+  lte: "most",
+  matches: "match",
+  key: "keys",
+};
+for (const [alias, name] of Object.entries(__aliases)) {
+  Assertion.prototype[alias] = Assertion.prototype[name];
+}
+Object.defineProperty(Assertion.prototype, "length", {
+  get() {
+    return (expected) => this.lengthOf(expected);
+  },
+});
+
+// Requests and responses
+
+const __request = (() => {
+  const state = __state.request;
+  return {
+    method: state.method,
+    url: new RequestUrl(state.url),
+    headers: new HeaderList(state.headers),
+    body: state.body === null ? undefined : { mode: "raw", raw: state.body, toString() { return this.raw; } },
+  };
+})();
+
+const __responseAssertion = (response, negate) => {
+  const check = (passed, message, negatedMessage) => {
+    if (negate ? passed : !passed) throw new AssertionError(negate ? negatedMessage : message);
+  };
+  const assertion = {
+    status(expected) {
+      const actual = typeof expected === "number" ? response.code : response.status;
+      check(actual === expected, `expected response to have status ${__format(expected)} but got ${__format(actual)}`, `expected response to not have status ${__format(expected)}`);
+    },
+    header(key, value) {
+      const actual = response.headers.get(key);
+      if (value === undefined) {
+        check(actual !== undefined, `expected response to have header ${key}`, `expected response to not have header ${key}`);
+      } else {
+        check(actual === value, `expected response header ${key} to be ${__format(value)} but got ${__format(actual)}`, `expected response header ${key} to not be ${__format(value)}`);
+      }
+    },
+    body(expected) {
+      const text = response.text();
+      if (expected === undefined) check(text.length > 0, "expected response to have a body", "expected response to not have a body");
+      else check(text === expected, `expected response body to equal ${__format(expected)}`, `expected response body to not equal ${__format(expected)}`);
+    },
+    jsonBody(path) {
+      let json;
+      try {
+        json = response.json();
+      } catch (e) {
+        check(false, "expected response to have a JSON body", "expected response to not have a JSON body");
+        return;
+      }
+      if (path !== undefined) {
+        const found = String(path).split(".").reduce((value, key) => (value === undefined || value === null ? undefined : value[key]), json);
+        check(found !== undefined, `expected response JSON to have ${path}`, `expected response JSON to not have ${path}`);
+      } else check(true, "", "expected response to not have a JSON body");
+    },
+  };
+  const range = (low, high, name) => ({
+    get() {
+      check(response.code >= low && response.code <= high, `expected response to be ${name} but got ${response.code}`, `expected response to not be ${name}`);
+      return assertion;
+    },
+  });
+  Object.defineProperty(assertion, "ok", range(200, 200, "ok"));
+  Object.defineProperty(assertion, "success", range(200, 299, "successful"));
+  Object.defineProperty(assertion, "redirection", range(300, 399, "a redirection"));
+  Object.defineProperty(assertion, "clientError", range(400, 499, "a client error"));
+  Object.defineProperty(assertion, "serverError", range(500, 599, "a server error"));
+  Object.defineProperty(assertion, "error", range(400, 599, "an error"));
+  Object.defineProperty(assertion, "notFound", range(404, 404, "not found"));
+  Object.defineProperty(assertion, "json", {
+    get() {
+      assertion.jsonBody();
+      return assertion;
+    },
+  });
+  assertion.have = assertion;
+  assertion.be = assertion;
+  Object.defineProperty(assertion, "not", {
+    get() {
+      return __responseAssertion(response, !negate);
+    },
+  });
+  return assertion;
+};
+
+const __makeResponse = (state) => {
+  const response = {
+    code: state.code,
+    status: state.status,
+    responseTime: state.responseTime,
+    headers: new HeaderList(state.headers),
+    text() {
+      return state.body;
+    },
+    json() {
+      return JSON.parse(state.body);
+    },
+  };
+  response.to = __responseAssertion(response, false);
+  return response;
+};
+
+// pm
+
+const pm = {
+  info: {
+    eventName: __state.eventName,
+    requestName: __state.requestName,
+    iteration: __state.iteration,
+    iterationCount: __state.iterationCount,
+  },
+  variables: new LocalScope(__state.variables.local),
+  environment: new VariableScope(__state.variables.environment),
+  collectionVariables: new VariableScope(__state.variables.collection),
+  globals: new VariableScope(__state.variables.globals),
+  iterationData: new VariableScope(__state.variables.data),
+  request: __request,
+  response: __state.response ? __makeResponse(__state.response) : undefined,
+  test(name, callback) {
+    try {
+      callback();
+      __state.tests.push({ name, error: null });
+    } catch (e) {
+      __state.tests.push({ name, error: String(e && e.message !== undefined ? e.message : e) });
+    }
+  },
+  expect(value, message) {
+    return new Assertion(value, message);
+  },
+  sendRequest(request, callback) {
+    const result = JSON.parse(__sendRequest(JSON.stringify(typeof request === "string" ? { url: request } : request)));
+    const error = result.error === null ? null : new Error(result.error);
+    const response = result.response ? __makeResponse(result.response) : undefined;
+    if (callback) callback(error, response);
+  },
+};
+pm.test.skip = (name) => __state.tests.push({ name, error: null, skipped: true });
+
+// Scripts imported from Insomnia use its Postman-compatible `insomnia` API
+const insomnia = pm;
+
+// Legacy `postman` and `tests` globals of older collections
+const tests = {};
+const postman = {
+  setEnvironmentVariable: (key, value) => pm.environment.set(key, value),
+  getEnvironmentVariable: (key) => pm.environment.get(key),
+  clearEnvironmentVariable: (key) => pm.environment.unset(key),
+  setGlobalVariable: (key, value) => pm.globals.set(key, value),
+  getGlobalVariable: (key) => pm.globals.get(key),
+  clearGlobalVariable: (key) => pm.globals.unset(key),
+};
+const responseCode = __state.response ? { code: __state.response.code, name: __state.response.status } : undefined;
+const responseBody = __state.response ? __state.response.body : undefined;
+
+const __finish = () => {
+  for (const [name, passed] of Object.entries(tests)) {
+    __state.tests.push({ name, error: passed ? null : "test evaluated to false" });
+  }
+  __state.request = {
+    method: __request.method,
+    url: __request.url.toString(),
+    headers: __request.headers.list,
+    body: __request.body === undefined ? null : __request.body.raw,
+  };
+  return JSON.stringify(__state);
+};
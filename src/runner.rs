@@ -1,11 +1,23 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use reqwest::multipart::{Form, Part};
 use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::time::SystemTime;
 
+use crate::auth::{self, AuthParts, TokenCache};
 use crate::collection::{
-    Environment, PostmanCollection, Request, RequestItem, Url,
+    Auth, Body, Environment, FileSource, FullRequest, KeyValue, PostmanCollection, Request, Url, UrlObject,
+    Variable,
 };
+use crate::dynamic::DynamicVariables;
+use crate::extract::{self, ExtractRules};
+use crate::iteration_data::DataRow;
+use crate::scripts::{self, ScriptRequest, ScriptResponse, ScriptState};
+use crate::security;
+use crate::variables::Variables;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Har {
@@ -34,6 +46,8 @@ pub struct HarEntry {
     pub response: HarResponse,
     pub cache: serde_json::Value,
     pub timings: HarTimings,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -52,6 +66,45 @@ pub struct HarRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "postData")]
     pub post_data: Option<HarPostData>,
+    /// Path of the URL with Postman path variables as `{name}` templates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "_pathTemplate")]
+    pub path_template: Option<String>,
+    /// Postman auth applied to the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "_auth")]
+    pub auth: Option<HarAuth>,
+}
+
+/// Describes how a request was authenticated, without any credentials.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarAuth {
+    /// Postman auth type: `bearer`, `basic`, `apikey`, `oauth2`, `digest` or `noauth`
+    #[serde(rename = "type")]
+    pub auth_type: String,
+    /// API key name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// API key location: `header` or `query`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "in")]
+    pub location: Option<String>,
+    /// OAuth 2.0 grant type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "grantType")]
+    pub grant_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "authorizationUrl")]
+    pub authorization_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "tokenUrl")]
+    pub token_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Vec<String>>,
+    /// `JWT` if the bearer token sent looks like one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "bearerFormat")]
+    pub bearer_format: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -88,6 +141,22 @@ pub struct HarPostData {
     #[serde(rename = "mimeType")]
     pub mime_type: String,
     pub text: String,
+    /// Fields of urlencoded and multipart bodies
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<Vec<HarParam>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HarParam {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "fileName")]
+    pub file_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "contentType")]
+    pub content_type: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -105,55 +174,249 @@ pub struct HarTimings {
     pub receive: f64,
 }
 
+/// What to do when a request still has placeholders that no variable
+/// defines, before it is sent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum UnresolvedMode {
+    /// Send the request with the placeholders as they are
+    Ignore,
+    /// List the placeholders and send the request anyway
+    #[default]
+    Warn,
+    /// List the placeholders and fail the request without sending it
+    Error,
+}
+
 pub struct CollectionRunner {
     collection: PostmanCollection,
-    variables: HashMap<String, String>,
+    /// The environment and globals as loaded, for exporting them after the run
+    environment: Environment,
+    globals: Environment,
+    variables: Variables,
+    dynamic: DynamicVariables,
+    tokens: TokenCache,
+    working_dir: PathBuf,
+    extract_rules: IndexMap<String, ExtractRules>,
+    unresolved_mode: UnresolvedMode,
+    iteration_data: Vec<DataRow>,
+    iteration_count: Option<usize>,
 }
 
 impl CollectionRunner {
     pub fn new(collection: PostmanCollection, environment: Option<Environment>) -> Self {
-        let mut variables = HashMap::new();
+        let mut variables = Variables::default();
 
         // Load collection variables
         if let Some(vars) = &collection.variable {
-            for var in vars {
-                variables.insert(var.key.clone(), var.value.clone());
+            for var in vars.iter().filter(|var| var.is_enabled()) {
+                variables.collection.insert(var.key.clone(), var.value_text());
             }
         }
 
-        // Load environment variables (override collection variables)
-        if let Some(env) = environment {
-            for var in env.values {
-                variables.insert(var.key.clone(), var.value.clone());
-            }
+        // Load environment variables (they take precedence over collection variables)
+        let environment = environment.unwrap_or_default();
+        for var in environment.values.iter().filter(|var| var.is_enabled()) {
+            variables.environment.insert(var.key.clone(), var.value_text());
         }
 
         Self {
             collection,
+            environment,
+            globals: Environment::default(),
             variables,
+            dynamic: DynamicVariables::default(),
+            tokens: TokenCache::default(),
+            working_dir: PathBuf::from("."),
+            extract_rules: IndexMap::new(),
+            unresolved_mode: UnresolvedMode::default(),
+            iteration_data: Vec::new(),
+            iteration_count: None,
         }
     }
 
-    pub async fn run(&self) -> Result<Har> {
-        let requests = self.collection.get_all_requests();
+    /// Sets the global variables, which every other scope takes precedence over.
+    pub fn with_globals(mut self, globals: Environment) -> Self {
+        self.variables.globals = globals
+            .values
+            .iter()
+            .filter(|var| var.is_enabled())
+            .map(|var| (var.key.clone(), var.value_text()))
+            .collect();
+        self.globals = globals;
+        self
+    }
+
+    /// Sets extraction rules keyed by request name or `Folder/Request` path,
+    /// in addition to those set on the items themselves.
+    pub fn with_extract_rules(mut self, extract_rules: IndexMap<String, ExtractRules>) -> Self {
+        self.extract_rules = extract_rules;
+        self
+    }
+
+    /// Sets the data rows of the iterations; the collection runs once per row.
+    pub fn with_iteration_data(mut self, iteration_data: Vec<DataRow>) -> Self {
+        self.iteration_data = iteration_data;
+        self
+    }
+
+    /// Sets how many times the collection runs, instead of once per data row.
+    pub fn with_iteration_count(mut self, iteration_count: Option<usize>) -> Self {
+        self.iteration_count = iteration_count;
+        self
+    }
+
+    /// Sets what happens to requests with placeholders no variable defines.
+    pub fn with_unresolved_mode(mut self, unresolved_mode: UnresolvedMode) -> Self {
+        self.unresolved_mode = unresolved_mode;
+        self
+    }
+
+    /// Seeds the generator behind dynamic variables such as `{{$guid}}`, so
+    /// runs produce the same values.
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.dynamic = DynamicVariables::new(seed);
+        self
+    }
+
+    /// Sets the directory that relative paths of files to upload are resolved
+    /// against, usually the one containing the collection.
+    pub fn with_working_dir(mut self, working_dir: impl Into<PathBuf>) -> Self {
+        self.working_dir = working_dir.into();
+        self
+    }
+
+    pub async fn run(&mut self) -> Result<Har> {
+        let requests = self.collection.get_all_request_contexts();
         let mut entries = Vec::new();
+        let (mut passed, mut failed) = (0, 0);
 
         println!("Running {} requests...", requests.len());
 
-        for (idx, request_item) in requests.iter().enumerate() {
-            println!("[{}/{}] {}", idx + 1, requests.len(), request_item.name);
+        let iterations = self
+            .iteration_count
+            .unwrap_or(self.iteration_data.len())
+            .max(1);
+
+        for iteration in 0..iterations {
+            if iterations > 1 {
+                println!("Iteration {}/{}", iteration + 1, iterations);
+            }
+
+            // Each iteration starts with its own data row and no local
+            // variables; once the rows run out, the last one is reused
+            self.variables.local.clear();
+            self.variables.data = self
+                .iteration_data
+                .get(iteration)
+                .or(self.iteration_data.last())
+                .cloned()
+                .unwrap_or_default();
+
+            for (idx, context) in requests.iter().enumerate() {
+                let name = &context.item.name;
+                println!("[{}/{}] {}", idx + 1, requests.len(), name);
+
+                let mut request = match &context.item.request {
+                    Request::Simple(url) => {
+                        eprintln!("  ✗ Failed: Simple URL requests not yet supported: {}", url);
+                        continue;
+                    }
+                    Request::Full(request) => request.clone(),
+                };
+
+                // Pre-request scripts may change variables and the request itself
+                let original = ScriptRequest::from_request(&request);
+                let mut state = ScriptState {
+                    event_name: "prerequest".to_string(),
+                    request_name: name.clone(),
+                    iteration,
+                    iteration_count: iterations,
+                    variables: std::mem::take(&mut self.variables),
+                    request: original.clone(),
+                    response: None,
+                    tests: Vec::new(),
+                };
+                run_scripts(&self.collection.scripts(context, "prerequest"), &mut state, &self.dynamic);
+                state.request.apply(&original, &mut request);
+                self.variables = std::mem::take(&mut state.variables);
+
+                let auth = self.collection.effective_auth(context);
+                let unresolved = match self.unresolved_mode {
+                    UnresolvedMode::Ignore => Vec::new(),
+                    _ => self
+                        .variables
+                        .unresolved(placeholder_texts(&request, auth).iter().map(String::as_str)),
+                };
+                let result = if unresolved.is_empty() {
+                    self.execute_request(name, &request, auth).await
+                } else {
+                    let list: Vec<String> = unresolved.iter().map(ToString::to_string).collect();
+                    let message = format!("Unresolved variables: {}", list.join(", "));
+                    if self.unresolved_mode == UnresolvedMode::Error {
+                        Err(anyhow::anyhow!(message))
+                    } else {
+                        eprintln!("  ! {}", message);
+                        self.execute_request(name, &request, auth).await
+                    }
+                };
 
-            match self.execute_request(request_item).await {
-                Ok(entry) => {
-                    println!("  ✓ {} {}", entry.response.status, entry.response.status_text);
-                    entries.push(entry);
+                match result {
+                    Ok(entry) => {
+                        println!("  ✓ {} {}", entry.response.status, entry.response.status_text);
+
+                        let path: Vec<&str> = context
+                            .folders
+                            .iter()
+                            .map(|folder| folder.name.as_str())
+                            .chain([name.as_str()])
+                            .collect();
+                        let rules = context
+                            .item
+                            .extract
+                            .iter()
+                            .chain(self.extract_rules.get(name))
+                            .chain(self.extract_rules.get(&path.join("/")).filter(|_| path.len() > 1));
+                        for rules in rules {
+                            for problem in extract::apply(rules, &entry, &mut self.variables.environment) {
+                                eprintln!("  ! {}", problem);
+                            }
+                        }
+
+                        let test_scripts = self.collection.scripts(context, "test");
+                        if !test_scripts.is_empty() {
+                            state.event_name = "test".to_string();
+                            state.variables = std::mem::take(&mut self.variables);
+                            state.response = Some(ScriptResponse::from_entry(&entry));
+                            run_scripts(&test_scripts, &mut state, &self.dynamic);
+                            self.variables = std::mem::take(&mut state.variables);
+                        }
+                        entries.push(entry);
+                    }
+                    Err(e) => {
+                        eprintln!("  ✗ Failed: {}", e);
+                    }
                 }
-                Err(e) => {
-                    eprintln!("  ✗ Failed: {}", e);
+
+                for test in &state.tests {
+                    match &test.error {
+                        _ if test.skipped => println!("    - {} (skipped)", test.name),
+                        None => {
+                            passed += 1;
+                            println!("    ✓ {}", test.name);
+                        }
+                        Some(error) => {
+                            failed += 1;
+                            println!("    ✗ {}: {}", test.name, error);
+                        }
+                    }
                 }
             }
         }
 
+        if passed + failed > 0 {
+            println!("Tests: {} passed, {} failed", passed, failed);
+        }
+
         Ok(Har {
             log: HarLog {
                 version: "1.2".to_string(),
@@ -166,74 +429,79 @@ impl CollectionRunner {
         })
     }
 
-    async fn execute_request(&self, request_item: &RequestItem) -> Result<HarEntry> {
-        let request = match &request_item.request {
-            Request::Simple(url) => {
-                return Err(anyhow::anyhow!("Simple URL requests not yet supported: {}", url));
-            }
-            Request::Full(req) => req,
-        };
+    /// The environment as it is after the run, including values set by
+    /// scripts and extractions, in Postman's format.
+    pub fn export_environment(&self) -> Environment {
+        export_environment(&self.environment, &self.variables.environment, "environment")
+    }
 
-        let url = self.resolve_url(&request.url)?;
+    /// The globals as they are after the run, in Postman's format.
+    pub fn export_globals(&self) -> Environment {
+        export_environment(&self.globals, &self.variables.globals, "globals")
+    }
+
+    /// The collection with its variables as they are after the run.
+    pub fn export_collection(&self) -> PostmanCollection {
+        let mut collection = self.collection.clone();
+        let variables = collection.variable.take().unwrap_or_default();
+        collection.variable = Some(export_variables(&variables, &self.variables.collection, None));
+        collection
+    }
+
+    async fn execute_request(&self, name: &str, request: &FullRequest, auth: Option<&Auth>) -> Result<HarEntry> {
         let method = request.method.to_uppercase();
 
         let client = reqwest::Client::builder()
             .danger_accept_invalid_certs(true)
             .build()?;
 
+        let auth = auth.filter(|auth| {
+            let supported = auth::is_supported(&auth.auth_type);
+            if !supported {
+                eprintln!("  ! {} auth is not supported; the request is sent without auth", auth.auth_type);
+            }
+            supported
+        });
+        let resolve = |text: &str| self.resolve_variables(text);
+        let auth_parts = match auth {
+            Some(auth) => auth::apply(auth, &resolve, &client, &self.tokens).await?,
+            None => AuthParts::default(),
+        };
+        // The HAR records the request without the credentials added by auth
+        let url = self.resolve_url(&request.url)?;
+        let sent_url = append_query(&url, &auth_parts.query);
+
         let start_time = SystemTime::now();
         let started_date_time = chrono::Utc::now().to_rfc3339();
 
-        let mut req_builder = match method.as_str() {
-            "GET" => client.get(&url),
-            "POST" => client.post(&url),
-            "PUT" => client.put(&url),
-            "DELETE" => client.delete(&url),
-            "PATCH" => client.patch(&url),
-            "HEAD" => client.head(&url),
-            "OPTIONS" => client.request(reqwest::Method::OPTIONS, &url),
-            _ => return Err(anyhow::anyhow!("Unsupported method: {}", method)),
-        };
+        let (req_builder, har_headers, post_data) = self.build_request(&client, &method, &sent_url, request, &auth_parts)?;
 
-        // Add headers
-        let mut har_headers = Vec::new();
-        if let Some(headers) = &request.header {
-            for header in headers {
-                if header.disabled.unwrap_or(false) {
-                    continue;
-                }
-                let value = self.resolve_variables(&header.value);
-                req_builder = req_builder.header(&header.key, &value);
-                har_headers.push(HarHeader {
-                    name: header.key.clone(),
-                    value,
-                });
-            }
-        }
+        // Execute request; digest auth answers the server's challenge with a retry
+        let digest = auth.and_then(|auth| auth::digest_credentials(auth, &resolve));
+        let mut response = req_builder.send().await?;
 
-        // Add body
-        let post_data = if let Some(body) = &request.body {
-            match body.mode.as_str() {
-                "raw" => {
-                    if let Some(raw) = &body.raw {
-                        let resolved_body = self.resolve_variables(raw);
-                        req_builder = req_builder.body(resolved_body.clone());
-                        Some(HarPostData {
-                            mime_type: "application/json".to_string(),
-                            text: resolved_body,
-                        })
-                    } else {
-                        None
-                    }
-                }
-                _ => None,
-            }
-        } else {
-            None
-        };
+        if let Some(credentials) = digest {
+            let challenge = response
+                .headers()
+                .get(reqwest::header::WWW_AUTHENTICATE)
+                .and_then(|value| value.to_str().ok());
+            let uri = match url::Url::parse(&sent_url) {
+                Ok(parsed) => match parsed.query() {
+                    Some(query) => format!("{}?{}", parsed.path(), query),
+                    None => parsed.path().to_string(),
+                },
+                Err(_) => sent_url.clone(),
+            };
+            let authorization = challenge
+                .filter(|_| response.status() == reqwest::StatusCode::UNAUTHORIZED)
+                .and_then(|challenge| auth::digest_authorization(challenge, &credentials, &method, &uri));
 
-        // Execute request
-        let response = req_builder.send().await?;
+            if let Some(value) = authorization {
+                // Built again, since multipart bodies cannot be cloned
+                let (retry, _, _) = self.build_request(&client, &method, &sent_url, request, &auth_parts)?;
+                response = retry.header(reqwest::header::AUTHORIZATION, &value).send().await?;
+            }
+        }
 
         let end_time = SystemTime::now();
         let duration = end_time.duration_since(start_time)?.as_millis() as f64;
@@ -267,10 +535,12 @@ impl CollectionRunner {
                 url: url.clone(),
                 http_version: "HTTP/1.1".to_string(),
                 headers: har_headers,
-                query_string: Vec::new(),
+                query_string: self.query_string(&url),
                 headers_size: -1,
                 body_size: post_data.as_ref().map(|p| p.text.len() as i32).unwrap_or(0),
                 post_data,
+                path_template: self.path_template(&request.url, &url),
+                auth: auth.map(|auth| self.har_auth(auth, &auth_parts)),
             },
             response: HarResponse {
                 status,
@@ -292,27 +562,648 @@ impl CollectionRunner {
                 wait: duration,
                 receive: 0.0,
             },
+            comment: Some(name.to_string()),
         })
     }
 
+    /// The request to send to `url`, with the headers and body recorded in
+    /// the HAR.
+    fn build_request(
+        &self,
+        client: &reqwest::Client,
+        method: &str,
+        url: &str,
+        request: &FullRequest,
+        auth_parts: &AuthParts,
+    ) -> Result<(reqwest::RequestBuilder, Vec<HarHeader>, Option<HarPostData>)> {
+        let mut req_builder = match method {
+            "GET" => client.get(url),
+            "POST" => client.post(url),
+            "PUT" => client.put(url),
+            "DELETE" => client.delete(url),
+            "PATCH" => client.patch(url),
+            "HEAD" => client.head(url),
+            "OPTIONS" => client.request(reqwest::Method::OPTIONS, url),
+            _ => return Err(anyhow::anyhow!("Unsupported method: {}", method)),
+        };
+
+        // Add headers
+        let mut har_headers = Vec::new();
+        let is_multipart = request.body.as_ref().is_some_and(|body| body.mode == "formdata");
+        if let Some(headers) = &request.header {
+            for header in headers {
+                if header.disabled.unwrap_or(false) {
+                    continue;
+                }
+                // The multipart content type needs the boundary chosen by reqwest
+                if is_multipart && header.key.eq_ignore_ascii_case("content-type") {
+                    continue;
+                }
+                let value = self.resolve_variables(header.value());
+                req_builder = req_builder.header(&header.key, &value);
+                har_headers.push(HarHeader {
+                    name: header.key.clone(),
+                    value,
+                });
+            }
+        }
+
+        // Add auth headers, unless the request sets the header itself
+        for (name, value) in &auth_parts.headers {
+            if har_headers.iter().any(|header| header.name.eq_ignore_ascii_case(name)) {
+                continue;
+            }
+            req_builder = req_builder.header(name, value);
+        }
+
+        // Add body
+        let post_data = match &request.body {
+            Some(body) => {
+                let (builder, post_data) = self.attach_body(req_builder, body, &har_headers)?;
+                req_builder = builder;
+                post_data
+            }
+            None => None,
+        };
+
+        Ok((req_builder, har_headers, post_data))
+    }
+
+    /// Attaches a body in any Postman body mode and describes it for the HAR.
+    fn attach_body(
+        &self,
+        mut req_builder: reqwest::RequestBuilder,
+        body: &Body,
+        headers: &[HarHeader],
+    ) -> Result<(reqwest::RequestBuilder, Option<HarPostData>)> {
+        let content_type = headers
+            .iter()
+            .find(|header| header.name.eq_ignore_ascii_case("content-type"))
+            .map(|header| header.value.clone());
+        let enabled = |fields: &Option<Vec<KeyValue>>| -> Vec<KeyValue> {
+            fields
+                .iter()
+                .flatten()
+                .filter(|field| !field.disabled.unwrap_or(false))
+                .cloned()
+                .collect()
+        };
+
+        match body.mode.as_str() {
+            "raw" => {
+                let Some(raw) = &body.raw else {
+                    return Ok((req_builder, None));
+                };
+                let text = self.resolve_variables(raw);
+                // Like Postman, a body without a language is plain text
+                let mime_type = content_type
+                    .clone()
+                    .unwrap_or_else(|| raw_content_type(body.raw_language().unwrap_or("text")).to_string());
+
+                if content_type.is_none() {
+                    req_builder = req_builder.header(reqwest::header::CONTENT_TYPE, &mime_type);
+                }
+                Ok((
+                    req_builder.body(text.clone()),
+                    Some(HarPostData {
+                        mime_type,
+                        text,
+                        params: None,
+                    }),
+                ))
+            }
+            "urlencoded" => {
+                let fields: Vec<(String, String)> = enabled(&body.urlencoded)
+                    .iter()
+                    .map(|field| (self.resolve_variables(&field.key), self.resolve_variables(field.value())))
+                    .collect();
+                let text = url::form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(&fields)
+                    .finish();
+                let params = fields
+                    .iter()
+                    .map(|(name, value)| HarParam {
+                        name: name.clone(),
+                        value: Some(value.clone()),
+                        file_name: None,
+                        content_type: None,
+                    })
+                    .collect();
+                Ok((
+                    req_builder.form(&fields),
+                    Some(HarPostData {
+                        mime_type: "application/x-www-form-urlencoded".to_string(),
+                        text,
+                        params: Some(params),
+                    }),
+                ))
+            }
+            "formdata" => {
+                let mut form = Form::new();
+                let mut params = Vec::new();
+                for field in enabled(&body.formdata) {
+                    let name = self.resolve_variables(&field.key);
+                    if field.is_file() {
+                        for src in field.src.iter().flat_map(FileSource::paths) {
+                            let path = self.working_dir.join(self.resolve_variables(src));
+                            let bytes = std::fs::read(&path)
+                                .with_context(|| format!("Failed to read form file {}", path.display()))?;
+                            let file_name = path
+                                .file_name()
+                                .map(|file_name| file_name.to_string_lossy().into_owned())
+                                .unwrap_or_default();
+                            let mime_type = field
+                                .content_type
+                                .clone()
+                                .unwrap_or_else(|| mime_guess::from_path(&path).first_or_octet_stream().to_string());
+
+                            form = form.part(
+                                name.clone(),
+                                Part::bytes(bytes).file_name(file_name.clone()).mime_str(&mime_type)?,
+                            );
+                            params.push(HarParam {
+                                name: name.clone(),
+                                value: None,
+                                file_name: Some(file_name),
+                                content_type: Some(mime_type),
+                            });
+                        }
+                    } else {
+                        let value = self.resolve_variables(field.value());
+                        let mut part = Part::text(value.clone());
+                        if let Some(mime_type) = &field.content_type {
+                            part = part.mime_str(mime_type)?;
+                        }
+                        form = form.part(name.clone(), part);
+                        params.push(HarParam {
+                            name,
+                            value: Some(value),
+                            file_name: None,
+                            content_type: field.content_type.clone(),
+                        });
+                    }
+                }
+
+                Ok((
+                    req_builder.multipart(form),
+                    Some(HarPostData {
+                        mime_type: "multipart/form-data".to_string(),
+                        text: String::new(),
+                        params: Some(params),
+                    }),
+                ))
+            }
+            "file" => {
+                let Some(src) = body.file.as_ref().and_then(|file| file.src.as_ref()) else {
+                    return Ok((req_builder, None));
+                };
+                let path = self.working_dir.join(self.resolve_variables(src));
+                let bytes = std::fs::read(&path)
+                    .with_context(|| format!("Failed to read body file {}", path.display()))?;
+                let mime_type = content_type
+                    .clone()
+                    .unwrap_or_else(|| mime_guess::from_path(&path).first_or_octet_stream().to_string());
+
+                if content_type.is_none() {
+                    req_builder = req_builder.header(reqwest::header::CONTENT_TYPE, &mime_type);
+                }
+                Ok((
+                    req_builder.body(bytes.clone()),
+                    Some(HarPostData {
+                        mime_type,
+                        // HAR has no encoding for binary post data
+                        text: String::from_utf8(bytes).unwrap_or_default(),
+                        params: None,
+                    }),
+                ))
+            }
+            "graphql" => {
+                let Some(graphql) = &body.graphql else {
+                    return Ok((req_builder, None));
+                };
+                let mut payload = serde_json::json!({ "query": self.resolve_variables(&graphql.query) });
+                if let Some(variables) = graphql
+                    .variables
+                    .as_deref()
+                    .map(|variables| self.resolve_variables(variables))
+                    .filter(|variables| !variables.trim().is_empty())
+                {
+                    payload["variables"] =
+                        serde_json::from_str(&variables).context("GraphQL variables are not valid JSON")?;
+                }
+                let text = payload.to_string();
+
+                if content_type.is_none() {
+                    req_builder = req_builder.header(reqwest::header::CONTENT_TYPE, "application/json");
+                }
+                Ok((
+                    req_builder.body(text.clone()),
+                    Some(HarPostData {
+                        mime_type: content_type.unwrap_or_else(|| "application/json".to_string()),
+                        text,
+                        params: None,
+                    }),
+                ))
+            }
+            _ => Ok((req_builder, None)),
+        }
+    }
+
+    fn har_auth(&self, auth: &Auth, parts: &AuthParts) -> HarAuth {
+        let param = |key: &str| auth.param(key).map(|value| self.resolve_variables(&value));
+
+        let mut har_auth = HarAuth {
+            auth_type: auth.auth_type.clone(),
+            name: None,
+            location: None,
+            grant_type: None,
+            authorization_url: None,
+            token_url: None,
+            scopes: None,
+            bearer_format: None,
+        };
+
+        match auth.auth_type.as_str() {
+            "apikey" => {
+                har_auth.name = param("key");
+                har_auth.location = Some(param("in").unwrap_or_else(|| "header".to_string()));
+            }
+            "oauth2" => {
+                har_auth.grant_type = param("grant_type");
+                har_auth.authorization_url = param("authUrl");
+                har_auth.token_url = param("accessTokenUrl");
+                har_auth.scopes = param("scope")
+                    .map(|scope| scope.split_whitespace().map(str::to_string).collect());
+            }
+            _ => {}
+        }
+
+        if matches!(auth.auth_type.as_str(), "bearer" | "oauth2") {
+            har_auth.bearer_format = parts
+                .headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("authorization"))
+                .and_then(|(_, value)| value.split_whitespace().nth(1))
+                .and_then(security::token_format);
+        }
+
+        har_auth
+    }
+
     fn resolve_url(&self, url: &Url) -> Result<String> {
         let url_str = match url {
             Url::String(s) => s.clone(),
-            Url::Object(obj) => obj.raw.clone().unwrap_or_default(),
+            Url::Object(obj) => {
+                // The fragment is not sent
+                let raw = url.raw();
+                let raw = raw.split('#').next().unwrap_or_default();
+                self.resolve_path_variables(raw, obj.variable.as_deref().unwrap_or_default())
+            }
         };
 
         Ok(self.resolve_variables(&url_str))
     }
 
+    /// Replaces `:name` path segments with the values of the URL's path variables.
+    fn resolve_path_variables(&self, url: &str, variables: &[Variable]) -> String {
+        if variables.is_empty() {
+            return url.to_string();
+        }
+
+        let (path, query) = match url.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (url, None),
+        };
+        let resolved: Vec<String> = path
+            .split('/')
+            .map(|segment| {
+                segment
+                    .strip_prefix(':')
+                    .and_then(|name| variables.iter().find(|variable| variable.key == name))
+                    .map(Variable::value_text)
+                    .unwrap_or_else(|| segment.to_string())
+            })
+            .collect();
+
+        match query {
+            Some(query) => format!("{}?{}", resolved.join("/"), query),
+            None => resolved.join("/"),
+        }
+    }
+
+    /// Path of the final URL with Postman path variables (`:userId`) and
+    /// whole-segment `{{variables}}` turned into `{name}` templates. Returns
+    /// `None` when the request uses neither.
+    fn path_template(&self, url: &Url, resolved: &str) -> Option<String> {
+        let segments: Vec<String> = match url {
+            Url::Object(UrlObject { path: Some(path), .. }) => path.segments('/'),
+            Url::Object(obj) => raw_path_segments(obj.raw.as_deref().unwrap_or_default()),
+            Url::String(raw) => raw_path_segments(raw),
+        };
+        let segments: Vec<&str> = segments
+            .iter()
+            .map(String::as_str)
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        if !segments.iter().any(|segment| template_name(segment).is_some()) {
+            return None;
+        }
+
+        // The host variable may carry a path prefix, so align from the end
+        let parsed = url::Url::parse(resolved).ok()?;
+        let actual: Vec<&str> = parsed.path().split('/').filter(|segment| !segment.is_empty()).collect();
+        let prefix_len = actual.len().checked_sub(segments.len())?;
+
+        let templated: Vec<String> = actual[..prefix_len]
+            .iter()
+            .map(|segment| segment.to_string())
+            .chain(segments.iter().zip(&actual[prefix_len..]).map(|(segment, value)| {
+                match template_name(segment) {
+                    Some(name) => format!("{{{}}}", name),
+                    None => value.to_string(),
+                }
+            }))
+            .collect();
+
+        Some(format!("/{}", templated.join("/")))
+    }
+
+    /// Decoded query parameters of the final URL, in order.
+    fn query_string(&self, url: &str) -> Vec<HarQueryParam> {
+        match url::Url::parse(url) {
+            Ok(parsed) => parsed
+                .query_pairs()
+                .map(|(name, value)| HarQueryParam {
+                    name: name.into_owned(),
+                    value: value.into_owned(),
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
     fn resolve_variables(&self, text: &str) -> String {
-        let mut result = text.to_string();
+        self.variables.resolve(text, &self.dynamic)
+    }
+}
+
+fn export_environment(original: &Environment, values: &HashMap<String, String>, scope: &str) -> Environment {
+    Environment {
+        id: original.id.clone(),
+        name: original.name.clone(),
+        values: export_variables(&original.values, values, Some(true)),
+        scope: Some(scope.to_string()),
+        exported_at: Some(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+        exported_using: Some(format!("harbinger/{}", env!("CARGO_PKG_VERSION"))),
+        extra: original.extra.clone(),
+    }
+}
+
+/// Writes current values back into a list of variables, keeping their order
+/// and settings. Variables that were unset are dropped, disabled ones are
+/// kept as they are and new ones are added at the end, marked `enabled` for
+/// environments.
+fn export_variables(original: &[Variable], values: &HashMap<String, String>, enabled: Option<bool>) -> Vec<Variable> {
+    let mut exported: Vec<Variable> = original
+        .iter()
+        .filter_map(|variable| match values.get(&variable.key) {
+            _ if !variable.is_enabled() => Some(variable.clone()),
+            // Unchanged values keep their original JSON type
+            Some(value) if *value == variable.value_text() => Some(variable.clone()),
+            Some(value) => Some(Variable {
+                value: Some(JsonValue::String(value.clone())),
+                ..variable.clone()
+            }),
+            None => None,
+        })
+        .collect();
+
+    let mut added: Vec<(&String, &String)> = values
+        .iter()
+        .filter(|(key, _)| !original.iter().any(|variable| &variable.key == *key && variable.is_enabled()))
+        .collect();
+    added.sort();
+    exported.extend(added.into_iter().map(|(key, value)| Variable {
+        id: None,
+        key: key.clone(),
+        value: Some(JsonValue::String(value.clone())),
+        var_type: Some("default".to_string()),
+        description: None,
+        disabled: None,
+        enabled,
+        extra: IndexMap::new(),
+    }));
+
+    exported
+}
+
+/// Runs the scripts of one event in order; a failing script is reported and
+/// the remaining ones still run.
+fn run_scripts(sources: &[String], state: &mut ScriptState, dynamic: &DynamicVariables) {
+    for source in sources {
+        if let Err(error) = scripts::run(source, state, dynamic) {
+            eprintln!("  ! Error in {} script: {}", state.event_name, error);
+        }
+    }
+}
+
+/// The parts of a request whose placeholders get resolved when it is sent:
+/// the URL, enabled headers, the body and the auth parameters.
+fn placeholder_texts(request: &FullRequest, auth: Option<&Auth>) -> Vec<String> {
+    let mut texts = Vec::new();
+
+    match &request.url {
+        Url::String(url) => texts.push(url.clone()),
+        Url::Object(url) => {
+            texts.extend(url.raw.clone());
+            let enabled_query = url.query.iter().flatten().filter(|param| !param.disabled.unwrap_or(false));
+            for param in enabled_query {
+                texts.extend(param.key.clone());
+                texts.extend(param.value_text());
+            }
+            texts.extend(url.variable.iter().flatten().map(Variable::value_text));
+        }
+    }
+
+    let enabled_headers = request.header.iter().flatten().filter(|header| !header.disabled.unwrap_or(false));
+    texts.extend(enabled_headers.map(|header| header.value().to_string()));
+
+    if let Some(body) = &request.body {
+        match body.mode.as_str() {
+            "raw" => texts.extend(body.raw.clone()),
+            "urlencoded" | "formdata" => {
+                let fields = if body.mode == "urlencoded" { &body.urlencoded } else { &body.formdata };
+                for field in fields.iter().flatten().filter(|field| !field.disabled.unwrap_or(false)) {
+                    texts.push(field.key.clone());
+                    match &field.src {
+                        Some(src) if field.is_file() => texts.extend(src.paths().into_iter().map(str::to_string)),
+                        _ => texts.push(field.value().to_string()),
+                    }
+                }
+            }
+            "file" => texts.extend(body.file.as_ref().and_then(|file| file.src.clone())),
+            "graphql" => {
+                if let Some(graphql) = &body.graphql {
+                    texts.push(graphql.query.clone());
+                    texts.extend(graphql.variables.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(params) = auth.and_then(|auth| auth.params.get(&auth.auth_type)) {
+        // Parameters are a list of key/value pairs, or an object in v2.0
+        let values: Vec<&serde_json::Value> = match params {
+            serde_json::Value::Array(pairs) => pairs.iter().filter_map(|pair| pair.get("value")).collect(),
+            serde_json::Value::Object(map) => map.values().collect(),
+            _ => Vec::new(),
+        };
+        texts.extend(values.into_iter().filter_map(|value| value.as_str().map(str::to_string)));
+    }
+
+    texts
+}
+
+/// Path segments of a raw Postman URL, without scheme, host and query.
+fn raw_path_segments(raw: &str) -> Vec<String> {
+    let without_query = raw.split(['?', '#']).next().unwrap_or_default();
+    let after_scheme = match without_query.split_once("://") {
+        Some((_, rest)) => rest,
+        None => without_query,
+    };
+    let path = match after_scheme.find('/') {
+        Some(index) => &after_scheme[index..],
+        None => "",
+    };
+
+    path.split('/').map(str::to_string).collect()
+}
+
+/// The parameter name of a `:name` or `{{name}}` path segment.
+fn template_name(segment: &str) -> Option<&str> {
+    segment
+        .strip_prefix(':')
+        .or_else(|| segment.strip_prefix("{{").and_then(|rest| rest.strip_suffix("}}")))
+        .filter(|name| !name.is_empty())
+}
+
+/// The content type Postman sends for the language of a raw body.
+pub(crate) fn raw_content_type(language: &str) -> &'static str {
+    match language {
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "javascript" => "application/javascript",
+        "html" => "text/html",
+        _ => "text/plain",
+    }
+}
+
+/// Appends query parameters, such as an API key, to a URL.
+fn append_query(url: &str, params: &[(String, String)]) -> String {
+    if params.is_empty() {
+        return url.to_string();
+    }
+
+    let query: Vec<String> = params
+        .iter()
+        .map(|(key, value)| {
+            let key: String = url::form_urlencoded::byte_serialize(key.as_bytes()).collect();
+            let value: String = url::form_urlencoded::byte_serialize(value.as_bytes()).collect();
+            format!("{}={}", key, value)
+        })
+        .collect();
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!("{}{}{}", url, separator, query.join("&"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn runner() -> CollectionRunner {
+        let collection = serde_json::from_value(json!({
+            "info": { "name": "Export", "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json" },
+            "item": [],
+            "variable": [
+                { "key": "page", "value": 1, "type": "number" },
+                { "key": "host", "value": "localhost" },
+                { "key": "off", "value": "x", "disabled": true },
+            ],
+        }))
+        .unwrap();
+        let environment = serde_json::from_value(json!({
+            "id": "e1",
+            "name": "Local",
+            "values": [
+                { "key": "token", "value": "old", "type": "secret", "enabled": true },
+                { "key": "user", "value": "ada", "enabled": true },
+                { "key": "spare", "value": "kept", "enabled": false },
+            ],
+            "color": "blue",
+        }))
+        .unwrap();
+        let globals = serde_json::from_value(json!({ "name": "Globals", "values": [{ "key": "region", "value": "eu", "enabled": true }] })).unwrap();
+        CollectionRunner::new(collection, Some(environment)).with_globals(globals)
+    }
+
+    #[test]
+    fn exports_variables_as_they_are_after_the_run() {
+        let mut runner = runner();
+        runner.variables.environment.insert("token".to_string(), "new".to_string());
+        runner.variables.environment.remove("user");
+        runner.variables.environment.insert("spare".to_string(), "set".to_string());
+        runner.variables.environment.insert("b".to_string(), "2".to_string());
+        runner.variables.environment.insert("a".to_string(), "1".to_string());
+        runner.variables.collection.insert("host".to_string(), "example.com".to_string());
+
+        let environment = serde_json::to_value(runner.export_environment()).unwrap();
+        assert_eq!(environment["id"], "e1");
+        assert_eq!(environment["color"], "blue");
+        assert_eq!(environment["_postman_variable_scope"], "environment");
+        assert_eq!(environment["values"], json!([
+            { "key": "token", "value": "new", "type": "secret", "enabled": true },
+            { "key": "spare", "value": "kept", "enabled": false },
+            { "key": "a", "value": "1", "type": "default", "enabled": true },
+            { "key": "b", "value": "2", "type": "default", "enabled": true },
+            { "key": "spare", "value": "set", "type": "default", "enabled": true },
+        ]));
+
+        // Unchanged values keep their type, and collection variables get no `enabled`
+        let collection = serde_json::to_value(runner.export_collection()).unwrap();
+        assert_eq!(collection["variable"], json!([
+            { "key": "page", "value": 1, "type": "number" },
+            { "key": "host", "value": "example.com" },
+            { "key": "off", "value": "x", "disabled": true },
+        ]));
+
+        let globals = serde_json::to_value(runner.export_globals()).unwrap();
+        assert_eq!(globals["_postman_variable_scope"], "globals");
+        assert_eq!(globals["values"], json!([{ "key": "region", "value": "eu", "enabled": true }]));
+    }
+
+    #[test]
+    fn exported_files_load_back_into_the_same_variables() {
+        let mut runner = runner();
+        runner.variables.environment.insert("token".to_string(), "new".to_string());
+        runner.variables.environment.insert("added".to_string(), "yes".to_string());
+        runner.variables.collection.remove("host");
+        runner.variables.collection.insert("next".to_string(), "2".to_string());
+        runner.variables.globals.insert("region".to_string(), "us".to_string());
 
-        // Replace {{variable}} with actual values
-        for (key, value) in &self.variables {
-            let placeholder = format!("{{{{{}}}}}", key);
-            result = result.replace(&placeholder, value);
+        // Through JSON text, as when the files are written and read again
+        fn reload<T: Serialize + serde::de::DeserializeOwned>(value: T) -> T {
+            serde_json::from_str(&serde_json::to_string_pretty(&value).unwrap()).unwrap()
         }
+        let environment = reload(runner.export_environment());
+        let globals = reload(runner.export_globals());
+        let collection = reload(runner.export_collection());
+        let reloaded = CollectionRunner::new(collection, Some(environment)).with_globals(globals);
 
-        result
+        assert_eq!(reloaded.variables.environment, runner.variables.environment);
+        assert_eq!(reloaded.variables.collection, runner.variables.collection);
+        assert_eq!(reloaded.variables.globals, runner.variables.globals);
+        assert_eq!(reloaded.export_environment().values.len(), runner.export_environment().values.len());
     }
 }
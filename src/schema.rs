@@ -0,0 +1,404 @@
+use indexmap::map::Entry;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value as JsonValue};
+
+/// An OpenAPI 3.0 Schema Object.
+///
+/// Every keyword is optional so any schema found in a spec can be represented,
+/// and unknown keywords (such as `x-` extensions) are kept in `extensions`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schema {
+    #[serde(rename = "$ref", skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub schema_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nullable: Option<bool>,
+    #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
+    pub enum_values: Option<Vec<JsonValue>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub example: Option<JsonValue>,
+
+    // Composition
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub one_of: Option<Vec<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub any_of: Option<Vec<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub all_of: Option<Vec<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not: Option<Box<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discriminator: Option<Discriminator>,
+
+    // Objects
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub properties: Option<IndexMap<String, Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub additional_properties: Option<AdditionalProperties>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_properties: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_properties: Option<u64>,
+
+    // Arrays
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<Box<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_items: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unique_items: Option<bool>,
+
+    // Numbers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<Number>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<Number>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclusive_minimum: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclusive_maximum: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multiple_of: Option<Number>,
+
+    // Strings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub xml: Option<Xml>,
+
+    /// Specification extensions and any keyword not modelled above.
+    #[serde(flatten)]
+    pub extensions: IndexMap<String, JsonValue>,
+}
+
+/// Extension recording the smallest numeric value seen across samples.
+pub const OBSERVED_MINIMUM: &str = "x-observed-minimum";
+/// Extension recording the largest numeric value seen across samples.
+pub const OBSERVED_MAXIMUM: &str = "x-observed-maximum";
+
+/// `additionalProperties` is either a boolean or a schema.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AdditionalProperties {
+    Allowed(bool),
+    Schema(Box<Schema>),
+}
+
+/// How a property is represented in XML.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Xml {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attribute: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wrapped: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Discriminator {
+    pub property_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mapping: Option<IndexMap<String, String>>,
+}
+
+impl Schema {
+    /// A schema with only `type` set.
+    pub fn of_type(schema_type: &str) -> Self {
+        Self {
+            schema_type: Some(schema_type.to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// A `$ref` to another schema, e.g. `#/components/schemas/User`.
+    pub fn reference(reference: &str) -> Self {
+        Self {
+            reference: Some(reference.to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// The schema observed for a JSON `null` sample: nullable, with no type.
+    pub fn null() -> Self {
+        Self {
+            nullable: Some(true),
+            ..Self::default()
+        }
+    }
+
+    /// True for a schema that places no constraint on the value besides
+    /// possibly allowing `null`.
+    pub fn is_unconstrained(&self) -> bool {
+        let mut schema = self.clone();
+        schema.nullable = None;
+        schema == Self::default()
+    }
+
+    /// Merges the schema of another sample into this one. Object properties are
+    /// unioned, `required` keeps only keys present in both, a `null` sample makes
+    /// the schema nullable, and conflicting types become a `oneOf`.
+    pub fn merge(self, other: Schema) -> Schema {
+        let nullable = self.nullable == Some(true) || other.nullable == Some(true);
+
+        let mut merged = if self.is_unconstrained() && self.nullable.is_some() {
+            // A null sample carries no shape information
+            other
+        } else if other.is_unconstrained() && other.nullable.is_some() {
+            self
+        } else if self.is_unconstrained() || other.is_unconstrained() {
+            Schema::default()
+        } else if let Some(variants) = self.one_of.clone().filter(|_| self.schema_type.is_none()) {
+            Self::merge_into_variants(variants, other)
+        } else if let Some(variants) = other.one_of.clone().filter(|_| other.schema_type.is_none()) {
+            Self::merge_into_variants(variants, self)
+        } else if self.is_compatible(&other) {
+            self.merge_same_type(other)
+        } else {
+            Schema {
+                one_of: Some(vec![self, other]),
+                ..Schema::default()
+            }
+        };
+
+        if nullable {
+            merged.nullable = Some(true);
+            if let Some(variants) = merged.one_of.as_mut() {
+                for variant in variants {
+                    variant.nullable = None;
+                }
+            }
+        }
+
+        merged
+    }
+
+    fn merge_into_variants(mut variants: Vec<Schema>, other: Schema) -> Schema {
+        if let Some(other_variants) = other.one_of.clone().filter(|_| other.schema_type.is_none()) {
+            return other_variants
+                .into_iter()
+                .fold(Schema { one_of: Some(variants), ..Schema::default() }, Schema::merge);
+        }
+
+        match variants
+            .iter()
+            .position(|variant| variant.is_compatible(&other))
+        {
+            Some(index) => {
+                let variant = std::mem::take(&mut variants[index]);
+                variants[index] = variant.merge(other);
+            }
+            None => variants.push(other),
+        }
+
+        Schema {
+            one_of: Some(variants),
+            ..Schema::default()
+        }
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(self.schema_type.as_deref(), Some("integer") | Some("number"))
+    }
+
+    /// Schemas of the same type (integers and numbers count as one) merge into
+    /// a single schema rather than a `oneOf`.
+    fn is_compatible(&self, other: &Schema) -> bool {
+        self.reference == other.reference
+            && (self.schema_type == other.schema_type || (self.is_numeric() && other.is_numeric()))
+    }
+
+    fn merge_same_type(mut self, other: Schema) -> Schema {
+        // Objects
+        if self.properties.is_some() || other.properties.is_some() {
+            let mut properties = self.properties.take().unwrap_or_default();
+            for (key, schema) in other.properties.unwrap_or_default() {
+                match properties.entry(key) {
+                    Entry::Occupied(mut entry) => {
+                        let existing = std::mem::take(entry.get_mut());
+                        entry.insert(existing.merge(schema));
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(schema);
+                    }
+                }
+            }
+            self.properties = Some(properties);
+        }
+
+        let other_required = other.required.unwrap_or_default();
+        let required: Vec<String> = self
+            .required
+            .take()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|key| other_required.contains(key))
+            .collect();
+        self.required = if required.is_empty() { None } else { Some(required) };
+
+        // Arrays
+        self.items = match (self.items.take(), other.items) {
+            (Some(left), Some(right)) => Some(Box::new(left.merge(*right))),
+            (left, right) => left.or(right),
+        };
+
+        // Numbers: an integer only widens to a number when a sample has a
+        // fractional part, and int32 widens to int64
+        if self.schema_type != other.schema_type {
+            self.schema_type = Some("number".to_string());
+            self.format = None;
+        } else if self.schema_type.as_deref() == Some("integer") && self.format != other.format {
+            self.format = match (self.format.as_deref(), other.format.as_deref()) {
+                (Some("int32" | "int64"), Some("int32" | "int64")) => Some("int64".to_string()),
+                _ => None,
+            };
+        } else if self.format != other.format {
+            // A format observed on only one side no longer holds for both samples
+            self.format = None;
+        }
+        self.merge_bound(&other.extensions, OBSERVED_MINIMUM, |left, right| left.min(right));
+        self.merge_bound(&other.extensions, OBSERVED_MAXIMUM, |left, right| left.max(right));
+
+        if self.enum_values != other.enum_values {
+            self.enum_values = None;
+        }
+        if self.example.is_none() {
+            self.example = other.example;
+        }
+
+        self
+    }
+
+    /// Combines an observed bound extension; it is only kept if both samples carry it.
+    fn merge_bound(&mut self, other: &IndexMap<String, JsonValue>, key: &str, pick: fn(f64, f64) -> f64) {
+        let left = self.extensions.get(key).and_then(JsonValue::as_f64);
+        let right = other.get(key).and_then(JsonValue::as_f64);
+
+        match (left, right) {
+            (Some(left_value), Some(right_value)) => {
+                if pick(left_value, right_value) == right_value {
+                    self.extensions.insert(key.to_string(), other[key].clone());
+                }
+            }
+            _ => {
+                self.extensions.shift_remove(key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(properties: &[(&str, Schema)], required: &[&str]) -> Schema {
+        Schema {
+            properties: Some(properties.iter().map(|(key, schema)| (key.to_string(), schema.clone())).collect()),
+            required: Some(required.iter().map(|key| key.to_string()).collect()),
+            ..Schema::of_type("object")
+        }
+    }
+
+    fn integer(format: &str) -> Schema {
+        Schema {
+            format: Some(format.to_string()),
+            ..Schema::of_type("integer")
+        }
+    }
+
+    #[test]
+    fn merge_unions_properties_and_intersects_required() {
+        let left = object(&[("id", integer("int32")), ("name", Schema::of_type("string"))], &["id", "name"]);
+        let right = object(&[("id", integer("int32")), ("email", Schema::of_type("string"))], &["id", "email"]);
+
+        let merged = left.merge(right);
+        let properties = merged.properties.unwrap();
+        assert_eq!(properties.keys().collect::<Vec<_>>(), ["id", "name", "email"]);
+        assert_eq!(merged.required, Some(vec!["id".to_string()]));
+    }
+
+    #[test]
+    fn merge_with_null_sample_is_nullable() {
+        let merged = Schema::of_type("string").merge(Schema::null());
+        assert_eq!(merged.schema_type.as_deref(), Some("string"));
+        assert_eq!(merged.nullable, Some(true));
+
+        let merged = Schema::null().merge(Schema::of_type("string"));
+        assert_eq!(merged.schema_type.as_deref(), Some("string"));
+        assert_eq!(merged.nullable, Some(true));
+    }
+
+    #[test]
+    fn merge_conflicting_types_is_one_of() {
+        let merged = Schema::of_type("string").merge(Schema::of_type("boolean"));
+        assert_eq!(merged.schema_type, None);
+        assert_eq!(merged.one_of, Some(vec![Schema::of_type("string"), Schema::of_type("boolean")]));
+
+        // A later sample of a known variant merges into it
+        let merged = merged.merge(Schema::of_type("boolean")).merge(Schema::of_type("array"));
+        assert_eq!(merged.one_of.unwrap().len(), 3);
+    }
+
+    #[test]
+    fn merge_widens_numbers() {
+        assert_eq!(integer("int32").merge(integer("int64")), integer("int64"));
+        assert_eq!(integer("int32").merge(Schema::of_type("number")), Schema::of_type("number"));
+    }
+
+    #[test]
+    fn merge_drops_formats_that_do_not_hold_for_both() {
+        let uuid = Schema {
+            format: Some("uuid".to_string()),
+            ..Schema::of_type("string")
+        };
+        assert_eq!(uuid.clone().merge(uuid.clone()).format.as_deref(), Some("uuid"));
+        assert_eq!(uuid.merge(Schema::of_type("string")).format, None);
+    }
+
+    #[test]
+    fn merge_array_items() {
+        let left = Schema {
+            items: Some(Box::new(object(&[("a", Schema::of_type("string"))], &["a"]))),
+            ..Schema::of_type("array")
+        };
+        let right = Schema {
+            items: Some(Box::new(object(&[("b", Schema::of_type("string"))], &["b"]))),
+            ..Schema::of_type("array")
+        };
+
+        let items = left.merge(right).items.unwrap();
+        assert_eq!(items.properties.unwrap().len(), 2);
+        assert_eq!(items.required, None);
+    }
+}
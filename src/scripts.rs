@@ -0,0 +1,558 @@
+//! Runs Postman pre-request and test scripts in an embedded JavaScript engine.
+//!
+//! The `pm` API is implemented in JavaScript (`pm.js`) on top of a state
+//! object that is handed to each script as JSON and read back when it
+//! finishes, so changes to variables and to the request carry over. Only
+//! `console` output, `pm.sendRequest` and `replaceIn` call back into Rust.
+//! Scripts imported from Hurl also get the helpers in `hurl.js`.
+
+use anyhow::{anyhow, Result};
+use boa_engine::{js_string, Context, JsArgs, JsResult, JsValue, NativeFunction, Source};
+use boa_gc::{Finalize, Trace};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::sync::{mpsc, OnceLock};
+use std::time::Instant;
+
+use crate::collection::{FullRequest, Header, Url};
+use crate::dynamic::DynamicVariables;
+use crate::runner::HarEntry;
+use crate::variables::Variables;
+
+const PRELUDE: &str = include_str!("pm.js");
+
+/// Helpers for the asserts and captures of Hurl entries (see `hurl.rs`).
+const HURL_HELPERS: &str = include_str!("hurl.js");
+
+/// Loops in a script stop with an error after this many iterations.
+const LOOP_ITERATION_LIMIT: u64 = 10_000_000;
+
+/// Everything a script can read or change.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptState {
+    /// `prerequest` or `test`
+    pub event_name: String,
+    pub request_name: String,
+    /// Zero-based
+    pub iteration: usize,
+    pub iteration_count: usize,
+    pub variables: Variables,
+    pub request: ScriptRequest,
+    pub response: Option<ScriptResponse>,
+    #[serde(default)]
+    pub tests: Vec<TestResult>,
+}
+
+/// The request as scripts see it, before variables are resolved.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScriptRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<ScriptHeader>,
+    /// The raw body, if the request has one
+    pub body: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScriptHeader {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptResponse {
+    pub code: u16,
+    pub status: String,
+    pub headers: Vec<ScriptHeader>,
+    pub body: String,
+    /// Milliseconds
+    pub response_time: f64,
+}
+
+impl ScriptRequest {
+    pub fn from_request(request: &FullRequest) -> Self {
+        let headers = request
+            .header
+            .iter()
+            .flatten()
+            .filter(|header| !header.disabled.unwrap_or(false))
+            .map(|header| ScriptHeader {
+                key: header.key.clone(),
+                value: header.value().to_string(),
+            })
+            .collect();
+
+        Self {
+            method: request.method.clone(),
+            url: request.url.raw(),
+            headers,
+            body: request
+                .body
+                .as_ref()
+                .filter(|body| body.mode == "raw")
+                .and_then(|body| body.raw.clone()),
+        }
+    }
+
+    /// Writes the changes a script made, compared to `original`, back to the request.
+    pub fn apply(&self, original: &ScriptRequest, request: &mut FullRequest) {
+        if self.method != original.method {
+            request.method = self.method.clone();
+        }
+
+        if self.url != original.url {
+            match &mut request.url {
+                Url::String(url) => *url = self.url.clone(),
+                Url::Object(url) => {
+                    // The new raw URL carries its own query string
+                    url.raw = Some(self.url.clone());
+                    url.query = None;
+                }
+            }
+        }
+
+        if self.headers != original.headers {
+            let previous = request.header.take().unwrap_or_default();
+            request.header = Some(
+                self.headers
+                    .iter()
+                    .map(|header| {
+                        // Keep the description and other details of headers the script kept
+                        let kept = previous.iter().find(|previous| previous.key == header.key);
+                        Header {
+                            key: header.key.clone(),
+                            value: Some(JsonValue::String(header.value.clone())),
+                            disabled: None,
+                            description: kept.and_then(|kept| kept.description.clone()),
+                            header_type: kept.and_then(|kept| kept.header_type.clone()),
+                            extra: kept.map(|kept| kept.extra.clone()).unwrap_or_default(),
+                        }
+                    })
+                    .collect(),
+            );
+        }
+
+        if self.body != original.body {
+            if let Some(body) = request.body.as_mut() {
+                body.raw = self.body.clone();
+            }
+        }
+    }
+}
+
+impl ScriptResponse {
+    pub fn from_entry(entry: &HarEntry) -> Self {
+        Self {
+            code: entry.response.status,
+            status: entry.response.status_text.clone(),
+            headers: entry
+                .response
+                .headers
+                .iter()
+                .map(|header| ScriptHeader {
+                    key: header.name.clone(),
+                    value: header.value.clone(),
+                })
+                .collect(),
+            body: entry.response.content.text.clone(),
+            response_time: entry.time,
+        }
+    }
+}
+
+/// The outcome of a `pm.test` call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestResult {
+    pub name: String,
+    /// The assertion that failed, if any
+    pub error: Option<String>,
+    #[serde(default)]
+    pub skipped: bool,
+}
+
+/// Runs one script, generating dynamic variables for `replaceIn` with
+/// `dynamic`. Changes the script made before failing are kept.
+pub fn run(source: &str, state: &mut ScriptState, dynamic: &DynamicVariables) -> Result<()> {
+    let mut context = Context::default();
+    context
+        .runtime_limits_mut()
+        .set_loop_iteration_limit(LOOP_ITERATION_LIMIT);
+    context
+        .register_global_callable(js_string!("__print"), 1, NativeFunction::from_fn_ptr(print))
+        .map_err(|error| anyhow!("{}", error))?;
+    context
+        .register_global_callable(js_string!("__sendRequest"), 1, NativeFunction::from_fn_ptr(send_request))
+        .map_err(|error| anyhow!("{}", error))?;
+
+    let replace_in = NativeFunction::from_copy_closure_with_captures(
+        replace_in,
+        ReplaceInCaptures {
+            dynamic: dynamic.clone(),
+        },
+    );
+    context
+        .register_global_callable(js_string!("__replaceIn"), 2, replace_in)
+        .map_err(|error| anyhow!("{}", error))?;
+
+    let setup = format!("const __state = {};\n{}", serde_json::to_string(state)?, PRELUDE);
+    context
+        .eval(Source::from_bytes(&setup))
+        .map_err(|error| anyhow!("Failed to set up script sandbox: {}", error))?;
+    if source.contains("__hurl.") {
+        context
+            .eval(Source::from_bytes(HURL_HELPERS))
+            .map_err(|error| anyhow!("Failed to set up Hurl helpers: {}", error))?;
+    }
+
+    // Like Postman's sandbox, the script runs in a function of its own, so it
+    // can declare names the prelude uses and `return` early
+    let wrapped = format!("(function () {{\n{}\n}})();", source);
+    let outcome = context.eval(Source::from_bytes(&wrapped));
+
+    let finished = context
+        .eval(Source::from_bytes("__finish()"))
+        .and_then(|value| value.to_string(&mut context))
+        .map_err(|error| anyhow!("Failed to read script state: {}", error))?;
+    *state = serde_json::from_str(&finished.to_std_string_escaped())?;
+
+    outcome.map(|_| ()).map_err(|error| anyhow!("{}", error))
+}
+
+/// The dynamic variable generator used by `replaceIn`.
+#[derive(Trace, Finalize)]
+struct ReplaceInCaptures {
+    // Holds no JavaScript values
+    #[unsafe_ignore_trace]
+    dynamic: DynamicVariables,
+}
+
+/// Backs `replaceIn`: takes the template and the variable scopes as JSON.
+fn replace_in(_: &JsValue, args: &[JsValue], captures: &ReplaceInCaptures, context: &mut Context) -> JsResult<JsValue> {
+    let template = args.get_or_undefined(0).to_string(context)?.to_std_string_escaped();
+    let scopes = args.get_or_undefined(1).to_string(context)?.to_std_string_escaped();
+    let variables: Variables = serde_json::from_str(&scopes).unwrap_or_default();
+    Ok(JsValue::from(js_string!(variables.resolve(&template, &captures.dynamic))))
+}
+
+fn print(_: &JsValue, args: &[JsValue], context: &mut Context) -> JsResult<JsValue> {
+    let message = args.get_or_undefined(0).to_string(context)?;
+    println!("    {}", message.to_std_string_escaped());
+    Ok(JsValue::undefined())
+}
+
+/// Backs `pm.sendRequest`: takes the request as JSON and returns
+/// `{"error": ..., "response": ...}` as JSON.
+fn send_request(_: &JsValue, args: &[JsValue], context: &mut Context) -> JsResult<JsValue> {
+    let request = args.get_or_undefined(0).to_string(context)?.to_std_string_escaped();
+
+    let (reply, response) = mpsc::channel();
+    let result = requests()
+        .send((request, reply))
+        .map_err(|_| anyhow!("The request thread has stopped"))
+        .and_then(|_| response.recv().map_err(|_| anyhow!("The request thread has stopped"))?);
+    let result = match result {
+        Ok(response) => json!({ "error": null, "response": response }),
+        Err(error) => json!({ "error": error.to_string(), "response": null }),
+    };
+
+    Ok(JsValue::from(js_string!(result.to_string())))
+}
+
+/// A `pm.sendRequest` request as JSON, and where to send its outcome.
+type SendJob = (String, mpsc::Sender<Result<ScriptResponse>>);
+
+/// The queue of the thread that sends `pm.sendRequest` requests. Scripts run
+/// inside the runner's async runtime, which cannot block on a request, so
+/// one thread with its own runtime and client sends them for every script.
+fn requests() -> &'static mpsc::Sender<SendJob> {
+    static REQUESTS: OnceLock<mpsc::Sender<SendJob>> = OnceLock::new();
+    REQUESTS.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<SendJob>();
+        std::thread::spawn(move || {
+            let setup = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(anyhow::Error::from)
+                .and_then(|runtime| {
+                    let client = reqwest::Client::builder().danger_accept_invalid_certs(true).build()?;
+                    Ok((runtime, client))
+                });
+            for (request, reply) in receiver {
+                let result = match &setup {
+                    Ok((runtime, client)) => runtime.block_on(send(client, &request)),
+                    Err(error) => Err(anyhow!("Failed to set up the request client: {}", error)),
+                };
+                let _ = reply.send(result);
+            }
+        });
+        sender
+    })
+}
+
+async fn send(client: &reqwest::Client, request: &str) -> Result<ScriptResponse> {
+    let request: JsonValue = serde_json::from_str(request)?;
+    let url = request
+        .get("url")
+        .and_then(JsonValue::as_str)
+        .ok_or_else(|| anyhow!("sendRequest needs a url"))?
+        .to_string();
+    let method = request
+        .get("method")
+        .and_then(JsonValue::as_str)
+        .unwrap_or("GET")
+        .to_uppercase();
+
+    let mut builder = client.request(reqwest::Method::from_bytes(method.as_bytes())?, &url);
+
+    // Headers are a list of `{key, value}` or an object
+    match request.get("header") {
+        Some(JsonValue::Array(headers)) => {
+            for header in headers {
+                if let (Some(key), Some(value)) = (header["key"].as_str(), header["value"].as_str()) {
+                    builder = builder.header(key, value);
+                }
+            }
+        }
+        Some(JsonValue::Object(headers)) => {
+            for (key, value) in headers {
+                builder = builder.header(key, value.as_str().unwrap_or_default());
+            }
+        }
+        _ => {}
+    }
+
+    if let Some(body) = request.get("body") {
+        match body["mode"].as_str() {
+            Some("raw") => {
+                builder = builder.body(body["raw"].as_str().unwrap_or_default().to_string());
+            }
+            Some("urlencoded") => {
+                let fields: Vec<(String, String)> = body["urlencoded"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|field| {
+                        (
+                            field["key"].as_str().unwrap_or_default().to_string(),
+                            field["value"].as_str().unwrap_or_default().to_string(),
+                        )
+                    })
+                    .collect();
+                builder = builder.form(&fields);
+            }
+            _ => {}
+        }
+    }
+
+    let started = Instant::now();
+    let response = builder.send().await?;
+    let code = response.status().as_u16();
+    let status = response.status().canonical_reason().unwrap_or("").to_string();
+    let headers = response
+        .headers()
+        .iter()
+        .map(|(name, value)| ScriptHeader {
+            key: name.to_string(),
+            value: value.to_str().unwrap_or("").to_string(),
+        })
+        .collect();
+    let body = response.text().await?;
+
+    Ok(ScriptResponse {
+        code,
+        status,
+        headers,
+        body,
+        response_time: started.elapsed().as_secs_f64() * 1000.0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    fn state(request: &FullRequest) -> ScriptState {
+        ScriptState {
+            event_name: "test".to_string(),
+            request_name: "Get user".to_string(),
+            iteration: 0,
+            iteration_count: 1,
+            variables: Variables::default(),
+            request: ScriptRequest::from_request(request),
+            response: None,
+            tests: Vec::new(),
+        }
+    }
+
+    fn request() -> FullRequest {
+        serde_json::from_value(json!({
+            "method": "PUT",
+            "url": "https://api.example.com/users/1?verbose",
+            "header": [{"key": "Accept", "value": "application/json", "description": "Wanted format"}],
+            "body": {"mode": "raw", "raw": "{}"}
+        }))
+        .unwrap()
+    }
+
+    fn scope(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    fn run_script(source: &str, state: &mut ScriptState) -> Result<()> {
+        run(source, state, &DynamicVariables::new(Some(1)))
+    }
+
+    #[test]
+    fn reads_and_sets_variables_in_each_scope() {
+        let mut state = state(&request());
+        state.variables.globals = scope(&[("host", "globals"), ("token", "old")]);
+        state.variables.collection = scope(&[("host", "collection")]);
+        state.variables.data = scope(&[("user", "ada")]);
+
+        let script = r#"
+            pm.environment.set("seen", pm.variables.get("host") + " " + pm.iterationData.get("user"));
+            pm.collectionVariables.set("count", 2);
+            pm.variables.set("filter", { active: true });
+            pm.globals.unset("token");
+            postman.setGlobalVariable("legacy", pm.globals.has("token"));
+            pm.environment.set("resolved", pm.variables.replaceIn("{{host}}/{{filter}}"));
+        "#;
+        run_script(script, &mut state).unwrap();
+
+        let variables = &state.variables;
+        assert_eq!(variables.environment["seen"], "collection ada");
+        assert_eq!(variables.collection["count"], "2");
+        assert_eq!(variables.local["filter"], r#"{"active":true}"#);
+        assert_eq!(variables.globals, scope(&[("host", "globals"), ("legacy", "false")]));
+        assert_eq!(variables.environment["resolved"], r#"collection/{"active":true}"#);
+    }
+
+    #[test]
+    fn applies_changes_to_the_request() {
+        let mut request = request();
+        let mut state = state(&request);
+        let original = state.request.clone();
+
+        let script = r#"
+            pm.request.method = "POST";
+            pm.request.url.query.upsert({ key: "page", value: 2 });
+            pm.request.headers.upsert({ key: "accept", value: "text/plain" });
+            pm.request.headers.add({ key: "X-Id", value: 7 });
+            pm.request.body.raw = JSON.stringify({ name: pm.request.url.getPath() });
+        "#;
+        run_script(script, &mut state).unwrap();
+        state.request.apply(&original, &mut request);
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.url.raw(), "https://api.example.com/users/1?verbose&page=2");
+        let headers = request.header.as_ref().unwrap();
+        assert_eq!(headers.len(), 2);
+        assert_eq!((headers[0].key.as_str(), headers[0].value()), ("Accept", "text/plain"));
+        assert!(headers[0].description.is_some());
+        assert_eq!((headers[1].key.as_str(), headers[1].value()), ("X-Id", "7"));
+        assert_eq!(request.body.unwrap().raw.unwrap(), r#"{"name":"/users/1"}"#);
+    }
+
+    #[test]
+    fn records_passing_failing_and_skipped_tests() {
+        let mut state = state(&request());
+        state.response = Some(ScriptResponse {
+            code: 201,
+            status: "Created".to_string(),
+            headers: vec![ScriptHeader {
+                key: "Content-Type".to_string(),
+                value: "application/json".to_string(),
+            }],
+            body: r#"{"user": {"id": 7, "tags": ["admin"]}}"#.to_string(),
+            response_time: 12.0,
+        });
+
+        let script = r#"
+            const body = pm.response.json();
+            pm.test("reads the JSON body", () => {
+                pm.expect(body.user.id).to.eql(7);
+                pm.expect(body.user.tags).to.include("admin");
+                pm.response.to.have.status(201);
+                pm.response.to.have.header("content-type", "application/json");
+            });
+            pm.test("wrong id", () => pm.expect(body.user.id, "user id").to.equal(8));
+            pm.test("wrong status", () => pm.response.to.be.ok);
+            pm.test("throws", () => { throw new TypeError("broken"); });
+            pm.test.skip("later");
+            tests["legacy"] = responseCode.code === 200;
+        "#;
+        run_script(script, &mut state).unwrap();
+
+        let results: Vec<(&str, Option<&str>, bool)> = state
+            .tests
+            .iter()
+            .map(|test| (test.name.as_str(), test.error.as_deref(), test.skipped))
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                ("reads the JSON body", None, false),
+                ("wrong id", Some("user id: expected 7 to equal 8"), false),
+                ("wrong status", Some("expected response to be ok but got 201"), false),
+                ("throws", Some("broken"), false),
+                ("later", None, true),
+                ("legacy", Some("test evaluated to false"), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn script_errors_keep_the_changes_made_before_them() {
+        let mut state = state(&request());
+        let error = run_script(r#"pm.variables.set("before", "yes"); null.field; pm.variables.set("after", "yes");"#, &mut state)
+            .unwrap_err();
+        assert!(error.to_string().contains("TypeError"), "{}", error);
+        assert_eq!(state.variables.local, scope(&[("before", "yes")]));
+
+        assert!(run_script("pm.test(", &mut state).is_err());
+        assert!(run_script(r#"pm.sendRequest({ method: "GET" }, (error) => { throw error; });"#, &mut state)
+            .unwrap_err()
+            .to_string()
+            .contains("sendRequest needs a url"));
+    }
+
+    #[test]
+    fn sends_requests_from_scripts() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for (count, stream) in listener.incoming().take(2).enumerate() {
+                let mut stream = stream.unwrap();
+                let mut buffer = [0; 4096];
+                let _ = stream.read(&mut buffer).unwrap();
+                let body = format!(r#"{{"count": {}}}"#, count + 1);
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        // Each script call goes through the same client
+        let mut state = state(&request());
+        let script = format!(
+            r#"
+            for (const method of ["GET", "POST"]) {{
+                pm.sendRequest({{ url: "http://{}/count", method, body: {{ mode: "raw", raw: "hi" }} }}, (error, response) => {{
+                    pm.variables.set(method, error ? error.message : response.code + " " + response.json().count);
+                }});
+            }}
+            "#,
+            address
+        );
+        run_script(&script, &mut state).unwrap();
+        assert_eq!(state.variables.local, scope(&[("GET", "200 1"), ("POST", "200 2")]));
+    }
+}
@@ -0,0 +1,305 @@
+//! Inference of security schemes from the Postman auth recorded by the runner
+//! and from credentials found in captured headers and query parameters.
+
+use indexmap::IndexMap;
+
+use crate::openapi::{OAuthFlow, OAuthFlows, SecurityScheme};
+use crate::runner::{HarAuth, HarEntry};
+
+/// Header names that carry API keys, compared case-insensitively.
+const API_KEY_HEADERS: &[&str] = &[
+    "x-api-key",
+    "api-key",
+    "apikey",
+    "x-apikey",
+    "x-api-token",
+    "x-auth-token",
+    "x-access-token",
+];
+
+/// Query parameter names that carry API keys or tokens.
+const API_KEY_QUERY_PARAMS: &[&str] = &["api_key", "apikey", "api-key", "access_token"];
+
+/// How a request was authenticated.
+pub struct Credential {
+    /// Preferred name of the scheme in `components.securitySchemes`
+    pub name: &'static str,
+    pub scheme: SecurityScheme,
+    pub scopes: Vec<String>,
+}
+
+/// Infers how a request was authenticated, or `None` if it was not.
+pub fn infer(entry: &HarEntry) -> Option<Credential> {
+    let from_auth = entry
+        .request
+        .auth
+        .as_ref()
+        .and_then(|auth| from_postman_auth(entry, auth));
+
+    // Other auth types (and `noauth`) may still send credentials set by hand
+    from_auth.or_else(|| from_headers(entry)).or_else(|| from_query(entry))
+}
+
+/// Whether the query parameter `name` of a request carries a credential: the
+/// API key of the scheme it was authenticated with, or a parameter that
+/// usually holds one.
+pub fn is_query_credential(entry: &HarEntry, name: &str) -> bool {
+    let covered = infer(entry).is_some_and(|credential| {
+        credential.scheme.location.as_deref() == Some("query")
+            && credential.scheme.name.as_deref().is_some_and(|key| key.eq_ignore_ascii_case(name))
+    });
+    covered || API_KEY_QUERY_PARAMS.contains(&name.to_lowercase().as_str())
+}
+
+/// Registers a scheme under `name`, reusing an identical one and otherwise
+/// adding a numeric suffix. Returns the name it was registered under.
+pub fn register(schemes: &mut IndexMap<String, SecurityScheme>, name: &str, scheme: SecurityScheme) -> String {
+    let mut candidate = name.to_string();
+    let mut suffix = 2;
+
+    loop {
+        match schemes.get_mut(&candidate) {
+            None => {
+                schemes.insert(candidate.clone(), scheme);
+                return candidate;
+            }
+            Some(existing) if is_same_scheme(existing, &scheme) => {
+                if let (Some(flows), Some(other)) = (existing.flows.as_mut(), scheme.flows) {
+                    flows.implicit = flows.implicit.take().or(other.implicit);
+                    flows.password = flows.password.take().or(other.password);
+                    flows.client_credentials = flows.client_credentials.take().or(other.client_credentials);
+                    flows.authorization_code = flows.authorization_code.take().or(other.authorization_code);
+                }
+                if existing.bearer_format.is_none() {
+                    existing.bearer_format = scheme.bearer_format;
+                }
+                return candidate;
+            }
+            Some(_) => {
+                candidate = format!("{}{}", name, suffix);
+                suffix += 1;
+            }
+        }
+    }
+}
+
+/// Schemes are the same if they only differ in details a single sample may
+/// not show, such as the bearer format or which OAuth 2.0 flows were used.
+fn is_same_scheme(left: &SecurityScheme, right: &SecurityScheme) -> bool {
+    left.scheme_type == right.scheme_type
+        && left.scheme == right.scheme
+        && left.name.as_deref().map(str::to_lowercase) == right.name.as_deref().map(str::to_lowercase)
+        && left.location == right.location
+}
+
+fn from_postman_auth(entry: &HarEntry, auth: &HarAuth) -> Option<Credential> {
+    match auth.auth_type.as_str() {
+        "bearer" => Some(http("bearerAuth", "bearer", bearer_format(entry))),
+        "basic" => Some(http("basicAuth", "basic", None)),
+        "digest" => Some(http("digestAuth", "digest", None)),
+        "apikey" => Some(api_key(
+            auth.name.as_deref().unwrap_or("X-API-Key"),
+            auth.location.as_deref().unwrap_or("header"),
+        )),
+        "oauth2" => Some(oauth2(entry, auth)),
+        _ => None,
+    }
+}
+
+fn from_headers(entry: &HarEntry) -> Option<Credential> {
+    for header in &entry.request.headers {
+        let name = header.name.to_lowercase();
+        if name == "authorization" {
+            let scheme = header.value.split_whitespace().next().unwrap_or_default().to_lowercase();
+            return Some(match scheme.as_str() {
+                "bearer" => http("bearerAuth", "bearer", bearer_format(entry)),
+                "basic" => http("basicAuth", "basic", None),
+                "digest" => http("digestAuth", "digest", None),
+                // Custom schemes such as `Token abc` are API keys in the header
+                _ => api_key(&header.name, "header"),
+            });
+        }
+        if API_KEY_HEADERS.contains(&name.as_str()) {
+            return Some(api_key(&header.name, "header"));
+        }
+    }
+
+    None
+}
+
+fn from_query(entry: &HarEntry) -> Option<Credential> {
+    entry
+        .request
+        .query_string
+        .iter()
+        .find(|param| API_KEY_QUERY_PARAMS.contains(&param.name.to_lowercase().as_str()))
+        .map(|param| api_key(&param.name, "query"))
+}
+
+fn http(name: &'static str, scheme: &str, bearer_format: Option<String>) -> Credential {
+    Credential {
+        name,
+        scheme: SecurityScheme {
+            scheme_type: "http".to_string(),
+            description: None,
+            name: None,
+            location: None,
+            scheme: Some(scheme.to_string()),
+            bearer_format,
+            flows: None,
+        },
+        scopes: Vec::new(),
+    }
+}
+
+fn api_key(name: &str, location: &str) -> Credential {
+    Credential {
+        name: "apiKeyAuth",
+        scheme: SecurityScheme {
+            scheme_type: "apiKey".to_string(),
+            description: None,
+            name: Some(name.to_string()),
+            location: Some(location.to_string()),
+            scheme: None,
+            bearer_format: None,
+            flows: None,
+        },
+        scopes: Vec::new(),
+    }
+}
+
+fn oauth2(entry: &HarEntry, auth: &HarAuth) -> Credential {
+    let scopes = auth.scopes.clone().unwrap_or_default();
+    let flow = |authorization_url: Option<&String>, token_url: Option<&String>| OAuthFlow {
+        authorization_url: authorization_url.cloned(),
+        token_url: token_url.cloned(),
+        refresh_url: None,
+        scopes: scopes.iter().map(|scope| (scope.clone(), String::new())).collect(),
+    };
+
+    let authorization_url = auth.authorization_url.as_ref().filter(|url| !url.is_empty());
+    let token_url = auth.token_url.as_ref().filter(|url| !url.is_empty());
+    let mut flows = OAuthFlows::default();
+    match auth.grant_type.as_deref() {
+        Some("client_credentials") if token_url.is_some() => {
+            flows.client_credentials = Some(flow(None, token_url));
+        }
+        Some("password") if token_url.is_some() => {
+            flows.password = Some(flow(None, token_url));
+        }
+        Some("implicit") if authorization_url.is_some() => {
+            flows.implicit = Some(flow(authorization_url, None));
+        }
+        _ if authorization_url.is_some() && token_url.is_some() => {
+            flows.authorization_code = Some(flow(authorization_url, token_url));
+        }
+        // Without the URLs a flow requires, all that is known is the bearer token
+        _ => return http("bearerAuth", "bearer", bearer_format(entry)),
+    }
+
+    Credential {
+        name: "oauth2",
+        scheme: SecurityScheme {
+            scheme_type: "oauth2".to_string(),
+            description: None,
+            name: None,
+            location: None,
+            scheme: None,
+            bearer_format: None,
+            flows: Some(flows),
+        },
+        scopes,
+    }
+}
+
+/// `JWT` if the bearer token sent with the request looks like one.
+fn bearer_format(entry: &HarEntry) -> Option<String> {
+    if let Some(format) = entry.request.auth.as_ref().and_then(|auth| auth.bearer_format.clone()) {
+        return Some(format);
+    }
+
+    let token = entry
+        .request
+        .headers
+        .iter()
+        .find(|header| header.name.eq_ignore_ascii_case("authorization"))?
+        .value
+        .split_whitespace()
+        .nth(1)?;
+    token_format(token)
+}
+
+/// `JWT` if `token` looks like one.
+pub fn token_format(token: &str) -> Option<String> {
+    let parts: Vec<&str> = token.split('.').collect();
+    let is_jwt = parts.len() == 3
+        && parts[..2].iter().all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '=')
+        });
+
+    if is_jwt {
+        Some("JWT".to_string())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value as JsonValue};
+
+    fn entry(auth: Option<JsonValue>, query: &[(&str, &str)]) -> HarEntry {
+        let query: Vec<JsonValue> = query.iter().map(|(name, value)| json!({ "name": name, "value": value })).collect();
+        serde_json::from_value(json!({
+            "startedDateTime": "2024-01-01T00:00:00Z",
+            "time": 0.0,
+            "request": {
+                "method": "GET",
+                "url": "https://example.com/items",
+                "httpVersion": "HTTP/1.1",
+                "headers": [],
+                "queryString": query,
+                "headersSize": -1,
+                "bodySize": 0,
+                "_auth": auth,
+            },
+            "response": {
+                "status": 200,
+                "statusText": "OK",
+                "httpVersion": "HTTP/1.1",
+                "headers": [],
+                "content": { "size": 0, "mimeType": "", "text": "" },
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": 0,
+            },
+            "cache": {},
+            "timings": { "send": 0.0, "wait": 0.0, "receive": 0.0 },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn recognizes_query_credentials() {
+        let keyed = entry(Some(json!({ "type": "apikey", "name": "sig", "in": "query" })), &[("sig", "secret"), ("page", "1")]);
+        assert!(is_query_credential(&keyed, "sig"));
+        assert!(is_query_credential(&keyed, "SIG"));
+        assert!(!is_query_credential(&keyed, "page"));
+
+        let plain = entry(None, &[("access_token", "secret"), ("sig", "x")]);
+        assert!(is_query_credential(&plain, "access_token"));
+        assert!(is_query_credential(&plain, "API_KEY"));
+        assert!(!is_query_credential(&plain, "sig"));
+    }
+
+    #[test]
+    fn recognizes_jwts() {
+        assert_eq!(token_format("eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiIxIn0.c2ln").as_deref(), Some("JWT"));
+        assert_eq!(token_format("opaque-token"), None);
+        assert_eq!(token_format("a..c"), None);
+    }
+}
@@ -0,0 +1,173 @@
+//! Detection of the OpenAPI `format` of observed string values.
+//!
+//! Besides the formats defined by OpenAPI and JSON Schema, two common ID
+//! shapes are recognised: `ulid` and `objectid` (24-digit hex MongoDB IDs).
+//! Merging samples drops the format as soon as one value does not match.
+
+use regex::Regex;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::OnceLock;
+
+/// Returns the format of a string value, or `None` if it has no recognisable shape.
+pub fn detect(value: &str) -> Option<&'static str> {
+    if value.is_empty() || value.trim() != value {
+        return None;
+    }
+
+    if is_uuid(value) {
+        Some("uuid")
+    } else if is_ulid(value) {
+        Some("ulid")
+    } else if is_object_id(value) {
+        Some("objectid")
+    } else if chrono::DateTime::parse_from_rfc3339(value).is_ok() {
+        Some("date-time")
+    } else if chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok() && value.len() == 10 {
+        Some("date")
+    } else if is_time(value) {
+        Some("time")
+    } else if is_email(value) {
+        Some("email")
+    } else if value.parse::<Ipv4Addr>().is_ok() {
+        Some("ipv4")
+    } else if value.contains(':') && value.parse::<Ipv6Addr>().is_ok() {
+        Some("ipv6")
+    } else if is_uri(value) {
+        Some("uri")
+    } else if is_hostname(value) {
+        Some("hostname")
+    } else if is_base64(value) {
+        Some("byte")
+    } else {
+        None
+    }
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("invalid format pattern"))
+}
+
+fn is_uuid(value: &str) -> bool {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    regex(
+        &PATTERN,
+        r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$",
+    )
+    .is_match(value)
+}
+
+fn is_ulid(value: &str) -> bool {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    // Crockford base32; the first character is at most 7 so the value fits 128 bits
+    regex(&PATTERN, r"^[0-7][0-9A-HJKMNP-TV-Z]{25}$").is_match(value)
+        && value.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+fn is_object_id(value: &str) -> bool {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    regex(&PATTERN, r"^[0-9a-f]{24}$").is_match(value)
+}
+
+fn is_time(value: &str) -> bool {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    regex(
+        &PATTERN,
+        r"^([01]\d|2[0-3]):[0-5]\d:([0-5]\d|60)(\.\d+)?([zZ]|[+-]([01]\d|2[0-3]):[0-5]\d)?$",
+    )
+    .is_match(value)
+}
+
+fn is_email(value: &str) -> bool {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    regex(
+        &PATTERN,
+        r"^[A-Za-z0-9.!#$%&'*+/=?^_`{|}~-]+@[A-Za-z0-9](?:[A-Za-z0-9-]{0,61}[A-Za-z0-9])?(?:\.[A-Za-z0-9](?:[A-Za-z0-9-]{0,61}[A-Za-z0-9])?)+$",
+    )
+    .is_match(value)
+}
+
+fn is_uri(value: &str) -> bool {
+    match url::Url::parse(value) {
+        // Reject things like "note:1" that merely look like a scheme
+        Ok(url) => url.has_host() || matches!(url.scheme(), "mailto" | "urn" | "tel" | "data"),
+        Err(_) => false,
+    }
+}
+
+/// The top-level domains a hostname may end in. Any dotted word matches the
+/// hostname syntax, so only common domains count: file names (`report.pdf`)
+/// and dotted names (`john.doe`) are not hostnames. Country codes that are
+/// also common file extensions, such as `md`, `rs` and `sh`, are left out.
+const TOP_LEVEL_DOMAINS: &[&str] = &[
+    "com", "org", "net", "edu", "gov", "mil", "int", "io", "dev", "app", "ai", "co", "info", "biz", "me", "tv", "cloud",
+    "tech", "online", "site", "xyz", "local", "internal", "localhost", "test", "example", "invalid", "us", "uk", "ca",
+    "au", "nz", "ie", "de", "fr", "nl", "be", "at", "ch", "it", "es", "pt", "se", "no", "dk", "fi", "eu", "cz", "hu",
+    "ro", "gr", "ru", "ua", "jp", "cn", "kr", "tw", "hk", "sg", "in", "id", "br", "ar", "mx", "cl", "za",
+];
+
+fn is_hostname(value: &str) -> bool {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let top_level = value.rsplit('.').next().unwrap_or_default().to_ascii_lowercase();
+    value.len() <= 253
+        && TOP_LEVEL_DOMAINS.contains(&top_level.as_str())
+        && regex(
+            &PATTERN,
+            r"^([A-Za-z0-9]([A-Za-z0-9-]{0,61}[A-Za-z0-9])?\.)+[A-Za-z]{2,63}$",
+        )
+        .is_match(value)
+}
+
+fn is_base64(value: &str) -> bool {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    // Short values and plain words are valid base64 too, so require some
+    // length and a mix of character classes before calling it encoded data
+    value.len() >= 16
+        && value.len().is_multiple_of(4)
+        && regex(&PATTERN, r"^[A-Za-z0-9+/]+={0,2}$").is_match(value)
+        && value.chars().any(|c| c.is_ascii_digit() || matches!(c, '+' | '/' | '='))
+        && value.chars().any(|c| c.is_ascii_uppercase())
+        && value.chars().any(|c| c.is_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_formats() {
+        let cases = [
+            ("550e8400-e29b-41d4-a716-446655440000", "uuid"),
+            ("01ARZ3NDEKTSV4RRFFQ69G5FAV", "ulid"),
+            ("507f1f77bcf86cd799439011", "objectid"),
+            ("2024-01-15T10:30:00Z", "date-time"),
+            ("2024-01-15T10:30:00.123+02:00", "date-time"),
+            ("2024-01-15", "date"),
+            ("10:30:00", "time"),
+            ("alice@example.com", "email"),
+            ("192.168.0.1", "ipv4"),
+            ("2001:db8::1", "ipv6"),
+            ("https://example.com/users?page=2", "uri"),
+            ("api.example.com", "hostname"),
+            ("Shop.Example.CO.UK", "hostname"),
+            ("db.internal", "hostname"),
+            ("SGVsbG8gV29ybGQhIDEyMw==", "byte"),
+        ];
+        for (value, format) in cases {
+            assert_eq!(detect(value), Some(format), "{}", value);
+        }
+    }
+
+    #[test]
+    fn plain_strings_have_no_format() {
+        for value in ["", "hello", "Hello World", " 2024-01-15", "note:1", "2024-13-45", "25:00:00", "abcdefghijklmnop"] {
+            assert_eq!(detect(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn dotted_names_are_not_hostnames() {
+        for value in ["report.pdf", "avatar.png", "README.md", "john.doe", "main.rs", "setup.sh", "archive.tar.gz", "v1.2"] {
+            assert_eq!(detect(value), None, "{}", value);
+        }
+    }
+}
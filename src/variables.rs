@@ -0,0 +1,247 @@
+//! Variables available to a collection run, kept per scope so scripts can
+//! read and change each scope separately, and the resolver that replaces
+//! `{{name}}` placeholders with their values.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::dynamic::DynamicVariables;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Variables {
+    /// Set by scripts through `pm.variables`; lasts for the run
+    pub local: HashMap<String, String>,
+    /// The current iteration's data row
+    pub data: HashMap<String, String>,
+    pub environment: HashMap<String, String>,
+    pub collection: HashMap<String, String>,
+    pub globals: HashMap<String, String>,
+}
+
+/// A placeholder that was left as it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Unresolved {
+    /// No scope defines the variable
+    Missing(String),
+    /// The variable's value refers back to itself through the listed variables
+    Cycle(Vec<String>),
+}
+
+impl fmt::Display for Unresolved {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unresolved::Missing(name) => write!(f, "{{{{{}}}}}", name),
+            Unresolved::Cycle(names) => {
+                let names: Vec<String> = names.iter().map(|name| format!("{{{{{}}}}}", name)).collect();
+                write!(f, "{} (cycle)", names.join(" -> "))
+            }
+        }
+    }
+}
+
+impl Variables {
+    /// Looks a variable up from the narrowest scope to the widest: local,
+    /// data, environment, collection, then globals.
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.local
+            .get(key)
+            .or_else(|| self.data.get(key))
+            .or_else(|| self.environment.get(key))
+            .or_else(|| self.collection.get(key))
+            .or_else(|| self.globals.get(key))
+    }
+
+    /// Replaces `{{name}}` placeholders in `text`, including those inside
+    /// variable values and inside other placeholders' names
+    /// (`{{url_{{env}}}}`). Dynamic variables such as `{{$guid}}` are
+    /// generated with `dynamic`. Unknown and cyclic placeholders are left as
+    /// they are.
+    pub fn resolve(&self, text: &str, dynamic: &DynamicVariables) -> String {
+        Resolver::new(self, Some(dynamic)).text(text)
+    }
+
+    /// Lists the placeholders in `texts` that [`Variables::resolve`] would
+    /// leave as they are, without generating any dynamic variables.
+    pub fn unresolved<'t>(&self, texts: impl IntoIterator<Item = &'t str>) -> Vec<Unresolved> {
+        let mut resolver = Resolver::new(self, None);
+        for text in texts {
+            resolver.text(text);
+        }
+        resolver.unresolved
+    }
+}
+
+struct Resolver<'a> {
+    variables: &'a Variables,
+    /// Generates dynamic variables; without it their names are only checked
+    dynamic: Option<&'a DynamicVariables>,
+    /// Variables whose values are being resolved, to detect cycles
+    stack: Vec<String>,
+    unresolved: Vec<Unresolved>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(variables: &'a Variables, dynamic: Option<&'a DynamicVariables>) -> Self {
+        Self {
+            variables,
+            dynamic,
+            stack: Vec::new(),
+            unresolved: Vec::new(),
+        }
+    }
+
+    fn text(&mut self, text: &str) -> String {
+        let mut output = String::new();
+        let mut rest = text;
+
+        while let Some(mut start) = rest.find("{{") {
+            // In `{{{name}}}` the placeholder is the innermost pair of braces
+            while rest[start + 2..].starts_with('{') {
+                start += 1;
+            }
+            output.push_str(&rest[..start]);
+            match self.placeholder(&rest[start + 2..]) {
+                Some((value, remaining)) => {
+                    output.push_str(&value);
+                    rest = remaining;
+                }
+                None => {
+                    // Never closed, so not a placeholder
+                    output.push_str(&rest[start..]);
+                    rest = "";
+                }
+            }
+        }
+
+        output.push_str(rest);
+        output
+    }
+
+    /// Reads a placeholder's name up to its closing `}}`, resolving the
+    /// placeholders nested in it first. Returns the placeholder's value and
+    /// the text after it, or `None` if it is never closed.
+    fn placeholder<'t>(&mut self, text: &'t str) -> Option<(String, &'t str)> {
+        let mut name = String::new();
+        let mut rest = text;
+
+        loop {
+            let close = rest.find("}}")?;
+            match rest.find("{{") {
+                Some(open) if open < close => {
+                    name.push_str(&rest[..open]);
+                    let (value, remaining) = self.placeholder(&rest[open + 2..])?;
+                    name.push_str(&value);
+                    rest = remaining;
+                }
+                _ => {
+                    name.push_str(&rest[..close]);
+                    return Some((self.value(&name), &rest[close + 2..]));
+                }
+            }
+        }
+    }
+
+    fn value(&mut self, name: &str) -> String {
+        let placeholder = format!("{{{{{}}}}}", name);
+
+        if let Some(value) = self.variables.get(name) {
+            if let Some(position) = self.stack.iter().position(|resolving| resolving == name) {
+                let mut cycle = self.stack[position..].to_vec();
+                cycle.push(name.to_string());
+                self.report(Unresolved::Cycle(cycle));
+                return placeholder;
+            }
+
+            self.stack.push(name.to_string());
+            let value = self.text(value);
+            self.stack.pop();
+            return value;
+        }
+
+        match self.dynamic {
+            Some(dynamic) => {
+                if let Some(value) = dynamic.generate(name) {
+                    return value;
+                }
+            }
+            None if DynamicVariables::is_defined(name) => return placeholder,
+            None => {}
+        }
+
+        // A name with an unresolved placeholder in it was already reported
+        if !name.contains("{{") {
+            self.report(Unresolved::Missing(name.to_string()));
+        }
+        placeholder
+    }
+
+    fn report(&mut self, unresolved: Unresolved) {
+        if !self.unresolved.contains(&unresolved) {
+            self.unresolved.push(unresolved);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    fn resolve(variables: &Variables, text: &str) -> String {
+        variables.resolve(text, &DynamicVariables::new(Some(1)))
+    }
+
+    #[test]
+    fn narrower_scopes_take_precedence() {
+        let variables = Variables {
+            local: scope(&[("a", "local")]),
+            data: scope(&[("a", "data"), ("b", "data")]),
+            environment: scope(&[("b", "environment"), ("c", "environment")]),
+            collection: scope(&[("c", "collection"), ("d", "collection")]),
+            globals: scope(&[("d", "globals"), ("e", "globals")]),
+        };
+        assert_eq!(resolve(&variables, "{{a}} {{b}} {{c}} {{d}} {{e}}"), "local data environment collection globals");
+    }
+
+    #[test]
+    fn resolves_values_and_names_recursively() {
+        let variables = Variables {
+            environment: scope(&[
+                ("env", "staging"),
+                ("host", "{{url_{{env}}}}"),
+                ("url_staging", "https://staging.example.com"),
+            ]),
+            ..Variables::default()
+        };
+        assert_eq!(resolve(&variables, "{{host}}/users"), "https://staging.example.com/users");
+        assert_eq!(resolve(&variables, "{{{env}}}"), "{staging}");
+    }
+
+    #[test]
+    fn leaves_missing_and_cyclic_placeholders() {
+        let variables = Variables {
+            collection: scope(&[("a", "{{b}}"), ("b", "{{a}}"), ("c", "ok")]),
+            ..Variables::default()
+        };
+        assert_eq!(resolve(&variables, "{{missing}} {{c}} {{unclosed"), "{{missing}} ok {{unclosed");
+        assert!(resolve(&variables, "{{a}}").contains("{{"));
+
+        let unresolved = variables.unresolved(["{{missing}}", "{{a}}", "{{$guid}}"]);
+        assert_eq!(unresolved[0], Unresolved::Missing("missing".to_string()));
+        assert!(matches!(&unresolved[1], Unresolved::Cycle(names) if names.contains(&"a".to_string())));
+        assert_eq!(unresolved.len(), 2);
+    }
+
+    #[test]
+    fn generates_dynamic_variables() {
+        let resolved = resolve(&Variables::default(), "{{$guid}}");
+        assert_eq!(resolved.len(), 36);
+        assert_eq!(resolve(&Variables::default(), "{{$guid}}"), resolved, "seeded values repeat");
+        assert_eq!(resolve(&Variables::default(), "{{$unknownDynamic}}"), "{{$unknownDynamic}}");
+    }
+}
@@ -0,0 +1,242 @@
+//! Schema inference for XML bodies.
+//!
+//! Elements with children or attributes become objects, repeated child
+//! elements become arrays and attributes become properties marked with
+//! `xml.attribute`. Text content is inferred like any other text value;
+//! next to attributes or children it becomes the `#text` property, a name
+//! no XML element or attribute can have.
+
+use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use crate::schema::{Schema, Xml};
+
+/// The property holding the text of an element that also has attributes or children.
+const TEXT_PROPERTY: &str = "#text";
+
+struct Element {
+    name: String,
+    prefix: Option<String>,
+    namespace: Option<String>,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+/// Infers the schema of an XML document; `scalar` infers the schema of a
+/// text value. The root element's name is kept in `xml.name`.
+pub fn infer(text: &str, scalar: &dyn Fn(&str) -> Schema) -> Result<Schema> {
+    let root = parse(text)?;
+    let mut schema = element_schema(&root, scalar);
+
+    let xml = schema.xml.get_or_insert_with(Xml::default);
+    xml.name = Some(root.name.clone());
+    xml.namespace = root.namespace.clone();
+    Ok(schema)
+}
+
+fn parse(text: &str) -> Result<Element> {
+    let mut reader = Reader::from_str(text);
+    reader.config_mut().trim_text(true);
+
+    let mut open: Vec<Element> = Vec::new();
+    loop {
+        let closed = match reader.read_event()? {
+            Event::Start(start) => {
+                open.push(element(&start)?);
+                None
+            }
+            Event::Empty(start) => Some(element(&start)?),
+            Event::End(_) => Some(open.pop().ok_or_else(|| anyhow!("Unexpected closing tag"))?),
+            Event::Text(text) => {
+                if let Some(current) = open.last_mut() {
+                    current.text.push_str(&text.unescape()?);
+                }
+                None
+            }
+            Event::CData(data) => {
+                if let Some(current) = open.last_mut() {
+                    current.text.push_str(&String::from_utf8_lossy(&data));
+                }
+                None
+            }
+            Event::Eof => return Err(anyhow!("XML document has no root element")),
+            _ => None,
+        };
+
+        if let Some(closed) = closed {
+            match open.last_mut() {
+                Some(parent) => parent.children.push(closed),
+                None => return Ok(closed),
+            }
+        }
+    }
+}
+
+fn element(start: &BytesStart) -> Result<Element> {
+    let name = start.name();
+    let prefix = name
+        .prefix()
+        .map(|prefix| String::from_utf8_lossy(prefix.as_ref()).into_owned());
+    let namespace_attribute = match &prefix {
+        Some(prefix) => format!("xmlns:{}", prefix),
+        None => "xmlns".to_string(),
+    };
+
+    let mut namespace = None;
+    let mut attributes = Vec::new();
+    for attribute in start.attributes() {
+        let attribute = attribute?;
+        let key = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+        let value = attribute.unescape_value()?.into_owned();
+
+        if key == namespace_attribute {
+            namespace = Some(value);
+        } else if key != "xmlns" && !key.starts_with("xmlns:") {
+            let local_name = String::from_utf8_lossy(attribute.key.local_name().as_ref()).into_owned();
+            attributes.push((local_name, value));
+        }
+    }
+
+    Ok(Element {
+        name: String::from_utf8_lossy(name.local_name().as_ref()).into_owned(),
+        prefix,
+        namespace,
+        attributes,
+        children: Vec::new(),
+        text: String::new(),
+    })
+}
+
+fn element_schema(element: &Element, scalar: &dyn Fn(&str) -> Schema) -> Schema {
+    if element.children.is_empty() && element.attributes.is_empty() {
+        return if element.text.is_empty() {
+            Schema::of_type("string")
+        } else {
+            scalar(&element.text)
+        };
+    }
+
+    let mut properties = IndexMap::new();
+    for (name, value) in &element.attributes {
+        let schema = Schema {
+            xml: Some(Xml {
+                attribute: Some(true),
+                ..Xml::default()
+            }),
+            ..scalar(value)
+        };
+        properties.insert(name.clone(), schema);
+    }
+
+    let mut children: IndexMap<&str, Vec<&Element>> = IndexMap::new();
+    for child in &element.children {
+        children.entry(child.name.as_str()).or_default().push(child);
+    }
+    for (name, group) in children {
+        let mut schema = group
+            .iter()
+            .map(|child| element_schema(child, scalar))
+            .reduce(Schema::merge)
+            .unwrap_or_default();
+        if let Some(prefix) = &group[0].prefix {
+            schema.xml.get_or_insert_with(Xml::default).prefix = Some(prefix.clone());
+        }
+
+        // Repeated elements are an unwrapped array
+        if group.len() > 1 {
+            schema = Schema {
+                items: Some(Box::new(schema)),
+                ..Schema::of_type("array")
+            };
+        }
+        properties.insert(name.to_string(), schema);
+    }
+
+    if !element.text.is_empty() {
+        properties.insert(TEXT_PROPERTY.to_string(), scalar(&element.text));
+    }
+
+    let required: Vec<String> = properties.keys().cloned().collect();
+    Schema {
+        properties: Some(properties),
+        required: Some(required),
+        ..Schema::of_type("object")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scalar(text: &str) -> Schema {
+        if text.parse::<i64>().is_ok() {
+            Schema::of_type("integer")
+        } else if text.parse::<f64>().is_ok() {
+            Schema::of_type("number")
+        } else {
+            Schema::of_type("string")
+        }
+    }
+
+    fn infer_json(text: &str) -> serde_json::Value {
+        serde_json::to_value(infer(text, &scalar).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn elements_become_objects_and_repeated_elements_arrays() {
+        let schema = infer_json("<order><id>7</id><item>a</item><item>b</item><note/></order>");
+        assert_eq!(
+            schema,
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "id": {"type": "integer"},
+                    "item": {"type": "array", "items": {"type": "string"}},
+                    "note": {"type": "string"}
+                },
+                "required": ["id", "item", "note"],
+                "xml": {"name": "order"}
+            })
+        );
+    }
+
+    #[test]
+    fn attributes_and_namespaces() {
+        let schema = infer_json(
+            r#"<feed xmlns="http://www.w3.org/2005/Atom" xmlns:m="urn:meta" lang="en"><m:count>2</m:count></feed>"#,
+        );
+        assert_eq!(schema["xml"], serde_json::json!({"name": "feed", "namespace": "http://www.w3.org/2005/Atom"}));
+        assert_eq!(schema["properties"]["lang"], serde_json::json!({"type": "string", "xml": {"attribute": true}}));
+        assert_eq!(schema["properties"]["count"], serde_json::json!({"type": "integer", "xml": {"prefix": "m"}}));
+    }
+
+    #[test]
+    fn keeps_text_next_to_attributes_and_children() {
+        let schema = infer_json(r#"<product><price currency="USD">9.99</price><name>Pen<![CDATA[ & ink]]></name></product>"#);
+        assert_eq!(
+            schema["properties"]["price"],
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "currency": {"type": "string", "xml": {"attribute": true}},
+                    "#text": {"type": "number"}
+                },
+                "required": ["currency", "#text"]
+            })
+        );
+        assert_eq!(schema["properties"]["name"], serde_json::json!({"type": "string"}));
+
+        let schema = infer_json("<p>Hello <b>world</b></p>");
+        assert_eq!(schema["properties"]["#text"], serde_json::json!({"type": "string"}));
+        assert_eq!(schema["properties"]["b"], serde_json::json!({"type": "string"}));
+    }
+
+    #[test]
+    fn rejects_documents_without_a_root_element() {
+        assert!(infer("", &scalar).is_err());
+        assert!(infer("<a></b>", &scalar).is_err());
+    }
+}